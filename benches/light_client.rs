@@ -0,0 +1,56 @@
+//! Benchmarks for Ethereum light client update verification.
+//!
+//! Run with `cargo bench --features ethereum-full --bench light_client`, or
+//! `cargo bench --features ethereum-full --bench light_client -- --test` to
+//! run each benchmark for a single iteration as a smoke test (as CI does).
+//! Enable `fast-verify` instead of `ethereum-full` to measure with the fastest
+//! available BLS backend.
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use nomic::ethereum::consensus::{relayer::Response, Bootstrap, LightClient, Network, Update};
+
+const NOW_SECONDS: u64 = 1727740110;
+
+fn fixtures() -> (Bootstrap, Vec<Update>, Update) {
+    let fixtures = include_str!("../src/ethereum/consensus/test_fixtures.json");
+    let (bootstrap, updates, finality_update): (
+        Response<Bootstrap>,
+        Vec<Response<Update>>,
+        Response<Update>,
+    ) = serde_json::from_str(fixtures).unwrap();
+
+    (
+        bootstrap.data,
+        updates.into_iter().map(|u| u.data).collect(),
+        finality_update.data,
+    )
+}
+
+fn update(c: &mut Criterion) {
+    let (bootstrap, updates, finality_update) = fixtures();
+    let client = LightClient::new(bootstrap, Network::ethereum_mainnet()).unwrap();
+
+    c.bench_function("full update", |b| {
+        b.iter_batched(
+            || (client.clone(), updates[0].clone()),
+            |(mut client, update)| client.update(update, NOW_SECONDS).unwrap(),
+            BatchSize::LargeInput,
+        )
+    });
+
+    let mut synced = client.clone();
+    for update in updates.iter().cloned() {
+        synced.update(update, NOW_SECONDS).unwrap();
+    }
+
+    c.bench_function("finality update", |b| {
+        b.iter_batched(
+            || (synced.clone(), finality_update.clone()),
+            |(mut client, update)| client.update(update, NOW_SECONDS).unwrap(),
+            BatchSize::LargeInput,
+        )
+    });
+}
+
+criterion_group!(benches, update);
+criterion_main!(benches);
@@ -0,0 +1,304 @@
+//! Consensus-layer primitives for the Ethereum light client which do not
+//! depend on the standard library: slot arithmetic, the fork schedule, SSZ
+//! hash tree roots and merkle branch verification.
+//!
+//! These are the parts of light client verification which can run in
+//! constrained environments (e.g. zkVM guests or embedded signers). The full
+//! light client, which additionally verifies sync committee signatures and
+//! keeps state, lives in `nomic::ethereum::consensus` and builds on this
+//! crate. Enable the default `std` feature to get [std::error::Error] impls.
+
+#![no_std]
+
+#[cfg(any(test, feature = "std"))]
+extern crate std;
+
+use core::fmt;
+use sha2::{Digest, Sha256};
+
+/// The number of members of a sync committee.
+pub const SYNC_COMMITTEE_SIZE: usize = 512;
+/// The number of slots in an epoch.
+pub const SLOTS_PER_EPOCH: u64 = 32;
+/// The number of epochs in a sync committee period.
+pub const EPOCHS_PER_SYNC_COMMITTEE_PERIOD: u64 = 256;
+/// The duration of a slot, in seconds.
+pub const SECONDS_PER_SLOT: u64 = 12;
+
+/// The epoch containing the given slot.
+pub const fn epoch_of_slot(slot: u64) -> u64 {
+    slot / SLOTS_PER_EPOCH
+}
+
+/// The sync committee period containing the given slot.
+pub const fn period_of_slot(slot: u64) -> u64 {
+    slot / slots_per_period()
+}
+
+/// The number of slots in a sync committee period.
+pub const fn slots_per_period() -> u64 {
+    SLOTS_PER_EPOCH * EPOCHS_PER_SYNC_COMMITTEE_PERIOD
+}
+
+/// A consensus-layer fork which the light client protocol is defined for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Fork {
+    Altair,
+    Bellatrix,
+    Capella,
+    Deneb,
+    Electra,
+}
+
+impl Fork {
+    /// Returns the fork active at the given epoch, given the activation
+    /// epochs of Altair, Bellatrix, Capella, Deneb and Electra in that order.
+    /// Epochs before Altair are reported as Altair, since the light client
+    /// protocol does not exist before it.
+    pub fn at_epoch(fork_epochs: [u64; 5], epoch: u64) -> Self {
+        let [_, bellatrix, capella, deneb, electra] = fork_epochs;
+        if epoch >= electra {
+            Fork::Electra
+        } else if epoch >= deneb {
+            Fork::Deneb
+        } else if epoch >= capella {
+            Fork::Capella
+        } else if epoch >= bellatrix {
+            Fork::Bellatrix
+        } else {
+            Fork::Altair
+        }
+    }
+
+    /// The generalized index of the finalized checkpoint root in this fork's
+    /// `BeaconState`.
+    pub const fn finalized_root_gindex(self) -> u64 {
+        match self {
+            Fork::Electra => 169,
+            _ => 105,
+        }
+    }
+
+    /// The generalized index of the current sync committee in this fork's
+    /// `BeaconState`.
+    pub const fn current_sync_committee_gindex(self) -> u64 {
+        match self {
+            Fork::Electra => 86,
+            _ => 54,
+        }
+    }
+
+    /// The generalized index of the next sync committee in this fork's
+    /// `BeaconState`.
+    pub const fn next_sync_committee_gindex(self) -> u64 {
+        match self {
+            Fork::Electra => 87,
+            _ => 55,
+        }
+    }
+}
+
+/// An error from verifying a merkle branch.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BranchError {
+    /// The generalized index was zero, which does not name a node.
+    ZeroIndex,
+    /// The branch length does not match the depth of the generalized index.
+    WrongLength {
+        len: usize,
+        depth: usize,
+        generalized_index: u64,
+    },
+}
+
+impl fmt::Display for BranchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BranchError::ZeroIndex => write!(f, "Generalized index must be positive"),
+            BranchError::WrongLength {
+                len,
+                depth,
+                generalized_index,
+            } => write!(
+                f,
+                "Branch has {} nodes, expected {} for generalized index {}",
+                len, depth, generalized_index
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for BranchError {}
+
+/// Verifies an SSZ merkle branch from `leaf` at `generalized_index` up to
+/// `root`. The branch is ordered from the leaf's sibling upwards.
+pub fn verify_merkle_branch(
+    leaf: &[u8; 32],
+    branch: &[[u8; 32]],
+    generalized_index: u64,
+    root: &[u8; 32],
+) -> Result<bool, BranchError> {
+    if generalized_index == 0 {
+        return Err(BranchError::ZeroIndex);
+    }
+    let depth = generalized_index.ilog2() as usize;
+    if branch.len() != depth {
+        return Err(BranchError::WrongLength {
+            len: branch.len(),
+            depth,
+            generalized_index,
+        });
+    }
+
+    let mut node = *leaf;
+    let mut index = generalized_index;
+    for sibling in branch {
+        node = if index & 1 == 1 {
+            hash_pair(sibling, &node)
+        } else {
+            hash_pair(&node, sibling)
+        };
+        index >>= 1;
+    }
+
+    Ok(&node == root)
+}
+
+/// A beacon block header, as hashed by SSZ.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BeaconBlockHeader {
+    pub slot: u64,
+    pub proposer_index: u64,
+    pub parent_root: [u8; 32],
+    pub state_root: [u8; 32],
+    pub body_root: [u8; 32],
+}
+
+impl BeaconBlockHeader {
+    /// The SSZ hash tree root of the header, i.e. the beacon block root.
+    pub fn hash_tree_root(&self) -> [u8; 32] {
+        let mut leaves = [[0; 32]; 8];
+        leaves[0][..8].copy_from_slice(&self.slot.to_le_bytes());
+        leaves[1][..8].copy_from_slice(&self.proposer_index.to_le_bytes());
+        leaves[2] = self.parent_root;
+        leaves[3] = self.state_root;
+        leaves[4] = self.body_root;
+        merkleize(&mut leaves)
+    }
+}
+
+/// The SSZ hash tree root of a sync committee with the given member pubkeys
+/// and aggregate pubkey.
+pub fn sync_committee_root(
+    pubkeys: &[[u8; 48]; SYNC_COMMITTEE_SIZE],
+    aggregate_pubkey: &[u8; 48],
+) -> [u8; 32] {
+    let mut leaves = [[0; 32]; SYNC_COMMITTEE_SIZE];
+    for (leaf, pubkey) in leaves.iter_mut().zip(pubkeys) {
+        *leaf = pubkey_root(pubkey);
+    }
+    let pubkeys_root = merkleize(&mut leaves);
+
+    hash_pair(&pubkeys_root, &pubkey_root(aggregate_pubkey))
+}
+
+/// The number of sync committee members which signed, given the sync
+/// aggregate's participation bits.
+pub fn participants(sync_committee_bits: &[u8]) -> usize {
+    sync_committee_bits
+        .iter()
+        .map(|byte| byte.count_ones() as usize)
+        .sum()
+}
+
+/// The hash tree root of a BLS pubkey, which packs into two chunks.
+fn pubkey_root(pubkey: &[u8; 48]) -> [u8; 32] {
+    let mut chunks = [[0; 32]; 2];
+    chunks[0].copy_from_slice(&pubkey[..32]);
+    chunks[1][..16].copy_from_slice(&pubkey[32..]);
+    hash_pair(&chunks[0], &chunks[1])
+}
+
+/// Merkleizes a power-of-two number of leaves in place, returning the root.
+fn merkleize(nodes: &mut [[u8; 32]]) -> [u8; 32] {
+    debug_assert!(nodes.len().is_power_of_two());
+    let mut len = nodes.len();
+    while len > 1 {
+        for i in 0..len / 2 {
+            nodes[i] = hash_pair(&nodes[2 * i], &nodes[2 * i + 1]);
+        }
+        len /= 2;
+    }
+    nodes[0]
+}
+
+fn hash_pair(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slot_conversions() {
+        assert_eq!(epoch_of_slot(63), 1);
+        assert_eq!(epoch_of_slot(64), 2);
+        assert_eq!(slots_per_period(), 8192);
+        assert_eq!(period_of_slot(8191), 0);
+        assert_eq!(period_of_slot(8192), 1);
+    }
+
+    #[test]
+    fn fork_schedule() {
+        let epochs = [74240, 144896, 194048, 269568, 364032];
+        assert_eq!(Fork::at_epoch(epochs, 0), Fork::Altair);
+        assert_eq!(Fork::at_epoch(epochs, 144896), Fork::Bellatrix);
+        assert_eq!(Fork::at_epoch(epochs, 269567), Fork::Capella);
+        assert_eq!(Fork::at_epoch(epochs, 269568), Fork::Deneb);
+        assert_eq!(Fork::at_epoch(epochs, u64::MAX), Fork::Electra);
+    }
+
+    #[test]
+    fn gindices() {
+        assert_eq!(Fork::Deneb.finalized_root_gindex().ilog2(), 6);
+        assert_eq!(Fork::Electra.finalized_root_gindex().ilog2(), 7);
+        assert_eq!(Fork::Capella.next_sync_committee_gindex(), 55);
+        assert_eq!(Fork::Electra.next_sync_committee_gindex(), 87);
+        assert_eq!(Fork::Altair.current_sync_committee_gindex(), 54);
+        assert_eq!(Fork::Electra.current_sync_committee_gindex(), 86);
+    }
+
+    #[test]
+    fn merkle_branch() {
+        let leaf = [1; 32];
+        let sibling = [2; 32];
+        let root = hash_pair(&sibling, &leaf);
+
+        assert!(verify_merkle_branch(&leaf, &[sibling], 3, &root).unwrap());
+        assert!(!verify_merkle_branch(&leaf, &[sibling], 2, &root).unwrap());
+        assert_eq!(
+            verify_merkle_branch(&leaf, &[sibling], 4, &root),
+            Err(BranchError::WrongLength {
+                len: 1,
+                depth: 2,
+                generalized_index: 4
+            })
+        );
+        assert_eq!(
+            verify_merkle_branch(&leaf, &[], 0, &root),
+            Err(BranchError::ZeroIndex)
+        );
+    }
+
+    #[test]
+    fn participant_count() {
+        assert_eq!(participants(&[0; 64]), 0);
+        assert_eq!(participants(&[0xff; 64]), SYNC_COMMITTEE_SIZE);
+        assert_eq!(participants(&[0b1010_0001, 0, 1]), 4);
+    }
+}
@@ -0,0 +1,111 @@
+use nomic_light_client_core::{
+    sync_committee_root, verify_merkle_branch, BeaconBlockHeader, BranchError, SYNC_COMMITTEE_SIZE,
+};
+use serde_json::Value;
+
+/// Mainnet light client data (a bootstrap, a batch of updates and a finality
+/// update), shared with the full light client's tests.
+const FIXTURES: &str = include_str!("../../src/ethereum/consensus/test_fixtures.json");
+
+fn bytes<const N: usize>(value: &Value) -> [u8; N] {
+    let hex = value.as_str().unwrap().trim_start_matches("0x");
+    hex::decode(hex).unwrap().try_into().unwrap()
+}
+
+fn branch(value: &Value) -> Vec<[u8; 32]> {
+    value.as_array().unwrap().iter().map(bytes).collect()
+}
+
+fn header(value: &Value) -> BeaconBlockHeader {
+    let beacon = &value["beacon"];
+    BeaconBlockHeader {
+        slot: beacon["slot"].as_str().unwrap().parse().unwrap(),
+        proposer_index: beacon["proposer_index"].as_str().unwrap().parse().unwrap(),
+        parent_root: bytes(&beacon["parent_root"]),
+        state_root: bytes(&beacon["state_root"]),
+        body_root: bytes(&beacon["body_root"]),
+    }
+}
+
+fn committee_root(value: &Value) -> [u8; 32] {
+    let pubkeys: Vec<[u8; 48]> = value["pubkeys"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(bytes)
+        .collect();
+    let pubkeys: [[u8; 48]; SYNC_COMMITTEE_SIZE] = pubkeys.try_into().unwrap();
+    sync_committee_root(&pubkeys, &bytes(&value["aggregate_pubkey"]))
+}
+
+fn fixtures() -> (Value, Vec<Value>, Value) {
+    let fixtures: Value = serde_json::from_str(FIXTURES).unwrap();
+    let bootstrap = fixtures[0]["data"].clone();
+    let updates = fixtures[1]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|update| update["data"].clone())
+        .collect();
+    let finality_update = fixtures[2]["data"].clone();
+    (bootstrap, updates, finality_update)
+}
+
+#[test]
+fn bootstrap_committee_branch() {
+    let (bootstrap, _, _) = fixtures();
+    let header = header(&bootstrap["header"]);
+
+    let leaf = committee_root(&bootstrap["current_sync_committee"]);
+    let branch = branch(&bootstrap["current_sync_committee_branch"]);
+    assert!(verify_merkle_branch(&leaf, &branch, 54, &header.state_root).unwrap());
+    assert!(!verify_merkle_branch(&leaf, &branch, 55, &header.state_root).unwrap());
+}
+
+#[test]
+fn update_branches() {
+    let (_, updates, _) = fixtures();
+    assert!(!updates.is_empty());
+
+    for update in updates {
+        let attested = header(&update["attested_header"]);
+
+        let finalized = header(&update["finalized_header"]).hash_tree_root();
+        let finality_branch = branch(&update["finality_branch"]);
+        assert!(
+            verify_merkle_branch(&finalized, &finality_branch, 105, &attested.state_root).unwrap()
+        );
+
+        let committee = committee_root(&update["next_sync_committee"]);
+        let committee_branch = branch(&update["next_sync_committee_branch"]);
+        assert!(
+            verify_merkle_branch(&committee, &committee_branch, 55, &attested.state_root).unwrap()
+        );
+    }
+}
+
+#[test]
+fn finality_update_branch() {
+    let (_, _, finality_update) = fixtures();
+    let attested = header(&finality_update["attested_header"]);
+    let finalized = header(&finality_update["finalized_header"]);
+    let branch = branch(&finality_update["finality_branch"]);
+
+    let leaf = finalized.hash_tree_root();
+    assert!(verify_merkle_branch(&leaf, &branch, 105, &attested.state_root).unwrap());
+
+    let mut forged = finalized.clone();
+    forged.slot += 1;
+    let leaf = forged.hash_tree_root();
+    assert!(!verify_merkle_branch(&leaf, &branch, 105, &attested.state_root).unwrap());
+
+    // the Electra finalized root gindex is one level deeper
+    assert_eq!(
+        verify_merkle_branch(&leaf, &branch, 169, &attested.state_root),
+        Err(BranchError::WrongLength {
+            len: 6,
+            depth: 7,
+            generalized_index: 169
+        })
+    );
+}
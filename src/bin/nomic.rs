@@ -31,7 +31,10 @@ use nomic::bitcoin::adapter::Adapter;
 use nomic::bitcoin::matches_bitcoin_network;
 use nomic::bitcoin::signatory::SignatorySet;
 use nomic::bitcoin::Nbtc;
-use nomic::bitcoin::{relayer::Relayer, signer::Signer};
+use nomic::bitcoin::{
+    relayer::{Relayer, StatusTracker},
+    signer::Signer,
+};
 use nomic::error::Result;
 #[cfg(feature = "ethereum")]
 use nomic::ethereum;
@@ -1351,10 +1354,133 @@ pub struct RelayerCmd {
     #[clap(short = 'P', long)]
     rpc_pass: Option<String>,
 
+    /// Whether to relay Bitcoin headers.
+    #[clap(long, default_value_t = true, action = clap::ArgAction::Set)]
+    relay_headers_enabled: bool,
+
+    /// Whether to relay deposits and recovery transactions.
+    #[clap(long, default_value_t = true, action = clap::ArgAction::Set)]
+    relay_deposits_enabled: bool,
+
+    /// Whether to broadcast checkpoint and emergency disbursal transactions and
+    /// relay checkpoint confirmations.
+    #[clap(long, default_value_t = true, action = clap::ArgAction::Set)]
+    relay_checkpoints_enabled: bool,
+
+    /// Whether to verify deposit merkle proofs against the sidechain's block
+    /// headers before relaying them.
+    #[clap(long)]
+    verify_deposit_proofs: bool,
+
+    /// Whether to record deposits in a write-ahead log while relaying them, so
+    /// interrupted relays are resumed on restart.
+    #[clap(long)]
+    deposit_wal: bool,
+
+    /// Whether to log checkpoint transactions instead of broadcasting them.
+    #[clap(long)]
+    dry_run_checkpoints: bool,
+
+    /// A directory in which to touch a heartbeat file for each relay loop on
+    /// every successful iteration, for external liveness checks.
+    #[clap(long)]
+    heartbeat_dir: Option<PathBuf>,
+
+    /// An address at which to serve a JSON status report (at `/status`) for
+    /// monitoring, e.g. `127.0.0.1:8998`.
+    #[clap(long)]
+    status_addr: Option<std::net::SocketAddr>,
+
+    /// The Bitcoin network the full node is on (`bitcoin`, `testnet`, `signet`
+    /// or `regtest`). Defaults to the network the binary was built for.
+    #[clap(long)]
+    bitcoin_network: Option<bitcoin::Network>,
+
     #[clap(flatten)]
     config: nomic::network::Config,
 }
 
+/// A loop run by the `relayer` command.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RelayLoop {
+    Headers,
+    Deposits,
+    RecoveryTxs,
+    Checkpoints,
+    CheckpointConfs,
+    EmergencyDisbursals,
+    Status,
+}
+
+/// The loops to start for the given relayer flags. Recovery transactions are
+/// relayed alongside deposits, and checkpoint confirmations and emergency
+/// disbursals alongside checkpoints.
+fn relay_loops(
+    headers_enabled: bool,
+    deposits_enabled: bool,
+    checkpoints_enabled: bool,
+    status_enabled: bool,
+) -> Vec<RelayLoop> {
+    let mut loops = vec![];
+    if headers_enabled {
+        loops.push(RelayLoop::Headers);
+    }
+    if deposits_enabled {
+        loops.extend([RelayLoop::Deposits, RelayLoop::RecoveryTxs]);
+    }
+    if checkpoints_enabled {
+        loops.extend([
+            RelayLoop::Checkpoints,
+            RelayLoop::CheckpointConfs,
+            RelayLoop::EmergencyDisbursals,
+        ]);
+    }
+    if status_enabled {
+        loops.push(RelayLoop::Status);
+    }
+    loops
+}
+
+/// Runs the given relay loop if it is enabled, otherwise never resolves so
+/// that the remaining loops keep running.
+async fn run_if_enabled<F>(
+    enabled: bool,
+    relay_loop: F,
+    shutdown: tokio::sync::watch::Receiver<bool>,
+) -> Result<()>
+where
+    F: std::future::Future<Output = Result<()>>,
+{
+    if enabled {
+        relay_loop.await
+    } else {
+        wait_for_shutdown(shutdown).await;
+        Ok(())
+    }
+}
+
+/// Runs a relay loop which has no shutdown handling of its own, dropping it
+/// once shutdown is signaled.
+async fn run_until_shutdown<F>(
+    enabled: bool,
+    relay_loop: F,
+    shutdown: tokio::sync::watch::Receiver<bool>,
+) -> Result<()>
+where
+    F: std::future::Future<Output = Result<()>>,
+{
+    tokio::select! {
+        res = run_if_enabled(enabled, relay_loop, shutdown.clone()) => res,
+        _ = wait_for_shutdown(shutdown) => Ok(()),
+    }
+}
+
+async fn wait_for_shutdown(mut shutdown: tokio::sync::watch::Receiver<bool>) {
+    if shutdown.wait_for(|stop| *stop).await.is_err() {
+        futures::future::pending().await
+    }
+}
+
 impl RelayerCmd {
     /// Builds Bitcoin RPC client.
     async fn btc_client(&self) -> Result<BtcClient> {
@@ -1373,47 +1499,119 @@ impl RelayerCmd {
 
     /// Runs the `relayer` command.
     async fn run(&self) -> Result<()> {
-        let create_relayer = async || {
-            let btc_client = self.btc_client().await.unwrap();
+        let loops = relay_loops(
+            self.relay_headers_enabled,
+            self.relay_deposits_enabled,
+            self.relay_checkpoints_enabled,
+            self.status_addr.is_some(),
+        );
+        let enabled = |relay_loop| loops.contains(&relay_loop);
 
-            Relayer::new(btc_client, self.config.node.as_ref().unwrap().to_string())
-        };
+        let status_tracker = StatusTracker::default();
+        let create_relayer = async || -> Result<Relayer> {
+            let btc_client = self.btc_client().await?;
 
-        let mut relayer = create_relayer().await;
-        let headers = relayer.start_header_relay();
+            let mut relayer =
+                Relayer::new(btc_client, self.config.node.as_ref().unwrap().to_string())
+                    .with_status_tracker(status_tracker.clone());
+            if let Some(network) = self.bitcoin_network {
+                relayer = relayer.with_network(network);
+            }
+            Ok(match &self.heartbeat_dir {
+                Some(dir) => relayer.with_heartbeat_dir(dir),
+                None => relayer,
+            })
+        };
 
         let relayer_dir_path = self.config.home_expect()?.join("relayer");
         if !relayer_dir_path.exists() {
             std::fs::create_dir(&relayer_dir_path)?;
         }
 
-        let relayer = create_relayer().await;
-        let deposits = relayer.start_deposit_relay(relayer_dir_path.clone(), 60 * 60 * 12);
+        let (shutdown_tx, shutdown) = tokio::sync::watch::channel(false);
+        tokio::spawn(async move {
+            if tokio::signal::ctrl_c().await.is_ok() {
+                log::info!("Received interrupt, shutting down relayer...");
+                shutdown_tx.send(true).ok();
+                // keep the sender alive until the process exits
+                futures::future::pending::<()>().await;
+            }
+        });
+
+        // the header and deposit relayers check their full node against the
+        // sidechain before starting, only if they are enabled
+        let relayer = create_relayer()
+            .await?
+            .with_shutdown_signal(shutdown.clone())
+            .with_header_height_dir(&relayer_dir_path)?;
+        let headers = async move { relayer.connect().await?.start_header_relay().await };
+
+        let relayer = create_relayer()
+            .await?
+            .with_local_proof_verification(self.verify_deposit_proofs)
+            .with_deposit_wal(self.deposit_wal)
+            .with_shutdown_signal(shutdown.clone());
+        let deposit_store_path = relayer_dir_path.clone();
+        let deposits = async move {
+            relayer
+                .connect()
+                .await?
+                .start_deposit_relay(deposit_store_path, 60 * 60 * 12)
+                .await
+        };
 
-        let mut relayer = create_relayer().await;
-        let recovery_txs = relayer.start_recovery_tx_relay(relayer_dir_path);
+        let mut relayer = create_relayer().await?;
+        let recovery_txs = relayer.start_recovery_tx_relay(relayer_dir_path.clone());
 
-        let mut relayer = create_relayer().await;
+        let mut relayer = create_relayer()
+            .await?
+            .with_dry_run(self.dry_run_checkpoints)
+            .with_settled_checkpoints_dir(&relayer_dir_path)?
+            .with_shutdown_signal(shutdown.clone());
         let checkpoints = relayer.start_checkpoint_relay();
 
-        let mut relayer = create_relayer().await;
+        let mut relayer = create_relayer().await?;
         let checkpoint_confs = relayer.start_checkpoint_conf_relay();
 
-        let mut relayer = create_relayer().await;
+        let mut relayer = create_relayer().await?;
         let emdis = relayer.start_emergency_disbursal_transaction_relay();
 
+        let relayer = create_relayer().await?;
+        let status = async {
+            if let Some(addr) = self.status_addr {
+                relayer.serve_status(addr).await;
+            }
+            Ok::<_, nomic::error::Error>(())
+        };
+
         let relaunch = relaunch_on_migrate(&self.config);
 
         futures::try_join!(
-            headers,
-            deposits,
-            recovery_txs,
-            checkpoints,
-            checkpoint_confs,
-            emdis,
-            relaunch
-        )
-        .unwrap();
+            run_if_enabled(enabled(RelayLoop::Headers), headers, shutdown.clone()),
+            run_if_enabled(enabled(RelayLoop::Deposits), deposits, shutdown.clone()),
+            run_until_shutdown(
+                enabled(RelayLoop::RecoveryTxs),
+                recovery_txs,
+                shutdown.clone()
+            ),
+            run_if_enabled(
+                enabled(RelayLoop::Checkpoints),
+                checkpoints,
+                shutdown.clone()
+            ),
+            run_until_shutdown(
+                enabled(RelayLoop::CheckpointConfs),
+                checkpoint_confs,
+                shutdown.clone()
+            ),
+            run_until_shutdown(
+                enabled(RelayLoop::EmergencyDisbursals),
+                emdis,
+                shutdown.clone()
+            ),
+            run_until_shutdown(enabled(RelayLoop::Status), status, shutdown.clone()),
+            run_until_shutdown(true, relaunch, shutdown)
+        )?;
 
         Ok(())
     }
@@ -3037,3 +3235,30 @@ pub fn main() {
         std::process::exit(1);
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relay_loop_selection() {
+        assert_eq!(
+            relay_loops(false, true, false, false),
+            vec![RelayLoop::Deposits, RelayLoop::RecoveryTxs]
+        );
+        assert_eq!(
+            relay_loops(true, false, false, true),
+            vec![RelayLoop::Headers, RelayLoop::Status]
+        );
+        assert_eq!(
+            relay_loops(false, false, true, false),
+            vec![
+                RelayLoop::Checkpoints,
+                RelayLoop::CheckpointConfs,
+                RelayLoop::EmergencyDisbursals
+            ]
+        );
+        assert_eq!(relay_loops(true, true, true, false).len(), 6);
+        assert!(relay_loops(false, false, false, false).is_empty());
+    }
+}
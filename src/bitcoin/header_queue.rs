@@ -14,7 +14,9 @@ use orga::Error as OrgaError;
 use orga::Result as OrgaResult;
 
 const MAX_LENGTH: u64 = 24_192; // ~6 months
-const MAX_RELAY: u64 = 250;
+/// The maximum number of headers which can be relayed in a single call to
+/// [HeaderQueue::add].
+pub const MAX_RELAY: u64 = 250;
 const MAX_TIME_INCREASE: u32 = 2 * 60 * 60;
 const RETARGET_INTERVAL: u32 = 2016;
 const TARGET_SPACING: u32 = 10 * 60;
@@ -213,6 +213,22 @@ pub fn calc_bridge_deposit_fee(amount: u64) -> u64 {
     amount / 100
 }
 
+/// The Bitcoin address for deposits to `dest` under the given signatory set,
+/// as displayed by wallets.
+pub fn deposit_address(
+    dest: &Dest,
+    sigset: &SignatorySet,
+    network: bitcoin::Network,
+) -> Result<bitcoin::Address> {
+    let script = sigset.output_script(&dest.commitment_bytes()?, SIGSET_THRESHOLD)?;
+    bitcoin::Address::from_script(&script, network).ok_or_else(|| {
+        Error::Address(format!(
+            "Deposit script can not be represented as a {} address",
+            network
+        ))
+    })
+}
+
 /// The main structure where Bitcoin bridge state is held.
 ///
 /// This structure is the main entry point for interacting with the Bitcoin
@@ -1292,6 +1308,44 @@ mod tests {
         *,
     };
 
+    #[test]
+    fn deposit_address_display() {
+        let secret = bitcoin::secp256k1::SecretKey::from_slice(&[1; 32]).unwrap();
+        let pubkey = bitcoin::secp256k1::PublicKey::from_secret_key(&Secp256k1::new(), &secret);
+        let sigset = SignatorySet {
+            index: 0,
+            create_time: 0,
+            present_vp: 100,
+            possible_vp: 100,
+            signatories: vec![signatory::Signatory {
+                voting_power: 100,
+                pubkey: pubkey.into(),
+            }],
+        };
+        let dest = Dest::RewardPool;
+        let script = sigset
+            .output_script(&dest.commitment_bytes().unwrap(), SIGSET_THRESHOLD)
+            .unwrap();
+
+        let addr = deposit_address(&dest, &sigset, bitcoin::Network::Bitcoin).unwrap();
+        let addr_str = addr.to_string();
+        // P2WSH addresses are bech32 encodings of a 32-byte witness program
+        assert!(addr_str.starts_with("bc1q"));
+        assert_eq!(addr_str.len(), 62);
+        assert_eq!(addr.script_pubkey(), script);
+        assert_eq!(addr_str.parse::<bitcoin::Address>().unwrap(), addr);
+
+        let addr = deposit_address(&dest, &sigset, bitcoin::Network::Testnet).unwrap();
+        assert!(addr.to_string().starts_with("tb1q"));
+        assert_eq!(addr.script_pubkey(), script);
+
+        let other_dest = Dest::NativeAccount {
+            address: Default::default(),
+        };
+        let other_addr = deposit_address(&other_dest, &sigset, bitcoin::Network::Testnet).unwrap();
+        assert_ne!(other_addr, addr);
+    }
+
     #[serial_test::serial]
     #[test]
     fn relay_height_validity() {
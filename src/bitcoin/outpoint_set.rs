@@ -1,4 +1,4 @@
-use orga::{collections::Map, orga, Result};
+use orga::{collections::Map, encoding::LengthVec, orga, Result};
 
 /// A Bitcoin transaction ID and output index.
 pub type Outpoint = ([u8; 32], u32);
@@ -40,6 +40,16 @@ impl OutpointSet {
         self.outpoints.contains_key(outpoint)
     }
 
+    /// Check which of the given outpoints the set contains, in order, so
+    /// clients can check many outpoints with a single query.
+    #[query]
+    pub fn contains_batch(&self, outpoints: LengthVec<u16, Outpoint>) -> Result<Vec<bool>> {
+        outpoints
+            .iter()
+            .map(|outpoint| self.outpoints.contains_key(*outpoint))
+            .collect()
+    }
+
     /// Insert an outpoint into the set, to be pruned at the given expiration
     /// timestamp.
     pub fn insert(&mut self, outpoint: Outpoint, expiration: u64) -> Result<()> {
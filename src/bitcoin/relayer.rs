@@ -4,28 +4,39 @@ use super::SIGSET_THRESHOLD;
 use crate::app::Dest;
 use crate::app_client;
 use crate::bitcoin::deposit_index::{Deposit, DepositIndex};
-use crate::bitcoin::{adapter::Adapter, header_queue::WrappedHeader};
+use crate::bitcoin::outpoint_set::Outpoint;
+use crate::bitcoin::{
+    adapter::Adapter,
+    header_queue::{WorkHeader, WrappedHeader, MAX_RELAY},
+};
 use crate::error::Error;
 use crate::error::Result;
 use crate::orga::encoding::Encode;
 use crate::utils::time_now;
 use bitcoin::consensus::{Decodable, Encodable};
-use bitcoin::Txid;
+use bitcoin::util::merkleblock::PartialMerkleTree;
 use bitcoin::{hashes::Hash, Block, BlockHash, Transaction};
+use bitcoin::{TxMerkleNode, Txid};
 use bitcoincore_rpc_async::{json::GetBlockHeaderResult, Client as BitcoinRpcClient, RpcApi};
+use futures::stream::{self, StreamExt, TryStreamExt};
 use log::{debug, error, info, warn};
-use orga::encoding::Decode;
+use orga::encoding::{Decode, LengthVec};
 use orga::macros::build_call;
+use rand::Rng;
 use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
-use std::collections::{BTreeMap, HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::convert::Infallible;
 use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use tokio::join;
-use tokio::sync::mpsc::Receiver;
-use tokio::sync::{Mutex, RwLock, RwLockReadGuard};
+use tokio::sync::mpsc::{error::TrySendError, Receiver, Sender};
+use tokio::sync::{watch, Mutex, RwLock, RwLockReadGuard, Semaphore};
 use warp::reject;
-use warp::reply::Json;
+use warp::reply::{Json, WithStatus};
 
 pub fn warp_reply_json<T>(val: T) -> Json
 where
@@ -34,31 +45,452 @@ where
     warp::reply::json(&val)
 }
 
+/// The JSON body returned by the relayer's HTTP API when a request fails.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ErrorResponse {
+    pub error: String,
+    pub code: u16,
+}
+
+/// Converts a rejection into a JSON error response with the appropriate HTTP
+/// status, for use with [warp::Filter::recover].
+pub async fn handle_rejection(
+    rejection: warp::Rejection,
+) -> std::result::Result<WithStatus<Json>, Infallible> {
+    let (status, error) = if let Some(err) = rejection.find::<Error>() {
+        (err.status_code(), err.to_string())
+    } else if rejection.is_not_found() {
+        (warp::http::StatusCode::NOT_FOUND, "Not found".to_string())
+    } else {
+        (
+            crate::error::rejection_status_code(&rejection),
+            format!("{:?}", rejection),
+        )
+    };
+
+    let body = ErrorResponse {
+        error,
+        code: status.as_u16(),
+    };
+    Ok(warp::reply::with_status(warp::reply::json(&body), status))
+}
+
 const HEADER_BATCH_SIZE: usize = 250;
+/// The largest configurable header batch, matching the number of headers a
+/// Bitcoin node returns for a single `getheaders` request.
+const MAX_HEADER_BATCH_SIZE: usize = 2000;
+const HEADER_HEIGHT_FILE: &str = "last-header-height";
+const MIN_RELAY_RATE_SAMPLES: usize = 3;
+const MAX_RELAY_RATE_SAMPLES: usize = 20;
+const MAX_LATENCY_SAMPLES: usize = 100;
+/// The maximum number of blocks fetched from the full node at once.
+const BLOCK_FETCH_CONCURRENCY: usize = 16;
+/// The default number of recent blocks kept in memory to avoid re-fetching
+/// them from the full node.
+const DEFAULT_BLOCK_CACHE_SIZE: usize = 64;
+/// The default number of deposits in a block relayed concurrently.
+const DEFAULT_MAX_INFLIGHT_DEPOSITS: usize = 8;
+/// The number of confirmations a block needs to be cached, so that cached
+/// blocks are never stale after a reorg.
+const BLOCK_CACHE_MIN_CONFS: i32 = 6;
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(2);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+/// The number of confirmations required before relaying a deposit when no
+/// confirmation policy is set.
+const DEFAULT_DEPOSIT_CONFIRMATIONS: u32 = 1;
+const DEPOSIT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// How long the header relay waits before checking again when the full node's
+/// tip is below the sidechain's, i.e. the full node is still syncing.
+const FULL_NODE_SYNC_DELAY: Duration = Duration::from_secs(30);
+/// The default minimum number of recent blocks scanned for deposits each time
+/// the sidechain's tip moves. This covers the deposit timeout (5 days, about
+/// 720 blocks) with margin, since deposits in older blocks can no longer be
+/// relayed.
+const DEFAULT_MIN_RESCAN_WINDOW: usize = 1100;
+/// The maximum number of blocks scanned for deposits at once, bounding the
+/// scan when the sidechain catches up on many headers at once, unless the
+/// minimum window is set higher.
+const MAX_RESCAN_WINDOW: usize = 2016;
+/// The default number of confirmations after which checkpoint transactions are
+/// no longer rebroadcast.
+const DEFAULT_CHECKPOINT_SETTLE_CONFS: u32 = 6;
+const DEPOSIT_SCAN_INTERVAL: Duration = Duration::from_secs(2);
+const MAX_DEPOSIT_SCAN_INTERVAL: Duration = Duration::from_secs(60);
+/// The number of recently completed checkpoints considered when looking up
+/// checkpoint transactions.
+const RECENT_CHECKPOINTS: u32 = 100;
 
 #[derive(Serialize, Deserialize)]
 pub struct DepositsQuery {
     pub receiver: String,
 }
 
+/// A policy check applied to deposit destinations before relaying.
+pub type DestValidator = Box<dyn Fn(&Dest) -> bool + Send + Sync>;
+
+/// A policy returning the number of confirmations required for deposits to
+/// the sigset with the given index.
+pub type ConfirmationPolicy = Box<dyn Fn(u32) -> u32 + Send + Sync>;
+
 pub struct Relayer {
-    btc_client: Arc<RwLock<BitcoinRpcClient>>,
+    btc_client: BtcClients,
     app_client_addr: String,
+    network: bitcoin::Network,
 
     scripts: Arc<Mutex<Option<WatchedScriptStore>>>,
     deposit_buffer: Option<u64>,
+
+    header_height_path: Option<PathBuf>,
+    heartbeat_dir: Option<PathBuf>,
+    last_relayed_header_height: Option<u32>,
+    max_headers_per_add: usize,
+    header_batch_size: usize,
+    header_relay_samples: VecDeque<(Instant, usize)>,
+    marked_processed: HashSet<(Txid, u32)>,
+    dest_validator: Option<DestValidator>,
+    confirmation_policy: Option<ConfirmationPolicy>,
+    checkpoint_deposit_confs: u32,
+    verify_proofs: bool,
+
+    events: Option<Sender<RelayerEvent>>,
+    rpc_latencies: std::sync::Mutex<HashMap<&'static str, LatencySamples>>,
+    relayed_outpoints: Option<std::sync::Mutex<RelayedOutpoints>>,
+    last_scan: std::sync::Mutex<Option<((u32, u32), usize)>>,
+    retry_backoff: Backoff,
+    min_rescan_window: usize,
+    checkpoint_broadcasts: CheckpointBroadcasts,
+    use_deposit_wal: bool,
+    deposit_wal: Option<std::sync::Mutex<DepositWal>>,
+    shutdown: Option<watch::Receiver<bool>>,
+    status: StatusTracker,
+    block_cache: std::sync::Mutex<BlockCache>,
+    inflight_deposits: Semaphore,
+    dry_run: bool,
 }
 
 impl Relayer {
+    /// Creates a relayer for the given full node and sidechain node. No
+    /// requests are made until the relayer is used; finish configuring it with
+    /// [Relayer::connect] to first check that the full node agrees with the
+    /// sidechain.
     pub fn new(btc_client: BitcoinRpcClient, app_client_addr: String) -> Self {
         Relayer {
-            btc_client: Arc::new(RwLock::new(btc_client)),
+            btc_client: BtcClients::new(btc_client),
             app_client_addr,
+            network: super::NETWORK,
             scripts: Arc::new(Mutex::new(None)),
             deposit_buffer: None,
+            header_height_path: None,
+            heartbeat_dir: None,
+            last_relayed_header_height: None,
+            max_headers_per_add: MAX_RELAY as usize,
+            header_batch_size: HEADER_BATCH_SIZE,
+            header_relay_samples: VecDeque::new(),
+            marked_processed: HashSet::new(),
+            dest_validator: None,
+            confirmation_policy: None,
+            checkpoint_deposit_confs: 0,
+            verify_proofs: false,
+            events: None,
+            rpc_latencies: Default::default(),
+            relayed_outpoints: None,
+            last_scan: Default::default(),
+            retry_backoff: Backoff::new(RETRY_BASE_DELAY, MAX_RETRY_DELAY, 2.0),
+            min_rescan_window: DEFAULT_MIN_RESCAN_WINDOW,
+            checkpoint_broadcasts: CheckpointBroadcasts::new(DEFAULT_CHECKPOINT_SETTLE_CONFS),
+            use_deposit_wal: false,
+            deposit_wal: None,
+            shutdown: None,
+            status: StatusTracker::default(),
+            block_cache: std::sync::Mutex::new(BlockCache::new(DEFAULT_BLOCK_CACHE_SIZE)),
+            inflight_deposits: Semaphore::new(DEFAULT_MAX_INFLIGHT_DEPOSITS),
+            dry_run: false,
+        }
+    }
+
+    /// Sets the maximum number of headers submitted in a single call to the
+    /// app's header queue. Larger batches are split into multiple calls.
+    /// Defaults to the app's own limit.
+    pub fn with_max_headers_per_add(mut self, max_headers_per_add: usize) -> Self {
+        self.max_headers_per_add = max_headers_per_add.clamp(1, MAX_RELAY as usize);
+        self
+    }
+
+    /// Sets how long the header, deposit and checkpoint relay loops wait
+    /// before retrying after an error. The delay starts at `base`, is
+    /// multiplied by `multiplier` after each consecutive failure up to `max`,
+    /// and is randomly reduced by up to half so that relayers sharing a full
+    /// node don't retry in lockstep. Defaults to 2 seconds, doubling up to 60
+    /// seconds.
+    pub fn with_retry_backoff(mut self, base: Duration, max: Duration, multiplier: f64) -> Self {
+        self.retry_backoff = Backoff::new(base, max, multiplier);
+        self
+    }
+
+    /// Sets the number of confirmations after which the checkpoint relay stops
+    /// rebroadcasting a checkpoint transaction. Defaults to 6.
+    pub fn with_checkpoint_settle_confirmations(mut self, confirmations: u32) -> Self {
+        self.checkpoint_broadcasts.required_confs = confirmations;
+        self
+    }
+
+    /// Persists the txids of settled checkpoint transactions to a file in the
+    /// given directory, loading any previously persisted ones, so the
+    /// checkpoint relay doesn't check them again after a restart.
+    pub fn with_settled_checkpoints_dir<P: AsRef<Path>>(mut self, dir: P) -> Result<Self> {
+        self.checkpoint_broadcasts.open(dir)?;
+        Ok(self)
+    }
+
+    /// Makes the checkpoint relay log each checkpoint transaction's txid and
+    /// serialized hex instead of broadcasting it, e.g. to inspect what a new
+    /// deployment would relay. Each transaction is logged once. Disabled by
+    /// default.
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Sets the minimum number of recent blocks rescanned for deposits each
+    /// time the sidechain's tip moves. Defaults to 1100 blocks, covering the
+    /// deposit timeout window, so lower values risk missing deposits whose
+    /// relay failed in an earlier scan.
+    pub fn with_min_rescan_window(mut self, blocks: usize) -> Self {
+        self.min_rescan_window = blocks.max(1);
+        self
+    }
+
+    /// Records deposits in a write-ahead log next to the watched scripts
+    /// while they are being submitted, so that if the relayer stops
+    /// mid-relay, the pending deposits are resubmitted on restart without
+    /// waiting for a rescan. Disabled by default.
+    pub fn with_deposit_wal(mut self, enabled: bool) -> Self {
+        self.use_deposit_wal = enabled;
+        self
+    }
+
+    /// Sets the number of headers fetched from the full node per relay round.
+    /// Larger batches catch up faster on a sidechain far behind the Bitcoin
+    /// chain, smaller ones are gentler on constrained nodes. Values are
+    /// clamped to between 1 and 2000.
+    pub fn with_header_batch_size(mut self, header_batch_size: usize) -> Self {
+        self.header_batch_size = header_batch_size.clamp(1, MAX_HEADER_BATCH_SIZE);
+        self
+    }
+
+    /// Sets the number of recent, reorg-safe blocks kept in memory to avoid
+    /// re-fetching them from the full node while scanning for deposits.
+    /// Defaults to 64, and 0 disables the cache.
+    pub fn with_block_cache_size(mut self, blocks: usize) -> Self {
+        self.block_cache = std::sync::Mutex::new(BlockCache::new(blocks));
+        self
+    }
+
+    /// Sets the Bitcoin network deposit addresses are derived and validated
+    /// for, which the full node must also be on. Defaults to the network the
+    /// binary was built for.
+    pub fn with_network(mut self, network: bitcoin::Network) -> Self {
+        self.network = network;
+        self
+    }
+
+    /// Sets the maximum number of deposits relayed concurrently when a scanned
+    /// block contains several, bounding the RPC and app connections opened
+    /// during deposit bursts. Defaults to 8.
+    pub fn with_max_inflight_deposits(mut self, max_inflight_deposits: usize) -> Self {
+        self.inflight_deposits = Semaphore::new(max_inflight_deposits.max(1));
+        self
+    }
+
+    /// Sets the number of confirmations required before relaying deposits
+    /// whose funding transaction spends an output of a recent checkpoint, so
+    /// that chains of reorg-sensitive transactions are not relayed. Defaults
+    /// to 0, applying no extra requirement.
+    pub fn with_checkpoint_deposit_confirmations(mut self, confirmations: u32) -> Self {
+        self.checkpoint_deposit_confs = confirmations;
+        self
+    }
+
+    /// Verifies each deposit's merkle proof against the sidechain's header at
+    /// the deposit's height before submitting it, so a bad proof, or a deposit
+    /// in a block the sidechain doesn't have, is rejected locally rather than
+    /// by the app. Disabled by default since it costs an extra query per
+    /// deposit.
+    pub fn with_local_proof_verification(mut self, verify: bool) -> Self {
+        self.verify_proofs = verify;
+        self
+    }
+
+    /// Estimates how long it will take for the sidechain's headers to catch up
+    /// to the full node, based on the observed header relay rate. Returns
+    /// `None` until enough header batches have been relayed to measure the
+    /// rate.
+    pub async fn estimate_header_sync_eta(&self) -> Result<Option<Duration>> {
+        let fullnode_height = self.btc_client().await.get_block_count().await?;
+        let sidechain_height = app_client(&self.app_client_addr)
+            .query(|app| Ok(app.bitcoin.headers.height()?))
+            .await?;
+        let headers_behind = fullnode_height.saturating_sub(sidechain_height as u64);
+
+        Ok(header_sync_eta(&self.header_relay_samples, headers_behind))
+    }
+
+    fn record_header_relay(&mut self, count: usize) {
+        self.header_relay_samples.push_back((Instant::now(), count));
+        while self.header_relay_samples.len() > MAX_RELAY_RATE_SAMPLES {
+            self.header_relay_samples.pop_front();
+        }
+    }
+
+    /// Marks a deposit outpoint as processed so the relayer skips it, for
+    /// deposits which were handled out-of-band and would otherwise be retried
+    /// until the app reflects them.
+    pub fn mark_processed(&mut self, txid: Txid, vout: u32) {
+        self.marked_processed.insert((txid, vout));
+    }
+
+    /// Only relays deposits whose destination passes the given policy check
+    /// (e.g. rejecting sanctioned or unregistered addresses). Deposits which
+    /// fail it are skipped with [DepositRelayOutcome::SkippedByPolicy].
+    pub fn with_dest_validator<F>(mut self, validator: F) -> Self
+    where
+        F: Fn(&Dest) -> bool + Send + Sync + 'static,
+    {
+        self.dest_validator = Some(Box::new(validator));
+        self
+    }
+
+    /// Sets the policy determining how many confirmations a deposit to the
+    /// given sigset needs before it is relayed, e.g. to require more for
+    /// recently rotated sigsets. Deposits with fewer are skipped with
+    /// [DepositRelayOutcome::AwaitingConfirmations] until a later scan.
+    pub fn with_confirmation_policy<F>(mut self, policy: F) -> Self
+    where
+        F: Fn(u32) -> u32 + Send + Sync + 'static,
+    {
+        self.confirmation_policy = Some(Box::new(policy));
+        self
+    }
+
+    /// The number of confirmations required before relaying a deposit to the
+    /// sigset with the given index.
+    pub fn confirmations_required_for(&self, sigset_index: u32) -> u32 {
+        self.confirmation_policy
+            .as_ref()
+            .map_or(DEFAULT_DEPOSIT_CONFIRMATIONS, |policy| policy(sigset_index))
+    }
+
+    /// Sends [RelayerEvent]s to the given channel as they occur.
+    ///
+    /// Events are sent with `try_send`, so a slow consumer never blocks
+    /// relaying: if the channel is full, the event is dropped and a warning is
+    /// logged.
+    pub fn with_event_sender(mut self, sender: Sender<RelayerEvent>) -> Self {
+        self.events = Some(sender);
+        self
+    }
+
+    fn emit(&self, event: RelayerEvent) {
+        emit_event(self.events.as_ref(), event);
+    }
+
+    /// Persists the height of the last successfully relayed header batch to a
+    /// file in the given directory, loading any previously persisted value.
+    pub fn with_header_height_dir<P: AsRef<Path>>(mut self, dir: P) -> Result<Self> {
+        let path = dir.as_ref().join(HEADER_HEIGHT_FILE);
+        self.last_relayed_header_height = load_header_height(&path)?;
+        self.header_height_path = Some(path);
+        Ok(self)
+    }
+
+    /// Adds full nodes to fail over to, in order of priority, when the current
+    /// one can't be reached. When a relay loop fails with a connection error,
+    /// the relayer switches to the next node (wrapping around to the primary)
+    /// before retrying.
+    pub fn with_fallback_btc_clients(mut self, clients: Vec<BitcoinRpcClient>) -> Self {
+        for client in clients {
+            self.btc_client.push(client);
+        }
+        self
+    }
+
+    /// The index of the full node currently in use, where 0 is the primary
+    /// node and higher indices are the fallbacks, in the order they were
+    /// added.
+    pub fn active_btc_client(&self) -> usize {
+        self.btc_client.active_index()
+    }
+
+    /// Switches to the next full node if the error means the current one could
+    /// not be reached.
+    fn fail_over_on(&self, err: &Error) {
+        if self.btc_client.len() < 2 || !is_rpc_connection_error(err) {
+            return;
+        }
+
+        let from = self.btc_client.active_index();
+        let to = self.btc_client.fail_over();
+        warn!(
+            "Failing over to another Bitcoin full node:\n\tfrom={}\n\tto={}\n\terror={}",
+            from, to, err,
+        );
+    }
+
+    /// Stops the header, deposit, and checkpoint relay loops once `true` is
+    /// sent on the given channel. The loops exit at the next safe point (never
+    /// mid-write) and the watched scripts file is flushed before returning.
+    pub fn with_shutdown_signal(mut self, shutdown: watch::Receiver<bool>) -> Self {
+        self.shutdown = Some(shutdown);
+        self
+    }
+
+    /// Touches a heartbeat file in the given directory on each successful
+    /// iteration of each relay loop, for watchdogs to detect a stuck loop by
+    /// its stale modification time. The files are named after the loops, e.g.
+    /// `header-relay.heartbeat`.
+    pub fn with_heartbeat_dir<P: AsRef<Path>>(mut self, dir: P) -> Self {
+        self.heartbeat_dir = Some(dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Shares relay progress with other relayer instances through `tracker`,
+    /// so that a single [Relayer::serve_status] server can report on all of
+    /// them.
+    pub fn with_status_tracker(mut self, tracker: StatusTracker) -> Self {
+        self.status = tracker;
+        self
+    }
+
+    /// Resolves once shutdown has been signaled. Never resolves if no shutdown
+    /// signal was configured or its sender was dropped without signaling.
+    fn shutdown_signal(&self) -> impl Future<Output = ()> + 'static {
+        let shutdown = self.shutdown.clone();
+        async move {
+            if let Some(mut shutdown) = shutdown {
+                if shutdown.wait_for(|stop| *stop).await.is_ok() {
+                    return;
+                }
+            }
+            futures::future::pending().await
+        }
+    }
+
+    fn heartbeat(&self, loop_name: &str) {
+        let Some(dir) = &self.heartbeat_dir else {
+            return;
+        };
+        let path = dir.join(format!("{}.heartbeat", loop_name));
+        if let Err(err) = touch_heartbeat(&path) {
+            warn!("Failed to write heartbeat file {}: {}", path.display(), err);
         }
     }
 
+    /// The height of the last header this relayer successfully submitted to
+    /// the sidechain, if known.
+    pub fn last_relayed_header_height(&self) -> Option<u32> {
+        self.last_relayed_header_height
+    }
+
     async fn sidechain_block_hash(&self) -> Result<BlockHash> {
         let hash = app_client(&self.app_client_addr)
             .query(|app| Ok(app.bitcoin.headers.hash()?))
@@ -67,34 +499,213 @@ impl Relayer {
         Ok(hash)
     }
 
+    /// Whether this relayer has already relayed the given deposit outpoint,
+    /// according to its on-disk cache.
+    fn is_relayed(&self, txid: Txid, vout: u32) -> bool {
+        self.relayed_outpoints
+            .as_ref()
+            .is_some_and(|relayed| relayed.lock().unwrap().contains(txid, vout))
+    }
+
     async fn btc_client(&self) -> RwLockReadGuard<BitcoinRpcClient> {
-        self.btc_client.read().await
+        self.btc_client.active().read().await
+    }
+
+    /// Awaits an RPC call to the full node, recording its latency under the
+    /// given method name.
+    async fn timed_rpc<T>(&self, method: &'static str, call: impl Future<Output = T>) -> T {
+        let start = Instant::now();
+        let res = call.await;
+        self.rpc_latencies
+            .lock()
+            .unwrap()
+            .entry(method)
+            .or_default()
+            .record(start.elapsed());
+        res
+    }
+
+    /// Returns latency statistics for each full node RPC method called by the
+    /// relayer, over its most recent calls, to diagnose a slow full node.
+    pub fn rpc_latency_stats(&self) -> HashMap<&'static str, LatencyStats> {
+        self.rpc_latencies
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|(method, samples)| Some((*method, samples.stats()?)))
+            .collect()
+    }
+
+    /// The inclusive range of block heights covered by the most recent deposit
+    /// scan, if any.
+    pub fn last_scan_range(&self) -> Option<(u32, u32)> {
+        self.last_scan.lock().unwrap().map(|(range, _)| range)
+    }
+
+    /// The number of watched outputs found by the most recent deposit scan,
+    /// if any.
+    pub fn last_scan_matches(&self) -> Option<usize> {
+        self.last_scan.lock().unwrap().map(|(_, matches)| matches)
+    }
+
+    /// Finishes building the relayer by checking that its full node agrees
+    /// with the sidechain (see [Relayer::verify_full_node_chain]). Call this
+    /// after the other builder methods, so that the check stops retrying once
+    /// shutdown is signaled.
+    pub async fn connect(self) -> Result<Self> {
+        self.verify_full_node_chain().await?;
+        Ok(self)
+    }
+
+    /// Checks that the configured full node is on the relayer's network and
+    /// knows about the sidechain's current header tip, catching
+    /// misconfiguration before entering the relay loops.
+    ///
+    /// Only the full node reporting another network or that it doesn't have
+    /// the tip is treated as a failure. Other errors, e.g. either node being
+    /// unreachable while it starts up, are retried with the relayer's retry
+    /// backoff until the check succeeds or shutdown is signaled.
+    pub async fn verify_full_node_chain(&self) -> Result<()> {
+        let shutdown = self.shutdown_signal();
+        tokio::pin!(shutdown);
+
+        let mut backoff = self.retry_backoff.clone();
+        loop {
+            let start = Instant::now();
+            let res = tokio::select! {
+                res = self.full_node_chain() => res,
+                // the relay loops exit immediately once shutdown is signaled
+                _ = &mut shutdown => return Ok(()),
+            };
+            let res = match res {
+                Ok(chain) => {
+                    check_full_node_network(&chain, self.network)?;
+                    tokio::select! {
+                        res = self.find_sidechain_tip() => res,
+                        _ = &mut shutdown => return Ok(()),
+                    }
+                }
+                Err(e) => Err(e),
+            };
+
+            match res {
+                Ok(None) => return Ok(()),
+                Ok(Some((hash, height, fullnode_height))) => {
+                    return check_full_node_chain(hash, height, fullnode_height)
+                }
+                Err(e) => {
+                    warn!("Could not check full node chain, retrying:\n\terror={}", e);
+                    self.fail_over_on(&e);
+                }
+            }
+
+            tokio::select! {
+                _ = tokio::time::sleep(backoff.after_attempt(start.elapsed())) => {}
+                _ = &mut shutdown => return Ok(()),
+            }
+        }
+    }
+
+    /// The name of the chain the full node is on, as reported by
+    /// `getblockchaininfo`.
+    async fn full_node_chain(&self) -> Result<String> {
+        Ok(self.btc_client().await.get_blockchain_info().await?.chain)
+    }
+
+    /// Looks up the sidechain's header tip on the full node. Returns `None` if
+    /// the full node has it, otherwise the tip's hash and height and the full
+    /// node's height.
+    async fn find_sidechain_tip(&self) -> Result<Option<(BlockHash, u32, u64)>> {
+        let sidechain_hash = self.sidechain_block_hash().await?;
+        if self.full_node_has_block(&sidechain_hash).await? {
+            return Ok(None);
+        }
+
+        let sidechain_height = app_client(&self.app_client_addr)
+            .query(|app| Ok(app.bitcoin.headers.height()?))
+            .await?;
+        let fullnode_height = self.btc_client().await.get_block_count().await?;
+
+        Ok(Some((sidechain_hash, sidechain_height, fullnode_height)))
+    }
+
+    /// Whether the full node has the given block. Errors other than the full
+    /// node reporting that it doesn't know the block are returned.
+    async fn full_node_has_block(&self, hash: &BlockHash) -> Result<bool> {
+        match self.btc_client().await.get_block_header_info(hash).await {
+            Ok(_) => Ok(true),
+            Err(e) => {
+                let e = Error::from(e);
+                if is_block_not_found(&e) {
+                    Ok(false)
+                } else {
+                    Err(e)
+                }
+            }
+        }
     }
 
     pub async fn start_header_relay(&mut self) -> Result<()> {
         info!("Starting header relay...");
 
+        let shutdown = self.shutdown_signal();
+        tokio::pin!(shutdown);
+
+        let log_header_progress = |report: &HeaderRelayReport| {
+            info!(
+                "Header relay progress:\n\theight={}\n\tblocks_behind={}",
+                report.to_height,
+                report.blocks_behind(),
+            );
+        };
+
+        let mut backoff = self.retry_backoff.clone();
         loop {
-            if let Err(e) = self.relay_headers().await {
-                error!("Header relay error: {}", e);
+            let start = Instant::now();
+            tokio::select! {
+                res = self.relay_headers(log_header_progress) => {
+                    if let Err(e) = res {
+                        error!("Header relay error: {}", e);
+                        self.fail_over_on(&e);
+                    }
+                }
+                _ = &mut shutdown => break,
             }
 
-            tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+            tokio::select! {
+                _ = tokio::time::sleep(backoff.after_attempt(start.elapsed())) => {}
+                _ = &mut shutdown => break,
+            }
         }
+
+        info!("Stopped header relay");
+        Ok(())
     }
 
-    async fn relay_headers(&mut self) -> Result<()> {
+    /// Relays headers until an error occurs, calling `on_batch` with a report
+    /// of each batch relayed.
+    async fn relay_headers<F>(&mut self, mut on_batch: F) -> Result<()>
+    where
+        F: FnMut(&HeaderRelayReport),
+    {
         let mut last_hash = None;
 
         loop {
             let fullnode_hash = self.btc_client().await.get_best_block_hash().await?;
             let sidechain_hash = self.sidechain_block_hash().await?;
+            self.heartbeat("header-relay");
 
             if fullnode_hash != sidechain_hash {
-                self.relay_header_batch(fullnode_hash, sidechain_hash)
-                    .await?;
+                if let Some(report) = self
+                    .relay_header_batch(fullnode_hash, sidechain_hash)
+                    .await?
+                {
+                    on_batch(&report);
+                }
+                self.status.record_header_relay();
                 continue;
             }
+            self.status.record_header_relay();
 
             if last_hash.is_none() || last_hash.is_some_and(|h| h != fullnode_hash) {
                 last_hash = Some(fullnode_hash);
@@ -121,20 +732,34 @@ impl Relayer {
         info!("Starting deposit relay...");
 
         let index = Arc::new(Mutex::new(DepositIndex::new()));
-        let scripts = WatchedScriptStore::open(store_path, &self.app_client_addr).await?;
+        let relayed_outpoints = RelayedOutpoints::open(&store_path)?;
+        self.relayed_outpoints = Some(std::sync::Mutex::new(relayed_outpoints));
+        if self.use_deposit_wal {
+            let wal = DepositWal::open(&store_path)?;
+            self.deposit_wal = Some(std::sync::Mutex::new(wal));
+            if let Err(err) = self.resume_pending_deposits().await {
+                error!("Failed to resume pending deposit relays: {}", err);
+            }
+        }
+        let scripts =
+            WatchedScriptStore::open(store_path, &self.app_client_addr, self.network).await?;
         self.scripts = Arc::new(Mutex::new(Some(scripts)));
 
         self.deposit_buffer = Some(deposit_buffer);
 
         let (server, mut recv) = self.create_address_server(index.clone())?;
+        let shutdown = self.shutdown_signal();
 
         let deposit_relay = async {
+            let mut backoff = self.retry_backoff.clone();
             loop {
+                let start = Instant::now();
                 if let Err(e) = self.relay_deposits(&mut recv, index.clone()).await {
                     error!("Deposit relay error: {}", e);
+                    self.fail_over_on(&e);
                 }
 
-                tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+                tokio::time::sleep(backoff.after_attempt(start.elapsed())).await;
             }
         };
 
@@ -142,20 +767,31 @@ impl Relayer {
 
         let mempool_relay = async {
             loop {
-                if let Err(e) = self
+                match self
                     .scan_for_mempool_deposits(index.clone(), &mut seen_mempool_txids)
                     .await
                 {
-                    if !e.to_string().contains("No completed checkpoints yet") {
+                    Ok(()) => self.heartbeat("mempool-relay"),
+                    Err(e) if !e.to_string().contains("No completed checkpoints yet") => {
                         error!("Mempool deposit relay error: {}", e);
+                        self.fail_over_on(&e);
                     }
+                    Err(_) => {}
                 }
 
                 tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
             }
         };
 
-        join!(server, deposit_relay, mempool_relay);
+        tokio::select! {
+            _ = async { join!(server, deposit_relay, mempool_relay) } => {}
+            _ = shutdown => {}
+        }
+
+        if let Some(scripts) = self.scripts.lock().await.as_mut() {
+            scripts.flush()?;
+        }
+        info!("Stopped deposit relay");
         Ok(())
     }
 
@@ -171,6 +807,7 @@ impl Relayer {
         let app_client_addr: &'static str = self.app_client_addr.clone().leak();
 
         let btc_client = self.btc_client.clone();
+        let network = self.network;
         let deposit_buffer = match self.deposit_buffer {
             Some(deposit_buffer) => deposit_buffer,
             None => return Err(Error::Relayer("Deposit buffer not set".to_string())),
@@ -194,6 +831,8 @@ impl Relayer {
                     async move {
                         let dest = Dest::decode(body.to_vec().as_slice())
                             .map_err(|e| warp::reject::custom(Error::from(e)))?;
+                        parse_network_address(&query.deposit_addr, network)
+                            .map_err(warp::reject::custom)?;
 
                         let mut sigsets = sigsets.lock().await;
 
@@ -225,7 +864,7 @@ impl Relayer {
                                     SIGSET_THRESHOLD,
                                 )
                                 .map_err(warp::reject::custom)?,
-                            super::NETWORK,
+                            network,
                         )
                         .unwrap()
                         .to_string();
@@ -298,12 +937,13 @@ impl Relayer {
             .and(warp::query::<DepositsQuery>())
             .map(move |query: DepositsQuery| (query, btc_client.clone(), index.clone()))
             .and_then(
-                move |(query, btc_client, index): (
+                move |(query, btc_clients, index): (
                     DepositsQuery,
-                    Arc<RwLock<BitcoinRpcClient>>,
+                    BtcClients,
                     Arc<Mutex<DepositIndex>>,
                 )| {
                     async move {
+                        let btc_client = btc_clients.active().clone();
                         let btc_client = btc_client.read().await;
                         let tip = btc_client
                             .get_best_block_hash()
@@ -330,6 +970,7 @@ impl Relayer {
                 .and(bcast_route.clone())
                 .or(sigset_route.clone())
                 .or(pending_deposits_route)
+                .recover(handle_rejection)
                 .with(
                     warp::cors()
                         .allow_any_origin()
@@ -349,43 +990,96 @@ impl Relayer {
         Ok((server, recv))
     }
 
+    /// Serves a JSON status report at `GET /status` on `addr`, for monitoring
+    /// systems to check the relayer's liveness. The report includes the
+    /// sidechain and full node tips and the progress recorded by all relayers
+    /// sharing this relayer's [StatusTracker].
+    pub fn serve_status(&self, addr: SocketAddr) -> impl Future<Output = ()> {
+        use warp::Filter;
+
+        let btc_client = self.btc_client.clone();
+        let app_client_addr = self.app_client_addr.clone();
+        let tracker = self.status.clone();
+
+        let status_route = warp::get().and(warp::path("status")).then(move || {
+            let btc_client = btc_client.clone();
+            let app_client_addr = app_client_addr.clone();
+            let tracker = tracker.clone();
+            async move {
+                let status = RelayerStatus {
+                    sidechain_tip: sidechain_tip(&app_client_addr).await.ok(),
+                    fullnode_tip: fullnode_tip(&btc_client).await.ok(),
+                    progress: tracker.progress(),
+                };
+                warp::reply::json(&status)
+            }
+        });
+
+        info!("Serving relayer status at http://{}/status", addr);
+        warp::serve(status_route).run(addr)
+    }
+
     async fn relay_deposits(
         &self,
         recv: &mut Receiver<(Dest, u32)>,
         index: Arc<Mutex<DepositIndex>>,
     ) -> Result<!> {
         let mut prev_tip = None;
+        let mut backoff = IdleBackoff::new(DEPOSIT_SCAN_INTERVAL, MAX_DEPOSIT_SCAN_INTERVAL);
 
         loop {
             self.insert_announced_addrs(recv).await?;
 
             let tip = self.sidechain_block_hash().await?;
+            self.heartbeat("deposit-relay");
             let prev = prev_tip.unwrap_or(tip);
             if prev_tip.is_some() && prev == tip {
+                tokio::time::sleep(backoff.idle()).await;
                 continue;
             }
+            backoff.reset();
 
             let start_height = self.common_ancestor(tip, prev).await?.height;
+            let prev_height = self
+                .btc_client()
+                .await
+                .get_block_header_info(&prev)
+                .await?
+                .height;
             let end_height = self
                 .btc_client()
                 .await
                 .get_block_header_info(&tip)
                 .await?
                 .height;
-            let num_blocks = (end_height - start_height).max(1100);
+            if let Some(depth) = reorg_depth(prev_height, start_height) {
+                warn!(
+                    "Detected Bitcoin reorg, rescanning deposits on the new branch:\n\tdepth={}\n\tancestor_height={}\n\ttip_height={}",
+                    depth, start_height, end_height,
+                );
+            }
+            let num_blocks = deposit_scan_window(start_height, end_height, self.min_rescan_window);
 
-            self.scan_for_deposits(num_blocks, index.clone()).await?;
+            // scan from the tip the window was computed for, in case the
+            // sidechain has moved since
+            self.scan_for_deposits(tip, num_blocks, index.clone())
+                .await?;
 
             prev_tip = Some(tip);
         }
     }
 
+    /// Scans the last `num_blocks` blocks of the sidechain's header chain for
+    /// deposits and relays them. Errors relaying individual deposits are
+    /// logged and collected in the returned [DepositScan] rather than aborting
+    /// the scan; only failures to fetch blocks or query the app are returned
+    /// as errors.
     async fn scan_for_deposits(
         &self,
+        tip: BlockHash,
         num_blocks: usize,
         index: Arc<Mutex<DepositIndex>>,
-    ) -> Result<BlockHash> {
-        let tip = self.sidechain_block_hash().await?;
+    ) -> Result<DepositScan> {
         let base_height = self
             .btc_client()
             .await
@@ -394,22 +1088,92 @@ impl Relayer {
             .height;
         let blocks = self.last_n_blocks(num_blocks, tip).await?;
 
+        let checkpoint_txids: HashSet<_> = if self.checkpoint_deposit_confs > 0 {
+            app_client(&self.app_client_addr)
+                .query(|app| Ok(app.bitcoin.checkpoints.completed_txs(RECENT_CHECKPOINTS)?))
+                .await?
+                .into_iter()
+                .map(|tx| tx.txid())
+                .collect()
+        } else {
+            HashSet::new()
+        };
+
+        let mut scan = DepositScan::new(tip, base_height as u32, blocks.len() as u32);
         for (i, block) in blocks.into_iter().enumerate().rev() {
             let height = (base_height - i) as u32;
-            for (tx, matches) in self.relevant_txs(&block).await? {
-                for output in matches {
-                    if let Err(err) = self
-                        .maybe_relay_deposit(tx, height, &block.block_hash(), output, index.clone())
-                        .await
-                    {
-                        // TODO: filter out harmless errors (e.g. deposit too small)
-                        warn!("Skipping deposit for error: {}", err);
-                    }
+            let candidates: Vec<_> = self
+                .relevant_txs(&block)
+                .await?
+                .flat_map(|(tx, matches)| matches.map(move |output| (tx, output)))
+                .collect();
+            if candidates.is_empty() {
+                continue;
+            }
+            scan.matched_outputs += candidates.len();
+
+            // outpoints this relayer already relayed don't need to be checked
+            // with the app
+            let mut processed: HashSet<_> = deposit_outpoints(&candidates)
+                .into_iter()
+                .filter(|(txid, vout)| self.is_relayed(Txid::from_inner(*txid), *vout))
+                .collect();
+            let unknown: Vec<_> = deposit_outpoints(&candidates)
+                .into_iter()
+                .filter(|outpoint| !processed.contains(outpoint))
+                .collect();
+            if !unknown.is_empty() {
+                processed.extend(self.processed_outpoints(unknown).await?);
+            }
+
+            let confirmations = base_height as u32 - height + 1;
+            let mut relayable = Vec::with_capacity(candidates.len());
+            for (tx, output) in candidates {
+                self.emit(RelayerEvent::DepositDetected(DepositInfo::new(
+                    tx,
+                    &output,
+                    height,
+                    base_height as u32,
+                )));
+
+                if spends_checkpoint(tx, &checkpoint_txids)
+                    && confirmations < self.checkpoint_deposit_confs
+                {
+                    debug!(
+                        "Deferring deposit funded by checkpoint until {} confirmations: {}:{}",
+                        self.checkpoint_deposit_confs,
+                        tx.txid(),
+                        output.vout
+                    );
+                    continue;
                 }
+                relayable.push((tx, output));
+            }
+
+            let results = with_permits(&self.inflight_deposits, relayable, |(tx, output)| {
+                let vout = output.vout;
+                let contains_outpoint = processed.contains(&(tx.txid().into_inner(), vout));
+                let res = self.maybe_relay_deposit(
+                    tx,
+                    height,
+                    confirmations,
+                    &block,
+                    output,
+                    contains_outpoint,
+                    index.clone(),
+                );
+                async move { (tx.txid(), vout, res.await) }
+            })
+            .await;
+            for (txid, vout, res) in results {
+                scan.record(txid, vout, res);
             }
         }
 
-        Ok(tip)
+        *self.last_scan.lock().unwrap() = Some((scan.range, scan.matched_outputs));
+        self.prune_relayed_outpoints(base_height as u32)?;
+
+        Ok(scan)
     }
 
     async fn scan_for_mempool_deposits(
@@ -417,7 +1181,11 @@ impl Relayer {
         index: Arc<Mutex<DepositIndex>>,
         seen_mempool_txids: &mut HashSet<Txid>,
     ) -> Result<()> {
-        let mempool = self.btc_client().await.get_raw_mempool().await?;
+        let mempool = self
+            .timed_rpc("get_raw_mempool", async {
+                self.btc_client().await.get_raw_mempool().await
+            })
+            .await?;
 
         for txid in mempool {
             if seen_mempool_txids.contains(&txid) {
@@ -427,9 +1195,12 @@ impl Relayer {
             tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
 
             let tx = self
-                .btc_client()
-                .await
-                .get_raw_transaction(&txid, None)
+                .timed_rpc("get_raw_transaction", async {
+                    self.btc_client()
+                        .await
+                        .get_raw_transaction(&txid, None)
+                        .await
+                })
                 .await?;
             for (vout, output) in tx.output.iter().enumerate() {
                 let mut script_bytes = vec![];
@@ -441,10 +1212,8 @@ impl Relayer {
                 }
 
                 if let Some((dest, _)) = script_guard.as_ref().unwrap().scripts.get(&script) {
-                    let bitcoin_address = bitcoin::Address::from_script(
-                        &output.script_pubkey.clone(),
-                        super::NETWORK,
-                    )?;
+                    let bitcoin_address =
+                        bitcoin::Address::from_script(&output.script_pubkey.clone(), self.network)?;
 
                     let mut index = index.lock().await;
                     let receiver_addr = match dest.to_receiver_addr() {
@@ -479,8 +1248,6 @@ impl Relayer {
     }
 
     async fn relay_emergency_disbursal_transactions(&mut self) -> Result<()> {
-        use std::time::{SystemTime, UNIX_EPOCH};
-
         let mut relayed = HashSet::new();
         loop {
             let disbursal_txs = app_client(&self.app_client_addr)
@@ -527,16 +1294,146 @@ impl Relayer {
         }
     }
 
+    /// Reports the status on the Bitcoin network of recently completed
+    /// checkpoint transactions.
+    ///
+    /// Confirmed transactions are looked up with `getrawtransaction`, so the
+    /// full node must have `txindex` enabled for them to be reported as
+    /// confirmed rather than dropped.
+    pub async fn checkpoint_mempool_status(&self) -> Result<Vec<(Txid, CheckpointTxStatus)>> {
+        let txs = app_client(&self.app_client_addr)
+            .query(|app| Ok(app.bitcoin.checkpoints.completed_txs(RECENT_CHECKPOINTS)?))
+            .await?;
+        let mempool: HashSet<_> = self
+            .btc_client()
+            .await
+            .get_raw_mempool()
+            .await?
+            .into_iter()
+            .collect();
+
+        let mut statuses = Vec::with_capacity(txs.len());
+        for tx in txs {
+            // skip checkpoints that came from backfill
+            if tx.input.is_empty() {
+                continue;
+            }
+
+            let txid = tx.txid();
+            let in_mempool = mempool.contains(&txid);
+            let confirmed_height = if in_mempool {
+                None
+            } else {
+                self.tx_confirmed_height(txid).await?
+            };
+
+            statuses.push((txid, CheckpointTxStatus::new(in_mempool, confirmed_height)));
+        }
+
+        Ok(statuses)
+    }
+
+    /// The number of confirmations of a checkpoint transaction according to
+    /// the full node, or `None` if it is unconfirmed, its reserve output has
+    /// been spent, or the node can't look it up.
+    ///
+    /// This looks up the reserve output with `gettxout`, which unlike
+    /// `getrawtransaction` doesn't need the node to have `txindex` enabled.
+    /// Once the reserve output is spent by a later checkpoint, rebroadcasting
+    /// the transaction fails as already mined, which settles it.
+    async fn tx_confirmations(&self, txid: Txid) -> Option<u32> {
+        self.btc_client()
+            .await
+            .get_tx_out(&txid, 0, Some(false))
+            .await
+            .ok()?
+            .map(|output| output.confirmations)
+    }
+
+    async fn tx_confirmed_height(&self, txid: Txid) -> Result<Option<u32>> {
+        let info = match self
+            .btc_client()
+            .await
+            .get_raw_transaction_info(&txid, None)
+            .await
+        {
+            Ok(info) => info,
+            // the node doesn't know about the transaction
+            Err(_) => return Ok(None),
+        };
+
+        let block_hash = match info.blockhash {
+            Some(block_hash) => block_hash,
+            None => return Ok(None),
+        };
+        let height = self
+            .btc_client()
+            .await
+            .get_block_header_info(&block_hash)
+            .await?
+            .height;
+
+        Ok(Some(height as u32))
+    }
+
     pub async fn start_checkpoint_relay(&mut self) -> Result<()> {
         info!("Starting checkpoint relay...");
+        let shutdown = self.shutdown_signal();
+        tokio::pin!(shutdown);
+
+        let mut backoff = self.retry_backoff.clone();
         loop {
-            if let Err(e) = self.relay_checkpoints().await {
-                if !e.to_string().contains("No completed checkpoints yet") {
-                    error!("Checkpoint relay error: {}", e);
+            let start = Instant::now();
+            tokio::select! {
+                res = self.relay_checkpoints() => {
+                    if let Err(e) = res {
+                        if !e.to_string().contains("No completed checkpoints yet") {
+                            error!("Checkpoint relay error: {}", e);
+                        }
+                        self.fail_over_on(&e);
+                    }
                 }
+                _ = &mut shutdown => break,
             }
 
-            tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+            tokio::select! {
+                _ = tokio::time::sleep(backoff.after_attempt(start.elapsed())) => {}
+                _ = &mut shutdown => break,
+            }
+        }
+
+        info!("Stopped checkpoint relay");
+        Ok(())
+    }
+
+    /// Estimates the earliest time at which the current `Building` checkpoint
+    /// may advance to signing, after which its transaction will be ready for
+    /// broadcast once signed: its creation time plus the queue's minimum
+    /// checkpoint interval. Returns `None` if the queue is empty or can't be
+    /// queried.
+    pub async fn estimate_next_checkpoint_time(&self) -> Result<Option<SystemTime>> {
+        let res = app_client(&self.app_client_addr)
+            .query(|app| {
+                let checkpoints = &app.bitcoin.checkpoints;
+                if checkpoints.is_empty()? {
+                    return Ok(None);
+                }
+                Ok(Some((
+                    checkpoints.building()?.create_time(),
+                    checkpoints.config.min_checkpoint_interval,
+                )))
+            })
+            .await;
+
+        match res {
+            Ok(Some((create_time, min_interval))) => {
+                Ok(next_checkpoint_time(create_time, min_interval))
+            }
+            Ok(None) => Ok(None),
+            Err(err) => {
+                debug!("Could not estimate next checkpoint time: {}", err);
+                Ok(None)
+            }
         }
     }
 
@@ -545,24 +1442,34 @@ impl Relayer {
             .query(|app| Ok(app.bitcoin.checkpoints.last_completed_tx()?))
             .await?;
         info!("Last checkpoint tx: {}", last_checkpoint.txid());
-        let mut relayed = HashSet::new();
 
         loop {
             let txs = app_client(&self.app_client_addr)
                 .query(|app| Ok(app.bitcoin.checkpoints.completed_txs(1_000)?))
                 .await?;
             for tx in txs {
-                if relayed.contains(&tx.txid()) {
+                let txid = tx.txid();
+                // skip checkpoints that came from backfill
+                if tx.input.is_empty() || self.checkpoint_broadcasts.is_settled(&txid) {
                     continue;
                 }
-                // skip checkpoints that came from backfill
-                if tx.input.is_empty() {
+
+                let confirmations = self.tx_confirmations(txid).await;
+                if !self
+                    .checkpoint_broadcasts
+                    .needs_broadcast(txid, confirmations)
+                {
                     continue;
                 }
 
                 let mut tx_bytes = vec![];
                 tx.consensus_encode(&mut tx_bytes)?;
 
+                if self.dry_run {
+                    self.log_dry_run_checkpoint(txid, &tx_bytes);
+                    continue;
+                }
+
                 match self
                     .btc_client()
                     .await
@@ -570,27 +1477,58 @@ impl Relayer {
                     .await
                 {
                     Ok(_) => {
-                        info!("Relayed checkpoint: {}", tx.txid());
+                        info!("Relayed checkpoint: {}", txid);
                     }
-                    Err(err) if err.to_string().contains("bad-txns-inputs-missingorspent") => {}
-                    Err(err)
-                        if err
-                            .to_string()
-                            .contains("Transaction already in block chain") => {}
-                    Err(err) => Err(err)?,
+                    Err(err) => self.handle_checkpoint_broadcast_error(txid, err.into())?,
                 }
-
-                relayed.insert(tx.txid());
             }
+            self.heartbeat("checkpoint-relay");
 
             tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
         }
     }
 
+    /// Logs a checkpoint transaction which would otherwise have been
+    /// broadcast, settling it so that it is only logged once.
+    fn log_dry_run_checkpoint(&mut self, txid: Txid, tx_bytes: &[u8]) {
+        info!(
+            "Dry run, not broadcasting checkpoint:\n\ttxid={}\n\thex={}",
+            txid,
+            hex::encode(tx_bytes)
+        );
+        self.checkpoint_broadcasts.skip(txid);
+    }
+
+    /// Handles an error from broadcasting a checkpoint transaction. Errors
+    /// meaning the transaction can never be accepted are reported (once per
+    /// transaction) as critical rather than returned, so they don't stall the
+    /// relay of other checkpoints.
+    fn handle_checkpoint_broadcast_error(&mut self, txid: Txid, err: Error) -> Result<()> {
+        let reason = err.to_string();
+        match classify_broadcast_error(&reason) {
+            BroadcastError::Settled => self.checkpoint_broadcasts.settle(txid),
+            BroadcastError::Invalid => {
+                if self.checkpoint_broadcasts.reject(txid) {
+                    error!(
+                        "CRITICAL: Checkpoint transaction rejected as invalid, signatory set signatures may be malformed:\n\ttxid={}\n\treason={}",
+                        txid, reason,
+                    );
+                    self.emit(RelayerEvent::CheckpointRejected { txid, reason });
+                } else {
+                    debug!("Checkpoint transaction {} still rejected: {}", txid, reason);
+                }
+            }
+            BroadcastError::Transient => return Err(err),
+        }
+
+        Ok(())
+    }
+
     pub async fn start_recovery_tx_relay<P: AsRef<Path>>(&mut self, store_path: P) -> Result<()> {
         info!("Starting recovery tx relay...");
 
-        let scripts = WatchedScriptStore::open(store_path, &self.app_client_addr).await?;
+        let scripts =
+            WatchedScriptStore::open(store_path, &self.app_client_addr, self.network).await?;
         self.scripts = Arc::new(Mutex::new(Some(scripts)));
 
         loop {
@@ -634,7 +1572,7 @@ impl Relayer {
                 }
 
                 let script_pubkey = signed_tx.tx.output[0].script_pubkey.clone();
-                let deposit_addr = bitcoin::Address::from_script(&script_pubkey, super::NETWORK)?;
+                let deposit_addr = bitcoin::Address::from_script(&script_pubkey, self.network)?;
                 let url = format!("{}/address", "http://localhost:8999",);
                 let client = reqwest::Client::new();
                 let res = client
@@ -803,32 +1741,118 @@ impl Relayer {
             .query(|app| Ok(app.bitcoin.checkpoints.config.max_age))
             .await?;
         let mut script_guard = self.scripts.lock().await;
-        script_guard
-            .as_mut()
-            .unwrap()
-            .scripts
-            .remove_expired(max_age)?;
+        let scripts = &mut script_guard.as_mut().unwrap().scripts;
+        scripts.remove_expired(max_age)?;
+        self.status.record_watched_scripts(scripts.len());
+
+        if let (Some(relayed), Some(oldest)) = (&self.relayed_outpoints, scripts.oldest_sigset()) {
+            let mut relayed = relayed.lock().unwrap();
+            relayed.prune(oldest)?;
+            self.status.record_relayed_outpoints(relayed.len());
+        }
 
         Ok(())
     }
 
+    /// Fetches the `n` blocks ending at `hash`, or fewer if the chain is
+    /// shorter, ordered from `hash` backwards. The hashes are resolved by
+    /// walking headers, then the blocks are fetched concurrently.
     pub async fn last_n_blocks(&self, n: usize, hash: BlockHash) -> Result<Vec<Block>> {
-        let mut blocks = vec![];
+        let mut headers = Vec::with_capacity(n);
+        let mut next = Some(hash);
+        while let Some(hash) = next {
+            if headers.len() == n {
+                break;
+            }
+            let info = self.btc_client().await.get_block_header_info(&hash).await?;
+            next = info.previous_block_hash;
+            headers.push((hash, info.confirmations));
+        }
+
+        stream::iter(headers)
+            .map(|(hash, confirmations)| self.get_block(hash, confirmations))
+            .buffered(BLOCK_FETCH_CONCURRENCY)
+            .try_collect()
+            .await
+    }
+
+    /// Writes a human-readable report of every watched deposit script, with the
+    /// app's current deposit timeout for each sigset, for diagnosing missed
+    /// deposits. See [WatchedScripts::dump].
+    pub async fn dump_watched_scripts<W: Write>(&self, writer: W) -> Result<()> {
+        let max_deposit_age = app_client(&self.app_client_addr)
+            .query(|app| Ok(app.bitcoin.config.max_deposit_age))
+            .await?;
+        match self.scripts.lock().await.as_ref() {
+            Some(store) => store.scripts.dump(writer, max_deposit_age),
+            None => Err(Error::Relayer("Watched scripts not loaded".to_string())),
+        }
+    }
+
+    /// Scans the last `num_blocks` blocks for deposits to addresses which were
+    /// never announced to this relayer, but which are derivable from a known
+    /// destination and one of the app's sigsets (e.g. a destination announced
+    /// for one sigset, paid at its address for another). Such deposits are
+    /// not detected by the deposit relay, so they are logged and returned for
+    /// investigation. They are not relayed.
+    pub async fn find_unannounced_deposits(
+        &self,
+        num_blocks: usize,
+    ) -> Result<Vec<UnannouncedDeposit>> {
+        let sigsets = WatchedScriptStore::app_sigsets(&self.app_client_addr).await?;
+        let candidates = match self.scripts.lock().await.as_ref() {
+            Some(store) => store.scripts.unannounced_scripts(&sigsets)?,
+            None => return Err(Error::Relayer("Watched scripts not loaded".to_string())),
+        };
+        if candidates.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let tip = self.btc_client().await.get_best_block_hash().await?;
+        let tip_height = self
+            .btc_client()
+            .await
+            .get_block_header_info(&tip)
+            .await?
+            .height as u32;
 
-        let mut hash = bitcoin::BlockHash::from_inner(hash.into_inner());
+        let mut found = vec![];
+        for (i, block) in self
+            .last_n_blocks(num_blocks, tip)
+            .await?
+            .iter()
+            .enumerate()
+        {
+            let height = tip_height - i as u32;
+            for deposit in match_unannounced(block, height, &candidates) {
+                warn!(
+                    "Found deposit to unannounced address:\n\ttxid={}\n\tvout={}\n\tsats={}\n\tsigset_index={}\n\theight={}",
+                    deposit.txid, deposit.vout, deposit.amount, deposit.sigset_index, deposit.height,
+                );
+                found.push(deposit);
+            }
+        }
 
-        for _ in 0..n {
-            let block = self.btc_client().await.get_block(&hash.clone()).await?;
-            hash = block.header.prev_blockhash;
+        Ok(found)
+    }
 
-            let mut block_bytes = vec![];
-            block.consensus_encode(&mut block_bytes).unwrap();
-            let block = Block::consensus_decode(&mut block_bytes.as_slice()).unwrap();
+    /// Fetches a block, from the block cache if possible. Blocks with enough
+    /// `confirmations` to be safe from reorgs are added to the cache.
+    async fn get_block(&self, hash: BlockHash, confirmations: i32) -> Result<Block> {
+        if let Some(block) = self.block_cache.lock().unwrap().get(&hash) {
+            return Ok(block);
+        }
 
-            blocks.push(block);
+        let block = self
+            .timed_rpc("get_block", async {
+                self.btc_client().await.get_block(&hash).await
+            })
+            .await?;
+        if confirmations >= BLOCK_CACHE_MIN_CONFS {
+            self.block_cache.lock().unwrap().insert(hash, block.clone());
         }
 
-        Ok(blocks)
+        Ok(block)
     }
 
     pub async fn relevant_txs<'a>(
@@ -848,6 +1872,7 @@ impl Relayer {
         &'a self,
         tx: &'a Transaction,
     ) -> Result<impl Iterator<Item = OutputMatch> + 'a> {
+        let memo = deposit_memo(tx);
         let mut matches = Vec::new();
         for (vout, output) in tx.output.iter().enumerate() {
             let mut script_bytes = vec![];
@@ -864,6 +1889,7 @@ impl Relayer {
                     sigset_index,
                     vout: vout as u32,
                     dest,
+                    memo: memo.clone(),
                 });
             }
         }
@@ -871,34 +1897,152 @@ impl Relayer {
         Ok(matches.into_iter())
     }
 
+    /// Fetches the full node's merkle proof that the given transaction is in
+    /// the given block, and checks it against the header the sidechain has at
+    /// `height`, catching bad proofs before they are submitted to the app.
+    /// Checking against the sidechain's header rather than the full node's
+    /// own means a full node on a fork, or one serving a bad header along with
+    /// a matching bad proof, can't pass the check.
+    ///
+    /// Returns whether the transaction is included in the block, or an error
+    /// if the block isn't the sidechain's block at `height` or
+    /// [Error::BitcoinMerkleBlockError] if the proof is invalid.
+    pub async fn verify_deposit_proof(
+        &self,
+        txid: Txid,
+        block_hash: BlockHash,
+        height: u32,
+    ) -> Result<bool> {
+        let proof_bytes = self
+            .timed_rpc("get_tx_out_proof", async {
+                self.btc_client()
+                    .await
+                    .get_tx_out_proof(&[txid], Some(&block_hash))
+                    .await
+            })
+            .await?;
+        let proof = ::bitcoin::MerkleBlock::consensus_decode(&mut proof_bytes.as_slice())?.txn;
+        let header = self.sidechain_header(height).await?;
+        check_sidechain_block(block_hash, height, &header)?;
+
+        check_merkle_proof(&proof, header.merkle_root(), txid)
+    }
+
+    /// The header the sidechain's header queue has at the given height, which
+    /// the app checks deposit proofs against.
+    async fn sidechain_header(&self, height: u32) -> Result<WorkHeader> {
+        app_client(&self.app_client_addr)
+            .query(|app| Ok(app.bitcoin.headers.get_by_height(height)?))
+            .await?
+            .ok_or_else(|| Error::Relayer(format!("Sidechain has no header at height {}", height)))
+    }
+
+    /// Waits until the app has processed the given deposit outpoint, polling
+    /// until `timeout` elapses. Deposits marked as processed with
+    /// [Relayer::mark_processed] resolve immediately.
+    pub async fn wait_for_deposit(
+        &self,
+        txid: Txid,
+        vout: u32,
+        timeout: Duration,
+    ) -> Result<DepositRelayOutcome> {
+        if self.marked_processed.contains(&(txid, vout)) {
+            return Ok(DepositRelayOutcome::MarkedProcessed);
+        }
+
+        let outpoint = (txid.into_inner(), vout);
+        let deadline = Instant::now() + timeout;
+        loop {
+            match self.processed_outpoints(vec![outpoint]).await {
+                Ok(processed) if processed.contains(&outpoint) => {
+                    return Ok(DepositRelayOutcome::Relayed)
+                }
+                Ok(_) => {}
+                Err(err) => debug!("Failed to query deposit {}:{}: {}", txid, vout, err),
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(Error::Relayer(format!(
+                    "Timed out waiting for deposit {}:{} to be relayed",
+                    txid, vout
+                )));
+            }
+            tokio::time::sleep(DEPOSIT_POLL_INTERVAL.min(deadline - now)).await;
+        }
+    }
+
+    /// Returns the subset of the given outpoints which the app has already
+    /// processed, checked with a single `contains_batch` query which the node
+    /// evaluates and proves in one round trip.
+    async fn processed_outpoints(&self, outpoints: Vec<Outpoint>) -> Result<HashSet<Outpoint>> {
+        let client = app_client(&self.app_client_addr);
+        batch_processed_outpoints(outpoints, |batch| async move {
+            Ok(client
+                .query(|app| {
+                    app.bitcoin
+                        .processed_outpoints
+                        .contains_batch(batch.clone())
+                })
+                .await?)
+        })
+        .await
+    }
+
     async fn maybe_relay_deposit(
         &self,
         tx: &Transaction,
         height: u32,
-        block_hash: &BlockHash,
+        confirmations: u32,
+        block: &Block,
         output: OutputMatch,
+        contains_outpoint: bool,
         index: Arc<Mutex<DepositIndex>>,
-    ) -> Result<()> {
+    ) -> Result<DepositRelayOutcome> {
         use bitcoin::hashes::Hash as _;
 
         let txid = tx.txid();
-        let outpoint = (txid.into_inner(), output.vout);
+        if self.marked_processed.contains(&(txid, output.vout)) {
+            debug!(
+                "Skipping deposit marked as processed: {}:{}",
+                txid, output.vout
+            );
+            return Ok(DepositRelayOutcome::MarkedProcessed);
+        }
+
+        if let Some(validator) = &self.dest_validator {
+            if !validator(&output.dest) {
+                info!(
+                    "Skipping deposit rejected by destination policy: {}:{}, {}",
+                    txid, output.vout, output.dest
+                );
+                return Ok(DepositRelayOutcome::SkippedByPolicy);
+            }
+        }
+
+        let required = self.confirmations_required_for(output.sigset_index);
+        if confirmations < required {
+            debug!(
+                "Deferring deposit until {} confirmations: {}:{}",
+                required, txid, output.vout
+            );
+            return Ok(DepositRelayOutcome::AwaitingConfirmations);
+        }
+
         let dest = output.dest.clone();
         let vout = output.vout;
-        let contains_outpoint = app_client(&self.app_client_addr)
-            .query(|app| app.bitcoin.processed_outpoints.contains(outpoint))
-            .await?;
+        let sigset_index = output.sigset_index;
 
         let deposit_address = bitcoin::Address::from_script(
             &tx.output.get(vout as usize).unwrap().script_pubkey,
-            super::NETWORK,
+            self.network,
         )?;
 
         if let Some(receiver_addr) = dest.to_receiver_addr() {
             if contains_outpoint {
                 let mut index = index.lock().await;
                 index.remove_deposit(receiver_addr, deposit_address, txid, vout)?;
-                return Ok(());
+                return Ok(DepositRelayOutcome::AlreadyProcessed);
             }
 
             let mut index_guard = index.lock().await;
@@ -914,63 +2058,197 @@ impl Relayer {
             );
         }
 
-        let proof_bytes = self
-            .btc_client()
-            .await
-            .get_tx_out_proof(&[tx.txid()], Some(block_hash))
-            .await?;
-        let proof = ::bitcoin::MerkleBlock::consensus_decode(&mut proof_bytes.as_slice())?.txn;
+        let block_hash = block.block_hash();
+        let proof = match local_merkle_proof(block, txid) {
+            Some(proof) => proof,
+            None => {
+                let proof_bytes = self
+                    .timed_rpc("get_tx_out_proof", async {
+                        self.btc_client()
+                            .await
+                            .get_tx_out_proof(&[txid], Some(&block_hash))
+                            .await
+                    })
+                    .await?;
+                ::bitcoin::MerkleBlock::consensus_decode(&mut proof_bytes.as_slice())?.txn
+            }
+        };
 
-        {
-            let mut tx_bytes = vec![];
-            tx.consensus_encode(&mut tx_bytes)?;
-            let tx = ::bitcoin::Transaction::consensus_decode(&mut tx_bytes.as_slice())?;
-            let tx = Adapter::new(tx);
-            let proof = Adapter::new(proof);
+        if self.verify_proofs {
+            let header = self.sidechain_header(height).await?;
+            verify_deposit_against_sidechain(&proof, txid, block_hash, height, &header)?;
+        }
 
-            let res = app_client(&self.app_client_addr)
-                .call(
-                    move |app| {
-                        build_call!(app.relay_deposit(
-                            tx,
-                            height,
-                            proof,
-                            output.vout,
-                            output.sigset_index,
-                            output.dest
-                        ))
-                    },
-                    |app| build_call!(app.app_noop()),
-                )
-                .await;
+        let mut tx_bytes = vec![];
+        tx.consensus_encode(&mut tx_bytes)?;
+        let tx = ::bitcoin::Transaction::consensus_decode(&mut tx_bytes.as_slice())?;
+
+        self.submit_deposit(PendingDeposit {
+            tx,
+            height,
+            proof,
+            vout,
+            sigset_index,
+            dest,
+        })
+        .await
+    }
 
-            match res {
-                Err(err)
-                    if err.to_string().contains("Deposit amount is below minimum")
-                        || err
-                            .to_string()
-                            .contains("Deposit amount is too small to pay its spending fee") =>
-                {
-                    return Ok(());
-                }
-                _ => res?,
-            };
+    /// Submits a deposit and its proof to the app. If the deposit write-ahead
+    /// log is enabled, the deposit is recorded in it until the app has
+    /// handled it, so it can be resumed if the relayer stops mid-relay.
+    async fn submit_deposit(&self, pending: PendingDeposit) -> Result<DepositRelayOutcome> {
+        if let Some(wal) = &self.deposit_wal {
+            wal.lock().unwrap().record(&pending)?;
+        }
+
+        let PendingDeposit {
+            tx,
+            height,
+            proof,
+            vout,
+            sigset_index,
+            dest,
+        } = pending.clone();
+        let res = app_client(&self.app_client_addr)
+            .call(
+                move |app| {
+                    build_call!(app.relay_deposit(
+                        Adapter::new(tx),
+                        height,
+                        Adapter::new(proof),
+                        vout,
+                        sigset_index,
+                        dest
+                    ))
+                },
+                |app| build_call!(app.app_noop()),
+            )
+            .await;
+
+        let outcome = match res {
+            Ok(_) => DepositRelayOutcome::Relayed,
+            Err(err) => match classify_deposit_error(&err.to_string()) {
+                Some(outcome) => outcome,
+                None => return Err(err.into()),
+            },
+        };
+
+        self.settle_deposit(&pending, outcome)
+    }
+
+    /// Records the outcome of submitting a deposit to the app, once the app
+    /// will not need it submitted again.
+    fn settle_deposit(
+        &self,
+        pending: &PendingDeposit,
+        outcome: DepositRelayOutcome,
+    ) -> Result<DepositRelayOutcome> {
+        let txid = pending.tx.txid();
+        let vout = pending.vout;
+        if let Some(wal) = &self.deposit_wal {
+            wal.lock().unwrap().remove(txid, vout)?;
+        }
+
+        match outcome {
+            DepositRelayOutcome::BelowMinimum => return Ok(outcome),
+            DepositRelayOutcome::AlreadyProcessed => {
+                // another relayer won the race to relay this deposit
+                debug!("Deposit already relayed:\n\ttxid={}\n\tvout={}", txid, vout);
+                self.record_relayed_outpoint(pending)?;
+                return Ok(outcome);
+            }
+            _ => {}
         }
 
+        let sats = pending.tx.output[vout as usize].value;
         info!(
-            "Relayed deposit: {} sats, {}",
-            tx.output[vout as usize].value,
-            dest.to_string(),
+            "Relayed deposit:\n\ttxid={}\n\tvout={}\n\tsats={}\n\tdest={}",
+            txid, vout, sats, pending.dest,
         );
+        self.status.record_deposit(txid, vout, sats);
+        self.record_relayed_outpoint(pending)?;
+        self.emit(RelayerEvent::DepositRelayed(RelayedDeposit {
+            txid,
+            vout,
+            amount: sats,
+            height: pending.height,
+            dest: pending.dest.clone(),
+            sigset_index: pending.sigset_index,
+        }));
+
+        Ok(outcome)
+    }
+
+    fn record_relayed_outpoint(&self, pending: &PendingDeposit) -> Result<()> {
+        if let Some(relayed) = &self.relayed_outpoints {
+            let mut relayed = relayed.lock().unwrap();
+            relayed.insert(
+                pending.tx.txid(),
+                pending.vout,
+                pending.sigset_index,
+                pending.height,
+            )?;
+            self.status.record_relayed_outpoints(relayed.len());
+        }
+
+        Ok(())
+    }
+
+    /// Drops relayed outpoints in blocks too deep below `tip_height` to be
+    /// rescanned, since the app has long since settled them, to keep the
+    /// cache from growing over the life of the relayer.
+    fn prune_relayed_outpoints(&self, tip_height: u32) -> Result<()> {
+        if let Some(relayed) = &self.relayed_outpoints {
+            let window = MAX_RESCAN_WINDOW.max(self.min_rescan_window) as u32;
+            let mut relayed = relayed.lock().unwrap();
+            relayed.prune_below_height(tip_height.saturating_sub(window))?;
+            self.status.record_relayed_outpoints(relayed.len());
+        }
+
+        Ok(())
+    }
+
+    /// Resubmits the deposits left in the write-ahead log by a previous run
+    /// which stopped mid-relay, dropping any the app has since processed.
+    async fn resume_pending_deposits(&self) -> Result<()> {
+        let Some(wal) = &self.deposit_wal else {
+            return Ok(());
+        };
+        let pending = wal.lock().unwrap().pending();
+        if pending.is_empty() {
+            return Ok(());
+        }
+        info!("Resuming {} pending deposit relays", pending.len());
+
+        let outpoints = pending
+            .iter()
+            .map(|deposit| (deposit.tx.txid().into_inner(), deposit.vout))
+            .collect();
+        let processed = self.processed_outpoints(outpoints).await?;
+
+        for deposit in pending {
+            let (txid, vout) = (deposit.tx.txid(), deposit.vout);
+            if processed.contains(&(txid.into_inner(), vout)) {
+                wal.lock().unwrap().remove(txid, vout)?;
+                continue;
+            }
+
+            if let Err(err) = self.submit_deposit(deposit).await {
+                warn!("Failed to resume deposit relay {}:{}: {}", txid, vout, err);
+            }
+        }
 
         Ok(())
     }
 
+    /// Relays the next batch of headers from the full node's tip, returning a
+    /// report of the headers relayed, or `None` if there were none to relay.
     async fn relay_header_batch(
         &mut self,
         fullnode_hash: BlockHash,
         sidechain_hash: BlockHash,
-    ) -> Result<()> {
+    ) -> Result<Option<HeaderRelayReport>> {
         let fullnode_info = self
             .btc_client()
             .await
@@ -983,12 +2261,31 @@ impl Relayer {
             .await?;
 
         if fullnode_info.height < sidechain_info.height {
-            // full node is still syncing
-            return Ok(());
+            info!(
+                "Full node is behind the sidechain, waiting for it to sync:\n\tfullnode_height={}\n\tsidechain_height={}",
+                fullnode_info.height, sidechain_info.height,
+            );
+            tokio::time::sleep(FULL_NODE_SYNC_DELAY).await;
+            return Ok(None);
         }
 
         let start = self.common_ancestor(fullnode_hash, sidechain_hash).await?;
+        let reorg_depth = (sidechain_info.height - start.height) as u64;
+        if reorg_depth > 0 {
+            let max_reorg_depth = app_client(&self.app_client_addr)
+                .query(|app| Ok(app.bitcoin.headers.len()))
+                .await?;
+            check_reorg_depth(reorg_depth, max_reorg_depth)?;
+        }
+
         let batch = self.get_header_batch(start.hash).await?;
+        let last_height = match batch.last() {
+            Some(header) => header.height(),
+            None => return Ok(None),
+        };
+        check_batch_connects(&batch, start.hash)?;
+        let batch_len = batch.len();
+        let first_height = batch[0].height();
 
         info!(
             "Relaying headers...\n\thash={}\n\theight={}\n\tbatch_len={}",
@@ -996,21 +2293,42 @@ impl Relayer {
             batch[0].height(),
             batch.len(),
         );
-        let res = app_client(&self.app_client_addr)
-            .call(
-                move |app| build_call!(app.bitcoin.headers.add(batch.clone().into())),
-                |app| build_call!(app.app_noop()),
-            )
-            .await;
+        for chunk in header_chunks(batch, self.max_headers_per_add) {
+            let res = app_client(&self.app_client_addr)
+                .call(
+                    move |app| build_call!(app.bitcoin.headers.add(chunk.clone().into())),
+                    |app| build_call!(app.app_noop()),
+                )
+                .await;
+
+            if res.is_err() {
+                // another relayer may have already brought the sidechain up to
+                // the full node's tip
+                if self.sidechain_block_hash().await? == fullnode_hash {
+                    break;
+                }
+                res?;
+            }
+        }
 
         let current_tip = self.sidechain_block_hash().await?;
         if current_tip == fullnode_hash {
             info!("Relayed headers");
-        } else {
-            res?;
         }
 
-        Ok(())
+        self.record_header_relay(batch_len);
+        self.last_relayed_header_height = Some(last_height);
+        if let Some(path) = &self.header_height_path {
+            store_header_height(path, last_height)?;
+        }
+
+        Ok(Some(HeaderRelayReport {
+            from_height: first_height,
+            to_height: last_height,
+            count: batch_len,
+            new_tip: current_tip,
+            fullnode_height: fullnode_info.height as u32,
+        }))
     }
 
     async fn get_header_batch(&self, from_hash: BlockHash) -> Result<Vec<WrappedHeader>> {
@@ -1020,8 +2338,8 @@ impl Relayer {
             .get_block_header_info(&from_hash)
             .await?;
 
-        let mut headers = Vec::with_capacity(HEADER_BATCH_SIZE);
-        for _ in 0..HEADER_BATCH_SIZE {
+        let mut headers = Vec::with_capacity(self.header_batch_size);
+        for _ in 0..self.header_batch_size {
             match cursor.next_block_hash {
                 Some(next_hash) => {
                     cursor = self
@@ -1034,9 +2352,9 @@ impl Relayer {
             };
 
             let header = self
-                .btc_client()
-                .await
-                .get_block_header(&cursor.hash)
+                .timed_rpc("get_block_header", async {
+                    self.btc_client().await.get_block_header(&cursor.hash).await
+                })
                 .await?;
             let mut header_bytes = vec![];
             header.consensus_encode(&mut header_bytes).unwrap();
@@ -1051,303 +2369,3581 @@ impl Relayer {
         Ok(headers)
     }
 
+    /// Finds the most recent common ancestor of two blocks. Blocks on the full
+    /// node's main chain are resolved from their heights, so headers are only
+    /// walked back one at a time on branches off the main chain.
     async fn common_ancestor(&self, a: BlockHash, b: BlockHash) -> Result<GetBlockHeaderResult> {
-        let mut a = self.btc_client().await.get_block_header_info(&a).await?;
-        let mut b = self.btc_client().await.get_block_header_info(&b).await?;
+        let mut a = self.block_header_info(&a).await?;
+        let mut b = self.block_header_info(&b).await?;
 
         while a != b {
-            if a.height > b.height && (b.confirmations - 1) as usize == a.height - b.height {
-                return Ok(b);
-            } else if b.height > a.height && (a.confirmations - 1) as usize == b.height - a.height {
-                return Ok(a);
-            } else if a.height > b.height {
+            if let Some(ancestor) = main_chain_ancestor(&a, &b) {
+                return Ok(ancestor.clone());
+            }
+
+            // walk back the side which is off the main chain, or the higher one
+            let a_main = a.confirmations > 0;
+            let b_main = b.confirmations > 0;
+            let step_a = if a_main != b_main {
+                !a_main
+            } else {
+                a.height > b.height
+            };
+            if step_a {
                 let prev = a.previous_block_hash.unwrap();
-                a = self.btc_client().await.get_block_header_info(&prev).await?;
+                a = self.block_header_info(&prev).await?;
             } else {
                 let prev = b.previous_block_hash.unwrap();
-                b = self.btc_client().await.get_block_header_info(&prev).await?;
+                b = self.block_header_info(&prev).await?;
             }
         }
 
         Ok(a)
     }
+
+    async fn block_header_info(&self, hash: &BlockHash) -> Result<GetBlockHeaderResult> {
+        Ok(self
+            .timed_rpc("get_block_header_info", async {
+                self.btc_client().await.get_block_header_info(hash).await
+            })
+            .await?)
+    }
 }
 
-#[derive(Serialize, Deserialize, Clone)]
-pub struct DepositAddress {
-    pub sigset_index: u32,
-    pub deposit_addr: String,
+/// If both headers are on the full node's main chain as of the same tip, the
+/// lower one is their common ancestor. Headers off the main chain have
+/// negative confirmations, and headers fetched across a change of tip have
+/// inconsistent ones.
+fn main_chain_ancestor<'a>(
+    a: &'a GetBlockHeaderResult,
+    b: &'a GetBlockHeaderResult,
+) -> Option<&'a GetBlockHeaderResult> {
+    if a.confirmations <= 0 || b.confirmations <= 0 {
+        return None;
+    }
+    let a_tip = a.height + a.confirmations as usize;
+    let b_tip = b.height + b.confirmations as usize;
+    if a_tip != b_tip {
+        return None;
+    }
+
+    Some(if a.height <= b.height { a } else { b })
 }
 
-pub struct OutputMatch {
-    sigset_index: u32,
-    vout: u32,
-    dest: Dest,
+/// The number of blocks of the previous tip's branch which were orphaned when
+/// the sidechain's tip moved, if it moved to a different branch.
+fn reorg_depth(prev_height: usize, ancestor_height: usize) -> Option<usize> {
+    prev_height
+        .checked_sub(ancestor_height)
+        .filter(|depth| *depth > 0)
 }
 
-#[derive(Clone, Serialize, Deserialize, Debug)]
-pub struct RawSignatorySet {
-    pub signatories: Vec<RawSignatory>,
-    pub index: u32,
-    #[serde(rename = "bridgeFeeRate")]
-    pub bridge_fee_rate: f64,
-    #[serde(rename = "minerFeeRate")]
-    pub miner_fee_rate: f64,
-    #[serde(rename = "depositsEnabled")]
-    pub deposits_enabled: bool,
-    pub threshold: (u64, u64),
+/// The number of blocks to scan for deposits after the sidechain's tip moved
+/// from a block at `ancestor_height` (or a descendant of it, on an orphaned
+/// branch) to `tip_height`. This covers every block on the new branch and at
+/// least `min_window` blocks, but no more than [MAX_RESCAN_WINDOW] (or
+/// `min_window`, if higher), since deposits in older blocks have timed out.
+fn deposit_scan_window(ancestor_height: usize, tip_height: usize, min_window: usize) -> usize {
+    (tip_height - ancestor_height)
+        .max(min_window)
+        .min(MAX_RESCAN_WINDOW.max(min_window))
 }
 
-impl RawSignatorySet {
-    pub fn new(
-        sigset: SignatorySet,
-        bridge_fee_rate: f64,
-        miner_fee_rate: f64,
-        deposits_enabled: bool,
-    ) -> Self {
-        let signatories = sigset
-            .iter()
-            .map(|s| RawSignatory::from(s.clone()))
-            .collect();
+/// Estimates the time to relay `headers_behind` headers from samples of
+/// `(time, headers_relayed)`. The first sample only marks the start of the
+/// measured window.
+fn header_sync_eta(samples: &VecDeque<(Instant, usize)>, headers_behind: u64) -> Option<Duration> {
+    if samples.len() < MIN_RELAY_RATE_SAMPLES {
+        return None;
+    }
+    if headers_behind == 0 {
+        return Some(Duration::ZERO);
+    }
 
-        RawSignatorySet {
-            signatories,
-            index: sigset.index(),
-            bridge_fee_rate,
-            miner_fee_rate,
-            deposits_enabled,
-            // TODO: get threshold from checkpoint once it is stored in state
-            #[cfg(feature = "testnet")]
-            threshold: (9, 10),
-            #[cfg(not(feature = "testnet"))]
-            threshold: (2, 3),
-        }
+    let (start, _) = samples.front()?;
+    let (end, _) = samples.back()?;
+    let elapsed = end.duration_since(*start).as_secs_f64();
+    let relayed: usize = samples.iter().skip(1).map(|(_, count)| count).sum();
+    if elapsed <= 0.0 || relayed == 0 {
+        return None;
     }
-}
 
-#[derive(Clone, Serialize, Deserialize, Debug)]
-pub struct RawSignatory {
-    pub voting_power: u64,
-    pub pubkey: Vec<u8>,
+    let rate = relayed as f64 / elapsed;
+    Some(Duration::from_secs_f64(headers_behind as f64 / rate))
 }
 
-impl From<Signatory> for RawSignatory {
-    fn from(sig: Signatory) -> Self {
-        RawSignatory {
-            voting_power: sig.voting_power,
-            pubkey: sig.pubkey.as_slice().to_vec(),
-        }
+/// Checks that a reorg of the sidechain's headers is within the depth the app
+/// can handle, which is bounded by the number of headers it retains.
+fn check_reorg_depth(reorg_depth: u64, max_reorg_depth: u64) -> Result<()> {
+    if reorg_depth >= max_reorg_depth {
+        error!(
+            "Detected Bitcoin reorg deeper than the sidechain can process:\n\tdepth={}\n\tmax_depth={}",
+            reorg_depth, max_reorg_depth,
+        );
+        return Err(Error::Relayer(format!(
+            "Reorg exceeds app limit (depth {}, max {})",
+            reorg_depth, max_reorg_depth
+        )));
     }
+
+    Ok(())
 }
 
-/// A collection which stores all watched addresses and signatory sets, for
-/// efficiently detecting deposit output scripts.
-#[derive(Default)]
-pub struct WatchedScripts {
-    scripts: HashMap<::bitcoin::Script, (Dest, u32)>,
-    sigsets: BTreeMap<u32, (SignatorySet, Vec<Dest>)>,
+/// The earliest time a checkpoint created at `create_time` (in seconds) may
+/// advance, given the queue's minimum checkpoint interval, or `None` if it is
+/// not representable.
+fn next_checkpoint_time(create_time: u64, min_checkpoint_interval: u64) -> Option<SystemTime> {
+    let secs = create_time.checked_add(min_checkpoint_interval)?;
+    UNIX_EPOCH.checked_add(Duration::from_secs(secs))
 }
 
-impl WatchedScripts {
-    pub fn new() -> Self {
-        Default::default()
+/// Checks that the first header of a batch builds on `expected_prev`, the
+/// position on the sidechain the batch is meant to extend.
+fn check_batch_connects(batch: &[WrappedHeader], expected_prev: BlockHash) -> Result<()> {
+    match batch.first() {
+        Some(first) if first.prev_blockhash() != expected_prev => {
+            error!(
+                "Header batch does not connect to sidechain:\n\tprev_hash={}\n\texpected={}",
+                first.prev_blockhash(),
+                expected_prev,
+            );
+            Err(Error::Header("batch does not connect to tip".to_string()))
+        }
+        _ => Ok(()),
     }
+}
 
-    pub fn get(&self, script: &::bitcoin::Script) -> Option<(Dest, u32)> {
-        self.scripts.get(script).cloned()
+/// Verifies that the proof's merkle root matches `merkle_root`, returning
+/// whether `txid` is among the transactions it proves.
+fn check_merkle_proof(
+    proof: &PartialMerkleTree,
+    merkle_root: TxMerkleNode,
+    txid: Txid,
+) -> Result<bool> {
+    let mut txids = vec![];
+    let mut block_indexes = vec![];
+    let proof_merkle_root = proof
+        .extract_matches(&mut txids, &mut block_indexes)
+        .map_err(|_| Error::BitcoinMerkleBlockError)?;
+    if proof_merkle_root != merkle_root {
+        return Err(Error::BitcoinMerkleBlockError);
     }
 
-    pub fn has(&self, script: &::bitcoin::Script) -> bool {
-        self.scripts.contains_key(script)
+    Ok(txids.contains(&txid))
+}
+
+/// Builds the merkle proof of `txid`'s inclusion in `block` from the block's
+/// transactions, saving a `gettxoutproof` call to the full node. Returns `None`
+/// if the block does not contain the transaction, or if its transactions don't
+/// commit to its header's merkle root.
+fn local_merkle_proof(block: &Block, txid: Txid) -> Option<PartialMerkleTree> {
+    let txids: Vec<_> = block.txdata.iter().map(|tx| tx.txid()).collect();
+    let matches: Vec<_> = txids.iter().map(|id| *id == txid).collect();
+    if !matches.contains(&true) {
+        return None;
     }
 
-    pub fn len(&self) -> usize {
-        self.scripts.len()
+    let proof = PartialMerkleTree::from_txids(&txids, &matches);
+    match check_merkle_proof(&proof, block.header.merkle_root, txid) {
+        Ok(true) => Some(proof),
+        _ => None,
     }
+}
 
-    pub fn is_empty(&self) -> bool {
-        self.scripts.is_empty()
+/// Checks that the proof commits to `merkle_root` and includes `txid`,
+/// returning [Error::BitcoinMerkleBlockError] otherwise.
+fn verify_deposit_merkle_proof(
+    proof: &PartialMerkleTree,
+    merkle_root: TxMerkleNode,
+    txid: Txid,
+) -> Result<()> {
+    if !check_merkle_proof(proof, merkle_root, txid)? {
+        return Err(Error::BitcoinMerkleBlockError);
     }
 
-    pub fn insert(&mut self, dest: Dest, sigset: &SignatorySet) -> Result<bool> {
-        let script = self.derive_script(&dest, sigset, SIGSET_THRESHOLD)?;
+    Ok(())
+}
 
-        if self.scripts.contains_key(&script) {
-            return Ok(false);
-        }
+/// Checks that `block_hash` is the sidechain's block at `height`, i.e. that
+/// the full node and the sidechain agree on the deposit's block.
+fn check_sidechain_block(
+    block_hash: BlockHash,
+    height: u32,
+    sidechain_header: &WorkHeader,
+) -> Result<()> {
+    if sidechain_header.block_hash() != block_hash {
+        return Err(Error::Relayer(format!(
+            "Full node block {} at height {} is not the sidechain's block {}",
+            block_hash,
+            height,
+            sidechain_header.block_hash()
+        )));
+    }
 
-        self.scripts.insert(script, (dest.clone(), sigset.index()));
+    Ok(())
+}
 
-        let (_, dests) = self
-            .sigsets
-            .entry(sigset.index())
-            .or_insert((sigset.clone(), vec![]));
-        dests.push(dest);
+/// Checks a deposit's proof against the sidechain's header at the deposit's
+/// height, which must be the block the full node found the deposit in.
+fn verify_deposit_against_sidechain(
+    proof: &PartialMerkleTree,
+    txid: Txid,
+    block_hash: BlockHash,
+    height: u32,
+    sidechain_header: &WorkHeader,
+) -> Result<()> {
+    check_sidechain_block(block_hash, height, sidechain_header)?;
+    verify_deposit_merkle_proof(proof, sidechain_header.merkle_root(), txid)
+}
 
-        Ok(true)
+/// Whether the transaction spends an output of one of the given checkpoint
+/// transactions.
+fn spends_checkpoint(tx: &Transaction, checkpoint_txids: &HashSet<Txid>) -> bool {
+    tx.input
+        .iter()
+        .any(|input| checkpoint_txids.contains(&input.previous_output.txid))
+}
+
+/// The outpoints of a block's candidate deposit outputs, in the form used by
+/// the app's processed-outpoint set.
+fn deposit_outpoints(candidates: &[(&Transaction, OutputMatch)]) -> Vec<([u8; 32], u32)> {
+    candidates
+        .iter()
+        .map(|(tx, output)| (tx.txid().into_inner(), output.vout))
+        .collect()
+}
+
+/// Checks which of `outpoints` the app has already processed, making a single
+/// call to `query` with all of them.
+async fn batch_processed_outpoints<F, Fut>(
+    outpoints: Vec<Outpoint>,
+    query: F,
+) -> Result<HashSet<Outpoint>>
+where
+    F: FnOnce(LengthVec<u16, Outpoint>) -> Fut,
+    Fut: Future<Output = Result<Vec<bool>>>,
+{
+    if outpoints.is_empty() {
+        return Ok(HashSet::new());
     }
 
-    pub fn remove_expired(&mut self, max_age: u64) -> Result<()> {
-        let now = time_now();
+    let batch = outpoints.clone().try_into().map_err(|_| {
+        Error::Relayer(format!(
+            "Too many outpoints for one query: {}",
+            outpoints.len()
+        ))
+    })?;
+    let processed = query(batch).await?;
+    if processed.len() != outpoints.len() {
+        return Err(Error::Relayer(format!(
+            "Queried {} outpoints but got {} results",
+            outpoints.len(),
+            processed.len()
+        )));
+    }
 
-        for (_, (sigset, dests)) in self.sigsets.iter() {
-            if now < sigset.create_time() + max_age {
-                break;
-            }
+    Ok(outpoints
+        .into_iter()
+        .zip(processed)
+        .filter_map(|(outpoint, processed)| processed.then_some(outpoint))
+        .collect())
+}
 
-            for dest in dests {
-                let script = self.derive_script(dest, sigset, SIGSET_THRESHOLD)?; // TODO: get threshold from state
-                self.scripts.remove(&script);
-            }
+/// The full nodes a relayer uses, in order of priority, and which one is
+/// currently in use. Clones share the active node.
+#[derive(Clone)]
+struct BtcClients {
+    clients: Vec<Arc<RwLock<BitcoinRpcClient>>>,
+    active: Arc<AtomicUsize>,
+}
+
+impl BtcClients {
+    fn new(primary: BitcoinRpcClient) -> Self {
+        BtcClients {
+            clients: vec![Arc::new(RwLock::new(primary))],
+            active: Arc::new(AtomicUsize::new(0)),
         }
+    }
 
-        Ok(())
+    fn push(&mut self, client: BitcoinRpcClient) {
+        self.clients.push(Arc::new(RwLock::new(client)));
     }
 
-    fn derive_script(
-        &self,
-        dest: &Dest,
-        sigset: &SignatorySet,
-        threshold: (u64, u64),
-    ) -> Result<::bitcoin::Script> {
-        sigset.output_script(&dest.commitment_bytes()?, threshold)
+    fn len(&self) -> usize {
+        self.clients.len()
+    }
+
+    fn active_index(&self) -> usize {
+        self.active.load(Ordering::Relaxed)
+    }
+
+    fn active(&self) -> &Arc<RwLock<BitcoinRpcClient>> {
+        &self.clients[self.active_index()]
+    }
+
+    /// Switches to the next client, wrapping around to the primary, and
+    /// returns its index.
+    fn fail_over(&self) -> usize {
+        let next = (self.active_index() + 1) % self.len();
+        self.active.store(next, Ordering::Relaxed);
+        next
     }
 }
 
-use std::fs::File;
-use std::io::{self, BufRead, BufReader, Write};
-use std::path::Path;
+/// Whether the error is a failure to reach the full node, rather than an error
+/// response from it.
+fn is_rpc_connection_error(err: &Error) -> bool {
+    match err {
+        Error::BitcoinCoreRpcAsync(err) => !err.to_string().contains("RPC error response"),
+        _ => false,
+    }
+}
 
-pub struct WatchedScriptStore {
-    scripts: WatchedScripts,
-    file: File,
+/// Whether the error is the full node reporting that it doesn't know the
+/// requested block (`RPC_INVALID_ADDRESS_OR_KEY`), rather than failing to
+/// answer.
+fn is_block_not_found(err: &Error) -> bool {
+    match err {
+        Error::BitcoinCoreRpcAsync(err) => {
+            let msg = err.to_string();
+            msg.contains("RPC error response") && msg.contains("code: -5")
+        }
+        _ => false,
+    }
 }
 
-impl WatchedScriptStore {
-    pub async fn open<P: AsRef<Path>>(path: P, app_client_addr: &str) -> Result<Self> {
-        let path = path.as_ref().join("watched-addrs.csv");
+/// A least-recently-used cache of full blocks.
+struct BlockCache {
+    capacity: usize,
+    blocks: HashMap<BlockHash, Block>,
+    order: VecDeque<BlockHash>,
+}
 
-        let mut scripts = WatchedScripts::new();
-        Self::maybe_load(&path, &mut scripts, app_client_addr).await?;
+impl BlockCache {
+    fn new(capacity: usize) -> Self {
+        BlockCache {
+            capacity,
+            blocks: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
 
-        let tmp_path = path.with_file_name("watched-addrs-tmp.csv");
-        let mut tmp_file = File::create(&tmp_path)?;
-        for (addr, sigset_index) in scripts.scripts.values() {
-            Self::write(&mut tmp_file, addr, *sigset_index)?;
+    fn get(&mut self, hash: &BlockHash) -> Option<Block> {
+        let block = self.blocks.get(hash)?.clone();
+        self.touch(hash);
+        Some(block)
+    }
+
+    fn insert(&mut self, hash: BlockHash, block: Block) {
+        if self.capacity == 0 {
+            return;
         }
-        tmp_file.flush()?;
-        drop(tmp_file);
-        std::fs::rename(tmp_path, &path)?;
+        if self.blocks.insert(hash, block).is_some() {
+            self.touch(&hash);
+            return;
+        }
+
+        self.order.push_back(hash);
+        if self.order.len() > self.capacity {
+            if let Some(evicted) = self.order.pop_front() {
+                self.blocks.remove(&evicted);
+            }
+        }
+    }
+
+    /// Marks `hash` as the most recently used entry.
+    fn touch(&mut self, hash: &BlockHash) {
+        if let Some(pos) = self.order.iter().position(|h| h == hash) {
+            self.order.remove(pos);
+            self.order.push_back(*hash);
+        }
+    }
 
-        let file = File::options().append(true).create(true).open(&path)?;
+    fn contains(&self, hash: &BlockHash) -> bool {
+        self.blocks.contains_key(hash)
+    }
+}
 
-        info!("Keeping track of deposit addresses at {}", path.display());
+/// Tracks which checkpoint transactions are settled on the Bitcoin chain, so
+/// the checkpoint relay only rebroadcasts the unsettled ones. Settled txids
+/// are optionally persisted to a file, one per line.
+struct CheckpointBroadcasts {
+    settled: HashSet<Txid>,
+    rejected: HashSet<Txid>,
+    required_confs: u32,
+    path: Option<PathBuf>,
+}
 
-        Ok(WatchedScriptStore { scripts, file })
+impl CheckpointBroadcasts {
+    fn new(required_confs: u32) -> Self {
+        CheckpointBroadcasts {
+            settled: HashSet::new(),
+            rejected: HashSet::new(),
+            required_confs,
+            path: None,
+        }
     }
 
-    async fn maybe_load<P: AsRef<Path>>(
-        path: P,
-        scripts: &mut WatchedScripts,
-        app_client_addr: &str,
-    ) -> Result<()> {
-        let file = match File::open(&path) {
-            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
-            Err(e) => return Err(e.into()),
-            Ok(file) => file,
-        };
+    /// Loads the settled txids persisted in the given directory, and persists
+    /// those settled from now on to the same file.
+    fn open<P: AsRef<Path>>(&mut self, dir: P) -> Result<()> {
+        let path = dir.as_ref().join(SETTLED_CHECKPOINTS_FILE);
 
-        let mut sigsets = BTreeMap::new();
-        app_client(app_client_addr)
-            .query(|app| {
-                for (index, checkpoint) in app.bitcoin.checkpoints.all()? {
-                    sigsets.insert(index, checkpoint.sigset.clone());
+        match File::open(&path) {
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+            Ok(file) => {
+                for line in BufReader::new(file).lines() {
+                    let line = line?;
+                    match line.parse() {
+                        Ok(txid) => {
+                            self.settled.insert(txid);
+                        }
+                        Err(_) => warn!("Skipping corrupt settled checkpoint: {:?}", line),
+                    }
                 }
-                Ok(())
-            })
-            .await?;
+            }
+        }
 
-        let lines = BufReader::new(file).lines();
-        for line in lines {
-            let line = line?;
-            let items: Vec<_> = line.split(',').collect();
+        self.path = Some(path);
+        Ok(())
+    }
 
-            let sigset_index: u32 = items[1]
-                .parse()
-                .map_err(|_| orga::Error::App("Could not parse sigset index".to_string()))?;
-            let sigset = match sigsets.get(&sigset_index) {
-                Some(sigset) => sigset,
-                None => continue,
-            };
+    fn is_settled(&self, txid: &Txid) -> bool {
+        self.settled.contains(txid)
+    }
 
-            let dest = Dest::from_base64(items[0])?;
+    /// Whether a transaction with the given number of confirmations should be
+    /// (re)broadcast, settling it if it has enough confirmations.
+    fn needs_broadcast(&mut self, txid: Txid, confirmations: Option<u32>) -> bool {
+        if confirmations.unwrap_or(0) >= self.required_confs {
+            self.settle(txid);
+        }
+        !self.is_settled(&txid)
+    }
 
-            scripts.insert(dest, sigset)?;
+    /// Marks a transaction as settled, so it is no longer rebroadcast.
+    fn settle(&mut self, txid: Txid) {
+        if !self.settled.insert(txid) {
+            return;
         }
-        let max_age = app_client(app_client_addr)
-            .query(|app| Ok(app.bitcoin.checkpoints.config.max_age))
-            .await?;
+        if let Err(err) = self.persist(txid) {
+            warn!("Failed to persist settled checkpoint {}: {}", txid, err);
+        }
+    }
 
-        scripts.remove_expired(max_age)?;
+    /// Stops rebroadcasting a transaction for the rest of this run only, e.g.
+    /// in dry run mode where it was never broadcast.
+    fn skip(&mut self, txid: Txid) {
+        self.settled.insert(txid);
+    }
 
-        info!("Loaded {} deposit addresses", scripts.len());
+    fn persist(&self, txid: Txid) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
 
+        let mut file = File::options().append(true).create(true).open(path)?;
+        writeln!(file, "{}", txid)?;
+        file.flush()?;
         Ok(())
     }
 
-    pub fn insert(&mut self, dest: Dest, sigset: &SignatorySet) -> Result<()> {
-        if self.scripts.insert(dest.clone(), sigset)? {
-            Self::write(&mut self.file, &dest, sigset.index())?;
+    /// Records that a transaction was rejected as invalid, returning whether
+    /// this is the first such rejection. Rejected transactions are still
+    /// rebroadcast, since they may be re-signed without changing their txid.
+    fn reject(&mut self, txid: Txid) -> bool {
+        self.rejected.insert(txid)
+    }
+}
+
+/// How the checkpoint relay treats an error from broadcasting a transaction.
+#[derive(Debug, PartialEq, Eq)]
+enum BroadcastError {
+    /// The transaction or a conflicting one has been mined.
+    Settled,
+    /// The transaction can never be accepted as-is, e.g. because of a bad
+    /// signature.
+    Invalid,
+    /// Any other error, which may resolve on retry.
+    Transient,
+}
+
+fn classify_broadcast_error(msg: &str) -> BroadcastError {
+    const SETTLED: [&str; 2] = [
+        "bad-txns-inputs-missingorspent",
+        "Transaction already in block chain",
+    ];
+    // covers both mandatory-script-verify-flag-failed and
+    // non-mandatory-script-verify-flag
+    const INVALID: [&str; 2] = ["script-verify-flag", "scriptsig-not-pushonly"];
+
+    if SETTLED.iter().any(|s| msg.contains(s)) {
+        BroadcastError::Settled
+    } else if INVALID.iter().any(|s| msg.contains(s)) {
+        BroadcastError::Invalid
+    } else {
+        BroadcastError::Transient
+    }
+}
+
+/// Classifies an app error from relaying a deposit which means the deposit
+/// should not be submitted again, rather than retried.
+fn classify_deposit_error(msg: &str) -> Option<DepositRelayOutcome> {
+    const BELOW_MINIMUM: [&str; 2] = [
+        "Deposit amount is below minimum",
+        "Deposit amount is too small to pay its spending fee",
+    ];
+    // returned when another relayer relayed the deposit first
+    const ALREADY_RELAYED: &str = "Output has already been relayed";
+
+    if BELOW_MINIMUM.iter().any(|s| msg.contains(s)) {
+        Some(DepositRelayOutcome::BelowMinimum)
+    } else if msg.contains(ALREADY_RELAYED) {
+        Some(DepositRelayOutcome::AlreadyProcessed)
+    } else {
+        None
+    }
+}
+
+/// Tracks how long a relay loop waits before retrying after an error, growing
+/// the delay exponentially across consecutive failures.
+#[derive(Clone, Debug)]
+struct Backoff {
+    base: Duration,
+    max: Duration,
+    multiplier: f64,
+    current: Duration,
+}
+
+impl Backoff {
+    fn new(base: Duration, max: Duration, multiplier: f64) -> Self {
+        let max = max.max(base);
+        Backoff {
+            base,
+            max,
+            multiplier: multiplier.max(1.0),
+            current: base,
         }
+    }
 
-        Ok(())
+    /// Returns the delay before the next attempt, given how long the failed
+    /// attempt ran for. The relay loops only return on error, so an attempt
+    /// which ran for longer than the maximum delay is treated as having
+    /// succeeded for a while, and the delay is reset before backing off.
+    fn after_attempt(&mut self, attempt_duration: Duration) -> Duration {
+        if attempt_duration >= self.max {
+            self.reset();
+        }
+        self.next_delay()
     }
 
-    fn write(file: &mut File, dest: &Dest, sigset_index: u32) -> Result<()> {
-        writeln!(file, "{},{}", dest.to_base64()?, sigset_index)?;
-        file.flush()?;
-        Ok(())
+    /// Returns the delay before the next retry, with jitter, and grows it for
+    /// the following one.
+    fn next_delay(&mut self) -> Duration {
+        let delay = self.current;
+        self.current = self.current.mul_f64(self.multiplier).min(self.max);
+        delay.mul_f64(rand::thread_rng().gen_range(0.5..=1.0))
+    }
+
+    fn reset(&mut self) {
+        self.current = self.base;
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::utils::test_bitcoin_client;
-    use bitcoincore_rpc_async::RpcApi as RpcApiAsync;
-    use bitcoind::BitcoinD;
+/// Tracks how long to wait between polls while nothing is changing, doubling
+/// the interval on each idle poll up to a cap.
+struct IdleBackoff {
+    base: Duration,
+    max: Duration,
+    current: Duration,
+}
 
-    #[tokio::test]
-    async fn relayer_fetch_batch() {
-        let bitcoind = BitcoinD::new(bitcoind::downloaded_exe_path().unwrap()).unwrap();
-        let rpc_url = bitcoind.rpc_url();
-        let cookie_file = bitcoind.params.cookie_file.clone();
-        let btc_client = test_bitcoin_client(rpc_url.clone(), cookie_file.clone()).await;
+impl IdleBackoff {
+    fn new(base: Duration, max: Duration) -> Self {
+        IdleBackoff {
+            base,
+            max,
+            current: base,
+        }
+    }
 
-        let address = btc_client.get_new_address(None, None).await.unwrap();
-        btc_client.generate_to_address(30, &address).await.unwrap();
+    /// Returns the interval to wait after an idle poll, and grows it for the
+    /// next one.
+    fn idle(&mut self) -> Duration {
+        let interval = self.current;
+        self.current = (self.current * 2).min(self.max);
+        interval
+    }
 
-        let relayer_client = test_bitcoin_client(rpc_url.clone(), cookie_file.clone()).await;
+    /// Resets the interval after a poll which observed a change.
+    fn reset(&mut self) {
+        self.current = self.base;
+    }
+}
 
-        btc_client.generate_to_address(25, &address).await.unwrap();
-        let relayer = Relayer::new(relayer_client, "http://localhost:26657".to_string());
+/// Latency statistics for an RPC method.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LatencyStats {
+    pub p50: Duration,
+    pub p95: Duration,
+    pub max: Duration,
+}
 
-        let block_hash = btc_client.get_block_hash(30).await.unwrap();
-        let headers = relayer.get_header_batch(block_hash).await.unwrap();
+/// The most recent latencies recorded for an RPC method.
+#[derive(Default)]
+struct LatencySamples(VecDeque<Duration>);
 
-        assert_eq!(headers.len(), 25);
+impl LatencySamples {
+    fn record(&mut self, latency: Duration) {
+        self.0.push_back(latency);
+        if self.0.len() > MAX_LATENCY_SAMPLES {
+            self.0.pop_front();
+        }
+    }
 
-        for (i, header) in headers.iter().enumerate() {
-            let height = 31 + i;
-            let btc_hash = btc_client.get_block_hash(height as u64).await.unwrap();
-            let btc_header = btc_client.get_block_header(&btc_hash).await.unwrap();
+    fn stats(&self) -> Option<LatencyStats> {
+        let mut sorted: Vec<_> = self.0.iter().copied().collect();
+        sorted.sort();
+        let max = *sorted.last()?;
+        // nearest-rank percentile
+        let percentile = |p: usize| sorted[(sorted.len() * p).div_ceil(100).max(1) - 1];
+
+        Some(LatencyStats {
+            p50: percentile(50),
+            p95: percentile(95),
+            max,
+        })
+    }
+}
 
-            assert_eq!(header.block_hash(), btc_header.block_hash());
-            assert_eq!(header.bits(), btc_header.bits);
-            assert_eq!(header.target(), btc_header.target());
-            assert_eq!(header.work(), btc_header.work());
+/// Splits a batch of headers into chunks no larger than `max_len`, each of
+/// which can be submitted to the app in a single call.
+fn header_chunks(batch: Vec<WrappedHeader>, max_len: usize) -> Vec<Vec<WrappedHeader>> {
+    batch
+        .chunks(max_len.max(1))
+        .map(|chunk| chunk.to_vec())
+        .collect()
+}
+
+/// Decides whether a full node which does not have the sidechain's tip is
+/// usable: this is only acceptable if the full node is still syncing up to the
+/// sidechain's height.
+/// Checks that the chain name reported by the full node's `getblockchaininfo`
+/// is for the given network.
+fn check_full_node_network(chain: &str, network: bitcoin::Network) -> Result<()> {
+    let fullnode_network = match chain {
+        "main" => bitcoin::Network::Bitcoin,
+        "test" => bitcoin::Network::Testnet,
+        "signet" => bitcoin::Network::Signet,
+        "regtest" => bitcoin::Network::Regtest,
+        _ => {
+            return Err(Error::Relayer(format!(
+                "Full node is on unknown chain {:?}",
+                chain
+            )))
+        }
+    };
+    if fullnode_network != network {
+        return Err(Error::Relayer(format!(
+            "Full node is on {} but the relayer is configured for {}",
+            fullnode_network, network
+        )));
+    }
+
+    Ok(())
+}
+
+fn check_full_node_chain(
+    sidechain_hash: BlockHash,
+    sidechain_height: u32,
+    fullnode_height: u64,
+) -> Result<()> {
+    if fullnode_height < sidechain_height as u64 {
+        warn!(
+            "Full node is still syncing, has not yet reached sidechain tip:\n\thash={}\n\theight={}",
+            sidechain_hash, sidechain_height
+        );
+        return Ok(());
+    }
+
+    Err(Error::Relayer(format!(
+        "Full node does not have sidechain tip {} at height {} (full node height {}), is it on the correct network?",
+        sidechain_hash, sidechain_height, fullnode_height
+    )))
+}
+
+fn load_header_height<P: AsRef<Path>>(path: P) -> Result<Option<u32>> {
+    let contents = match std::fs::read_to_string(path) {
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+        Ok(contents) => contents,
+    };
+
+    let height = contents
+        .trim()
+        .parse()
+        .map_err(|_| Error::Relayer(format!("Could not parse header height: {}", contents)))?;
+
+    Ok(Some(height))
+}
+
+/// Writes the current time to a heartbeat file, updating its modification
+/// time.
+fn touch_heartbeat<P: AsRef<Path>>(path: P) -> Result<()> {
+    std::fs::write(path, time_now().to_string())?;
+    Ok(())
+}
+
+fn store_header_height<P: AsRef<Path>>(path: P, height: u32) -> Result<()> {
+    let path = path.as_ref();
+    let tmp_path = path.with_extension("tmp");
+    std::fs::write(&tmp_path, height.to_string())?;
+    std::fs::rename(tmp_path, path)?;
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct DepositAddress {
+    pub sigset_index: u32,
+    pub deposit_addr: String,
+}
+
+pub struct OutputMatch {
+    sigset_index: u32,
+    vout: u32,
+    dest: Dest,
+    memo: Option<String>,
+}
+
+/// The prefix identifying an OP_RETURN output's data as a deposit memo. The
+/// rest of the pushed data is the memo itself, as UTF-8.
+pub const DEPOSIT_MEMO_PREFIX: &[u8] = b"nomic:";
+
+/// Extracts the memo from the first OP_RETURN output of the transaction which
+/// carries one, in the form `OP_RETURN <DEPOSIT_MEMO_PREFIX || utf8 memo>`.
+/// Malformed or unrecognized OP_RETURN outputs are ignored.
+fn deposit_memo(tx: &Transaction) -> Option<String> {
+    use bitcoin::blockdata::opcodes::all::OP_RETURN;
+    use bitcoin::blockdata::script::Instruction;
+
+    tx.output.iter().find_map(|output| {
+        let mut instructions = output.script_pubkey.instructions();
+        match instructions.next()? {
+            Ok(Instruction::Op(OP_RETURN)) => {}
+            _ => return None,
+        }
+        let data = match instructions.next()? {
+            Ok(Instruction::PushBytes(data)) => data,
+            _ => return None,
+        };
+        if instructions.next().is_some() {
+            return None;
+        }
+
+        let memo = data.strip_prefix(DEPOSIT_MEMO_PREFIX)?;
+        String::from_utf8(memo.to_vec()).ok()
+    })
+}
+
+/// The status of a broadcast checkpoint transaction on the Bitcoin network.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CheckpointTxStatus {
+    /// The transaction is in the full node's mempool.
+    InMempool,
+    /// The transaction is included in a block at the given height.
+    Confirmed { height: u32 },
+    /// The transaction is neither in the mempool nor confirmed.
+    Dropped,
+}
+
+impl CheckpointTxStatus {
+    fn new(in_mempool: bool, confirmed_height: Option<u32>) -> Self {
+        match (in_mempool, confirmed_height) {
+            (true, _) => CheckpointTxStatus::InMempool,
+            (false, Some(height)) => CheckpointTxStatus::Confirmed { height },
+            (false, None) => CheckpointTxStatus::Dropped,
         }
     }
+}
+
+/// The result of attempting to relay a detected deposit output.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DepositRelayOutcome {
+    /// The deposit was relayed to the sidechain.
+    Relayed,
+    /// The sidechain had already processed the deposit.
+    AlreadyProcessed,
+    /// The deposit was too small to be accepted by the sidechain.
+    BelowMinimum,
+    /// The deposit's destination was rejected by the relayer's destination
+    /// policy.
+    SkippedByPolicy,
+    /// The deposit was manually marked as processed by the operator.
+    MarkedProcessed,
+    /// The deposit does not yet have the confirmations required by the
+    /// relayer's confirmation policy.
+    AwaitingConfirmations,
+}
+
+/// The results of a scan for deposits.
+#[derive(Debug)]
+pub struct DepositScan {
+    /// The sidechain's Bitcoin tip at the time of the scan.
+    pub tip: BlockHash,
+    /// The inclusive range of block heights scanned.
+    pub range: (u32, u32),
+    /// The number of watched outputs found in the scanned blocks.
+    pub matched_outputs: usize,
+    /// The outcome of each deposit which was handled without error.
+    pub outcomes: Vec<(Txid, u32, DepositRelayOutcome)>,
+    /// The deposits which could not be relayed, with the error encountered.
+    pub failures: Vec<(Txid, u32, String)>,
+}
+
+impl DepositScan {
+    fn new(tip: BlockHash, tip_height: u32, num_blocks: u32) -> Self {
+        DepositScan {
+            tip,
+            range: ((tip_height + 1).saturating_sub(num_blocks), tip_height),
+            matched_outputs: 0,
+            outcomes: vec![],
+            failures: vec![],
+        }
+    }
+
+    fn record(&mut self, txid: Txid, vout: u32, res: Result<DepositRelayOutcome>) {
+        let outcome = match res {
+            Ok(outcome) => outcome,
+            Err(err) => match classify_deposit_error(&err.to_string()) {
+                Some(outcome) => outcome,
+                None => {
+                    warn!("Skipping deposit for error: {}", err);
+                    self.failures.push((txid, vout, err.to_string()));
+                    return;
+                }
+            },
+        };
+
+        self.outcomes.push((txid, vout, outcome));
+    }
+}
+
+/// A batch of headers relayed to the sidechain.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HeaderRelayReport {
+    /// The height of the first header in the batch.
+    pub from_height: u32,
+    /// The height of the last header in the batch.
+    pub to_height: u32,
+    /// The number of headers in the batch.
+    pub count: usize,
+    /// The sidechain's Bitcoin tip after relaying the batch.
+    pub new_tip: BlockHash,
+    /// The height of the full node's tip when the batch was fetched.
+    pub fullnode_height: u32,
+}
+
+impl HeaderRelayReport {
+    /// The estimated number of blocks the sidechain is behind the full node
+    /// after this batch.
+    pub fn blocks_behind(&self) -> u32 {
+        self.fullnode_height.saturating_sub(self.to_height)
+    }
+}
+
+/// Relay progress recorded by one or more relayers, for status reporting.
+#[derive(Clone, Default)]
+pub struct StatusTracker(Arc<std::sync::Mutex<RelayProgress>>);
+
+impl StatusTracker {
+    pub fn progress(&self) -> RelayProgress {
+        self.0.lock().unwrap().clone()
+    }
+
+    fn record_header_relay(&self) {
+        self.0.lock().unwrap().last_header_relay_at = Some(time_now());
+    }
+
+    fn record_watched_scripts(&self, count: usize) {
+        self.0.lock().unwrap().watched_scripts = Some(count);
+    }
+
+    fn record_relayed_outpoints(&self, count: usize) {
+        self.0.lock().unwrap().relayed_outpoints = Some(count);
+    }
+
+    fn record_deposit(&self, txid: Txid, vout: u32, sats: u64) {
+        self.0.lock().unwrap().last_deposit = Some(RelayedDepositStatus {
+            txid,
+            vout,
+            sats,
+            relayed_at: time_now(),
+        });
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct RelayProgress {
+    /// The time, in seconds, at which the header relay last relayed headers or
+    /// found the sidechain up-to-date.
+    pub last_header_relay_at: Option<u64>,
+    /// The number of deposit scripts being watched, once known.
+    pub watched_scripts: Option<usize>,
+    /// The number of outpoints in the local cache of relayed deposits, once
+    /// loaded.
+    pub relayed_outpoints: Option<usize>,
+    pub last_deposit: Option<RelayedDepositStatus>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct RelayedDepositStatus {
+    pub txid: Txid,
+    pub vout: u32,
+    pub sats: u64,
+    pub relayed_at: u64,
+}
+
+/// The report served by [Relayer::serve_status]. Tips are omitted if they
+/// could not be fetched.
+#[derive(Clone, Debug, Serialize)]
+pub struct RelayerStatus {
+    pub sidechain_tip: Option<TipStatus>,
+    pub fullnode_tip: Option<TipStatus>,
+    #[serde(flatten)]
+    pub progress: RelayProgress,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct TipStatus {
+    pub height: u32,
+    pub hash: BlockHash,
+}
+
+async fn sidechain_tip(app_client_addr: &str) -> Result<TipStatus> {
+    let (height, hash) = app_client(app_client_addr)
+        .query(|app| Ok((app.bitcoin.headers.height()?, app.bitcoin.headers.hash()?)))
+        .await?;
+    Ok(TipStatus {
+        height,
+        hash: BlockHash::from_slice(hash.as_slice())?,
+    })
+}
+
+async fn fullnode_tip(btc_client: &BtcClients) -> Result<TipStatus> {
+    let btc_client = btc_client.active().clone();
+    let btc_client = btc_client.read().await;
+    let hash = btc_client.get_best_block_hash().await?;
+    let height = btc_client.get_block_header_info(&hash).await?.height as u32;
+    Ok(TipStatus { height, hash })
+}
+
+/// Parses a deposit address, rejecting addresses for another network than the
+/// relayer's.
+fn parse_network_address(addr: &str, network: bitcoin::Network) -> Result<bitcoin::Address> {
+    let address: bitcoin::Address = addr
+        .parse()
+        .map_err(|_| Error::Address(format!("Invalid deposit address: {}", addr)))?;
+    if !address.is_valid_for_network(network) {
+        return Err(Error::Address(format!(
+            "Deposit address {} is not a {} address",
+            addr, network
+        )));
+    }
+
+    Ok(address)
+}
+
+/// Runs `f` on each of `items` concurrently, with at most as many calls in
+/// flight as `limit` has permits. Results are in the order of `items`.
+async fn with_permits<T, R, F, Fut>(limit: &Semaphore, items: Vec<T>, f: F) -> Vec<R>
+where
+    F: Fn(T) -> Fut,
+    Fut: Future<Output = R>,
+{
+    futures::future::join_all(items.into_iter().map(|item| {
+        let fut = f(item);
+        async move {
+            let _permit = limit.acquire().await;
+            fut.await
+        }
+    }))
+    .await
+}
+
+/// A deposit to an address derivable from a known destination and sigset, but
+/// never announced to the relayer. See [Relayer::find_unannounced_deposits].
+#[derive(Clone, Debug)]
+pub struct UnannouncedDeposit {
+    pub txid: Txid,
+    pub vout: u32,
+    pub amount: u64,
+    pub dest: Dest,
+    pub sigset_index: u32,
+    pub height: u32,
+}
+
+/// Finds the outputs of `block` paying to one of the `candidates` scripts.
+fn match_unannounced(
+    block: &Block,
+    height: u32,
+    candidates: &HashMap<::bitcoin::Script, (Dest, u32)>,
+) -> Vec<UnannouncedDeposit> {
+    let mut found = vec![];
+    for tx in block.txdata.iter() {
+        for (vout, output) in tx.output.iter().enumerate() {
+            if let Some((dest, sigset_index)) = candidates.get(&output.script_pubkey) {
+                found.push(UnannouncedDeposit {
+                    txid: tx.txid(),
+                    vout: vout as u32,
+                    amount: output.value,
+                    dest: dest.clone(),
+                    sigset_index: *sigset_index,
+                    height,
+                });
+            }
+        }
+    }
+
+    found
+}
+
+/// An event emitted by the relayer, for consumers such as analytics or
+/// monitoring tools.
+#[derive(Clone, Debug)]
+pub enum RelayerEvent {
+    /// A watched deposit output was found in a block, whether or not it ends
+    /// up being relayed.
+    DepositDetected(DepositInfo),
+    /// A checkpoint transaction was rejected by the full node as invalid, which
+    /// likely means the signatory set's signatures are malformed. Emitted once
+    /// per transaction.
+    CheckpointRejected { txid: Txid, reason: String },
+    /// A deposit was relayed to the sidechain by this relayer.
+    DepositRelayed(RelayedDeposit),
+}
+
+/// A deposit relayed to the sidechain, as reported by
+/// [RelayerEvent::DepositRelayed].
+#[derive(Clone, Debug)]
+pub struct RelayedDeposit {
+    pub txid: Txid,
+    pub vout: u32,
+    pub amount: u64,
+    pub height: u32,
+    pub dest: Dest,
+    pub sigset_index: u32,
+}
+
+/// Information about a deposit output detected by the relayer.
+#[derive(Clone, Debug)]
+pub struct DepositInfo {
+    pub txid: Txid,
+    pub vout: u32,
+    pub amount: u64,
+    pub dest: Dest,
+    pub sigset_index: u32,
+    pub height: u32,
+    pub confirmations: u32,
+    pub memo: Option<String>,
+}
+
+impl DepositInfo {
+    fn new(tx: &Transaction, output: &OutputMatch, height: u32, tip_height: u32) -> Self {
+        DepositInfo {
+            txid: tx.txid(),
+            vout: output.vout,
+            amount: tx.output[output.vout as usize].value,
+            dest: output.dest.clone(),
+            sigset_index: output.sigset_index,
+            height,
+            confirmations: tip_height.saturating_sub(height) + 1,
+            memo: output.memo.clone(),
+        }
+    }
+}
+
+fn emit_event(sender: Option<&Sender<RelayerEvent>>, event: RelayerEvent) {
+    let Some(sender) = sender else {
+        return;
+    };
+
+    match sender.try_send(event) {
+        Ok(()) => {}
+        Err(TrySendError::Full(event)) => {
+            warn!("Relayer event channel is full, dropping event: {:?}", event)
+        }
+        Err(TrySendError::Closed(_)) => {}
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct RawSignatorySet {
+    pub signatories: Vec<RawSignatory>,
+    pub index: u32,
+    #[serde(rename = "bridgeFeeRate")]
+    pub bridge_fee_rate: f64,
+    #[serde(rename = "minerFeeRate")]
+    pub miner_fee_rate: f64,
+    #[serde(rename = "depositsEnabled")]
+    pub deposits_enabled: bool,
+    pub threshold: (u64, u64),
+}
+
+impl RawSignatorySet {
+    pub fn new(
+        sigset: SignatorySet,
+        bridge_fee_rate: f64,
+        miner_fee_rate: f64,
+        deposits_enabled: bool,
+    ) -> Self {
+        let signatories = sigset
+            .iter()
+            .map(|s| RawSignatory::from(s.clone()))
+            .collect();
+
+        RawSignatorySet {
+            signatories,
+            index: sigset.index(),
+            bridge_fee_rate,
+            miner_fee_rate,
+            deposits_enabled,
+            // TODO: get threshold from checkpoint once it is stored in state
+            #[cfg(feature = "testnet")]
+            threshold: (9, 10),
+            #[cfg(not(feature = "testnet"))]
+            threshold: (2, 3),
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct RawSignatory {
+    pub voting_power: u64,
+    pub pubkey: Vec<u8>,
+}
+
+impl From<Signatory> for RawSignatory {
+    fn from(sig: Signatory) -> Self {
+        RawSignatory {
+            voting_power: sig.voting_power,
+            pubkey: sig.pubkey.as_slice().to_vec(),
+        }
+    }
+}
+
+/// A collection which stores all watched addresses and signatory sets, for
+/// efficiently detecting deposit output scripts.
+pub struct WatchedScripts {
+    scripts: HashMap<::bitcoin::Script, (Dest, u32)>,
+    sigsets: BTreeMap<u32, (SignatorySet, Vec<Dest>)>,
+    network: bitcoin::Network,
+}
+
+impl Default for WatchedScripts {
+    fn default() -> Self {
+        Self::for_network(super::NETWORK)
+    }
+}
+
+impl WatchedScripts {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Creates an empty collection whose deposit addresses are for the given
+    /// network.
+    pub fn for_network(network: bitcoin::Network) -> Self {
+        WatchedScripts {
+            scripts: HashMap::new(),
+            sigsets: BTreeMap::new(),
+            network,
+        }
+    }
+
+    pub fn get(&self, script: &::bitcoin::Script) -> Option<(Dest, u32)> {
+        self.scripts.get(script).cloned()
+    }
+
+    pub fn has(&self, script: &::bitcoin::Script) -> bool {
+        self.scripts.contains_key(script)
+    }
+
+    pub fn len(&self) -> usize {
+        self.scripts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scripts.is_empty()
+    }
+
+    pub fn insert(&mut self, dest: Dest, sigset: &SignatorySet) -> Result<bool> {
+        let script = self.derive_script(&dest, sigset, SIGSET_THRESHOLD)?;
+
+        if self.scripts.contains_key(&script) {
+            return Ok(false);
+        }
+        if bitcoin::Address::from_script(&script, self.network).is_err() {
+            return Err(Error::Relayer(format!(
+                "Deposit script for {} under sigset {} has no {} address",
+                dest,
+                sigset.index(),
+                self.network
+            )));
+        }
+
+        self.scripts.insert(script, (dest.clone(), sigset.index()));
+
+        let (_, dests) = self
+            .sigsets
+            .entry(sigset.index())
+            .or_insert((sigset.clone(), vec![]));
+        dests.push(dest);
+
+        Ok(true)
+    }
+
+    pub fn remove_expired(&mut self, max_age: u64) -> Result<()> {
+        let now = time_now();
+
+        let expired: Vec<_> = self
+            .sigsets
+            .iter()
+            .filter(|(_, (sigset, _))| now >= sigset.create_time() + max_age)
+            .map(|(index, _)| *index)
+            .collect();
+
+        for index in expired {
+            let (sigset, dests) = self.sigsets.remove(&index).unwrap();
+            for dest in dests.iter() {
+                let script = self.derive_script(dest, &sigset, SIGSET_THRESHOLD)?; // TODO: get threshold from state
+                self.scripts.remove(&script);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Derives the scripts for every known destination under each of the given
+    /// sigsets, keeping those which are not already watched.
+    pub fn unannounced_scripts(
+        &self,
+        sigsets: &BTreeMap<u32, SignatorySet>,
+    ) -> Result<HashMap<::bitcoin::Script, (Dest, u32)>> {
+        let mut dests = HashMap::new();
+        for (dest, _) in self.scripts.values() {
+            dests.insert(dest.commitment_bytes()?, dest);
+        }
+
+        let mut scripts = HashMap::new();
+        for (index, sigset) in sigsets {
+            for dest in dests.values() {
+                let script = self.derive_script(dest, sigset, SIGSET_THRESHOLD)?;
+                if !self.has(&script) {
+                    scripts.insert(script, ((*dest).clone(), *index));
+                }
+            }
+        }
+
+        Ok(scripts)
+    }
+
+    /// Iterates over the watched scripts, with the deposit address of each
+    /// and the index of the sigset it was derived from.
+    pub fn iter_scripts(
+        &self,
+    ) -> impl Iterator<Item = (&::bitcoin::Script, bitcoin::Address, u32)> + '_ {
+        self.scripts.iter().filter_map(|(script, (_, index))| {
+            let address = bitcoin::Address::from_script(script, self.network).ok()?;
+            Some((script, address, *index))
+        })
+    }
+
+    /// The deposit addresses watched for the given sigset, sorted, or `None`
+    /// if no scripts are watched for it.
+    pub fn addresses_for_sigset(&self, index: u32) -> Option<Vec<bitcoin::Address>> {
+        let mut addresses: Vec<_> = self
+            .iter_scripts()
+            .filter(|(_, _, sigset_index)| *sigset_index == index)
+            .map(|(_, address, _)| address)
+            .collect();
+        if addresses.is_empty() {
+            return None;
+        }
+        addresses.sort_by_key(|address| address.to_string());
+
+        Some(addresses)
+    }
+
+    /// Every watched deposit address with the index of its sigset, ordered by
+    /// sigset index then address, for diagnostics.
+    pub fn export(&self) -> Vec<(bitcoin::Address, u32)> {
+        let mut addresses: Vec<_> = self
+            .iter_scripts()
+            .map(|(_, address, index)| (address, index))
+            .collect();
+        addresses.sort_by_cached_key(|(address, index)| (*index, address.to_string()));
+
+        addresses
+    }
+
+    /// Writes a report of every watched script, ordered like
+    /// [WatchedScripts::export]: its deposit address, destination, sigset
+    /// index, and the time after which deposits to it are no longer accepted,
+    /// given the app's maximum deposit age.
+    pub fn dump<W: Write>(&self, mut writer: W, max_deposit_age: u64) -> Result<()> {
+        let mut entries: Vec<_> = self.iter_scripts().collect();
+        entries.sort_by_cached_key(|(_, address, index)| (*index, address.to_string()));
+
+        writeln!(writer, "{} watched scripts", entries.len())?;
+        for (script, address, index) in entries {
+            let (dest, _) = &self.scripts[script];
+            let deposit_timeout = self
+                .sigsets
+                .get(&index)
+                .map(|(sigset, _)| sigset.create_time() + max_deposit_age);
+            writeln!(
+                writer,
+                "{}\n\tdest={}\n\tsigset_index={}\n\tdeposit_timeout={}",
+                address,
+                dest,
+                index,
+                deposit_timeout.map_or("unknown".to_string(), |t| t.to_string()),
+            )?;
+        }
+
+        Ok(writer.flush()?)
+    }
+
+    /// The index of the oldest sigset which still has watched scripts.
+    pub fn oldest_sigset(&self) -> Option<u32> {
+        self.scripts.values().map(|(_, index)| *index).min()
+    }
+
+    /// Whether every sigset referenced by the watched scripts is present, and
+    /// identical, in the given set of the app's sigsets.
+    pub fn is_consistent_with(&self, sigsets: &BTreeMap<u32, SignatorySet>) -> bool {
+        self.sigsets
+            .iter()
+            .all(|(index, (sigset, _))| sigsets.get(index) == Some(sigset))
+    }
+
+    fn derive_script(
+        &self,
+        dest: &Dest,
+        sigset: &SignatorySet,
+        threshold: (u64, u64),
+    ) -> Result<::bitcoin::Script> {
+        sigset.output_script(&dest.commitment_bytes()?, threshold)
+    }
+}
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+const RELAYED_OUTPOINTS_FILE: &str = "relayed-outpoints.csv";
+
+const SETTLED_CHECKPOINTS_FILE: &str = "settled-checkpoints.txt";
+
+/// An on-disk record of the deposit outpoints this relayer has relayed, so
+/// rescans after a restart don't need to query the app for each of them. Each
+/// outpoint is stored with its sigset index and the height of its block.
+struct RelayedOutpoints {
+    outpoints: HashMap<(Txid, u32), (u32, u32)>,
+    path: PathBuf,
+}
+
+impl RelayedOutpoints {
+    fn open<P: AsRef<Path>>(dir: P) -> Result<Self> {
+        let path = dir.as_ref().join(RELAYED_OUTPOINTS_FILE);
+        let mut outpoints = HashMap::new();
+
+        match File::open(&path) {
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+            Ok(file) => {
+                for line in BufReader::new(file).lines() {
+                    let line = line?;
+                    match Self::parse_line(&line) {
+                        Some((outpoint, entry)) => {
+                            outpoints.insert(outpoint, entry);
+                        }
+                        None => warn!("Skipping corrupt relayed outpoint: {:?}", line),
+                    }
+                }
+            }
+        }
+
+        Ok(RelayedOutpoints { outpoints, path })
+    }
+
+    /// Parses a `txid,vout,sigset_index,height` line.
+    fn parse_line(line: &str) -> Option<((Txid, u32), (u32, u32))> {
+        let mut items = line.split(',');
+        let txid = items.next()?.parse().ok()?;
+        let vout = items.next()?.parse().ok()?;
+        let sigset_index = items.next()?.parse().ok()?;
+        let height = items.next()?.parse().ok()?;
+        if items.next().is_some() {
+            return None;
+        }
+
+        Some(((txid, vout), (sigset_index, height)))
+    }
+
+    fn format_line(txid: &Txid, vout: u32, sigset_index: u32, height: u32) -> String {
+        format!("{},{},{},{}", txid, vout, sigset_index, height)
+    }
+
+    fn contains(&self, txid: Txid, vout: u32) -> bool {
+        self.outpoints.contains_key(&(txid, vout))
+    }
+
+    fn len(&self) -> usize {
+        self.outpoints.len()
+    }
+
+    fn insert(&mut self, txid: Txid, vout: u32, sigset_index: u32, height: u32) -> Result<()> {
+        if self
+            .outpoints
+            .insert((txid, vout), (sigset_index, height))
+            .is_some()
+        {
+            return Ok(());
+        }
+
+        let mut file = File::options().append(true).create(true).open(&self.path)?;
+        writeln!(
+            file,
+            "{}",
+            Self::format_line(&txid, vout, sigset_index, height)
+        )?;
+        file.flush()?;
+        Ok(())
+    }
+
+    /// Removes outpoints of sigsets older than `oldest_sigset`, whose deposits
+    /// can no longer be relayed, and rewrites the file.
+    fn prune(&mut self, oldest_sigset: u32) -> Result<()> {
+        self.retain(|(sigset_index, _)| *sigset_index >= oldest_sigset)
+    }
+
+    /// Removes outpoints in blocks below `min_height`, which are too deep to
+    /// be rescanned, and rewrites the file.
+    fn prune_below_height(&mut self, min_height: u32) -> Result<()> {
+        self.retain(|(_, height)| *height >= min_height)
+    }
+
+    fn retain<F>(&mut self, mut keep: F) -> Result<()>
+    where
+        F: FnMut(&(u32, u32)) -> bool,
+    {
+        let len = self.outpoints.len();
+        self.outpoints.retain(|_, entry| keep(entry));
+        if self.outpoints.len() == len {
+            return Ok(());
+        }
+
+        let tmp_path = self.path.with_extension("tmp");
+        let mut tmp_file = File::create(&tmp_path)?;
+        for ((txid, vout), (sigset_index, height)) in self.outpoints.iter() {
+            writeln!(
+                tmp_file,
+                "{}",
+                Self::format_line(txid, *vout, *sigset_index, *height)
+            )?;
+        }
+        tmp_file.flush()?;
+        drop(tmp_file);
+        std::fs::rename(tmp_path, &self.path)?;
+
+        Ok(())
+    }
+}
+
+const DEPOSIT_WAL_FILE: &str = "pending-deposits.csv";
+
+/// A deposit whose proof has been fetched, to be submitted to the app.
+#[derive(Clone, Debug)]
+struct PendingDeposit {
+    tx: Transaction,
+    height: u32,
+    proof: PartialMerkleTree,
+    vout: u32,
+    sigset_index: u32,
+    dest: Dest,
+}
+
+impl PendingDeposit {
+    fn to_line(&self) -> Result<String> {
+        Ok(format!(
+            "{},{},{},{},{},{}",
+            bitcoin::consensus::encode::serialize_hex(&self.tx),
+            self.vout,
+            self.height,
+            self.sigset_index,
+            self.dest.to_base64()?,
+            bitcoin::consensus::encode::serialize_hex(&self.proof),
+        ))
+    }
+
+    fn from_line(line: &str) -> Option<Self> {
+        let items: Vec<_> = line.split(',').collect();
+        let [tx, vout, height, sigset_index, dest, proof] = items.as_slice() else {
+            return None;
+        };
+
+        Some(PendingDeposit {
+            tx: bitcoin::consensus::encode::deserialize(&hex::decode(tx).ok()?).ok()?,
+            vout: vout.parse().ok()?,
+            height: height.parse().ok()?,
+            sigset_index: sigset_index.parse().ok()?,
+            dest: Dest::from_base64(dest).ok()?,
+            proof: bitcoin::consensus::encode::deserialize(&hex::decode(proof).ok()?).ok()?,
+        })
+    }
+}
+
+/// A write-ahead log of the deposits being submitted to the app, so that
+/// relays interrupted by a crash can be resumed.
+struct DepositWal {
+    pending: BTreeMap<(Txid, u32), PendingDeposit>,
+    path: PathBuf,
+}
+
+impl DepositWal {
+    fn open<P: AsRef<Path>>(dir: P) -> Result<Self> {
+        let path = dir.as_ref().join(DEPOSIT_WAL_FILE);
+        let mut pending = BTreeMap::new();
+
+        match File::open(&path) {
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+            Ok(file) => {
+                for line in BufReader::new(file).lines() {
+                    let line = line?;
+                    match PendingDeposit::from_line(&line) {
+                        Some(deposit) => {
+                            pending.insert((deposit.tx.txid(), deposit.vout), deposit);
+                        }
+                        None => warn!("Skipping corrupt pending deposit: {:?}", line),
+                    }
+                }
+            }
+        }
+
+        Ok(DepositWal { pending, path })
+    }
+
+    fn pending(&self) -> Vec<PendingDeposit> {
+        self.pending.values().cloned().collect()
+    }
+
+    fn record(&mut self, deposit: &PendingDeposit) -> Result<()> {
+        let key = (deposit.tx.txid(), deposit.vout);
+        if self.pending.insert(key, deposit.clone()).is_some() {
+            return Ok(());
+        }
+
+        let mut file = File::options().append(true).create(true).open(&self.path)?;
+        writeln!(file, "{}", deposit.to_line()?)?;
+        file.flush()?;
+        file.sync_data()?;
+        Ok(())
+    }
+
+    /// Removes a deposit which the app has handled, rewriting the file.
+    fn remove(&mut self, txid: Txid, vout: u32) -> Result<()> {
+        if self.pending.remove(&(txid, vout)).is_none() {
+            return Ok(());
+        }
+
+        let tmp_path = self.path.with_extension("tmp");
+        let mut tmp_file = File::create(&tmp_path)?;
+        for deposit in self.pending.values() {
+            writeln!(tmp_file, "{}", deposit.to_line()?)?;
+        }
+        tmp_file.flush()?;
+        drop(tmp_file);
+        std::fs::rename(tmp_path, &self.path)?;
+
+        Ok(())
+    }
+}
+
+pub struct WatchedScriptStore {
+    scripts: WatchedScripts,
+    file: File,
+    path: PathBuf,
+}
+
+impl WatchedScriptStore {
+    pub async fn open<P: AsRef<Path>>(
+        path: P,
+        app_client_addr: &str,
+        network: bitcoin::Network,
+    ) -> Result<Self> {
+        let path = path.as_ref().join("watched-addrs.csv");
+        let (scripts, file) = Self::load(&path, app_client_addr, network).await?;
+
+        info!("Keeping track of deposit addresses at {}", path.display());
+
+        Ok(WatchedScriptStore {
+            scripts,
+            file,
+            path,
+        })
+    }
+
+    /// Loads the watched scripts from the file at `path`, keeping only those
+    /// for sigsets known to the app, and rewrites the file to match.
+    async fn load(
+        path: &Path,
+        app_client_addr: &str,
+        network: bitcoin::Network,
+    ) -> Result<(WatchedScripts, File)> {
+        let mut scripts = WatchedScripts::for_network(network);
+        Self::maybe_load(path, &mut scripts, app_client_addr).await?;
+
+        let tmp_path = path.with_file_name("watched-addrs-tmp.csv");
+        let mut tmp_file = File::create(&tmp_path)?;
+        for (addr, sigset_index) in scripts.scripts.values() {
+            Self::write(&mut tmp_file, addr, *sigset_index)?;
+        }
+        tmp_file.sync_all()?;
+        drop(tmp_file);
+        std::fs::rename(tmp_path, path)?;
+
+        let file = File::options().append(true).create(true).open(path)?;
+
+        Ok((scripts, file))
+    }
+
+    async fn app_sigsets(app_client_addr: &str) -> Result<BTreeMap<u32, SignatorySet>> {
+        let mut sigsets = BTreeMap::new();
+        app_client(app_client_addr)
+            .query(|app| {
+                for (index, checkpoint) in app.bitcoin.checkpoints.all()? {
+                    sigsets.insert(index, checkpoint.sigset.clone());
+                }
+                Ok(())
+            })
+            .await?;
+
+        Ok(sigsets)
+    }
+
+    async fn maybe_load<P: AsRef<Path>>(
+        path: P,
+        scripts: &mut WatchedScripts,
+        app_client_addr: &str,
+    ) -> Result<()> {
+        let contents = match std::fs::read_to_string(&path) {
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e.into()),
+            Ok(contents) => contents,
+        };
+
+        let sigsets = Self::app_sigsets(app_client_addr).await?;
+
+        for (dest, sigset_index) in Self::parse_contents(&contents) {
+            let sigset = match sigsets.get(&sigset_index) {
+                Some(sigset) => sigset,
+                None => continue,
+            };
+
+            if let Err(err) = scripts.insert(dest, sigset) {
+                warn!("Skipping watched address: {}", err);
+            }
+        }
+        let max_age = app_client(app_client_addr)
+            .query(|app| Ok(app.bitcoin.checkpoints.config.max_age))
+            .await?;
+
+        scripts.remove_expired(max_age)?;
+
+        info!("Loaded {} deposit addresses", scripts.len());
+
+        Ok(())
+    }
+
+    /// Parses the entries of the store's file, skipping corrupt lines and a
+    /// final line missing its newline, which may have been cut short by an
+    /// interrupted write (e.g. leaving a truncated sigset index).
+    fn parse_contents(contents: &str) -> Vec<(Dest, u32)> {
+        let mut entries = vec![];
+        for (i, line) in contents.split_inclusive('\n').enumerate() {
+            let line = match line.strip_suffix('\n') {
+                Some(line) => line,
+                None => {
+                    warn!("Skipping incomplete watched address on line {}", i + 1);
+                    continue;
+                }
+            };
+            match Self::parse_line(i + 1, line) {
+                Ok(entry) => entries.push(entry),
+                Err(err) => warn!("Skipping corrupt watched address: {}", err),
+            }
+        }
+
+        entries
+    }
+
+    /// Parses a `<base64 dest>,<sigset index>` line of the store's file, with
+    /// errors naming the 1-based line number and (truncated) line content.
+    fn parse_line(line_no: usize, line: &str) -> Result<(Dest, u32)> {
+        let err = |msg: &str| {
+            let mut snippet: String = line.chars().take(64).collect();
+            if snippet.len() < line.len() {
+                snippet.push_str("...");
+            }
+            orga::Error::App(format!("{} on line {}: {:?}", msg, line_no, snippet))
+        };
+
+        let (dest, sigset_index) = line
+            .split_once(',')
+            .ok_or_else(|| err("Missing sigset index"))?;
+        let sigset_index = sigset_index
+            .parse()
+            .map_err(|_| err("Could not parse sigset index"))?;
+        let dest = Dest::from_base64(dest).map_err(|_| err("Could not parse address"))?;
+
+        Ok((dest, sigset_index))
+    }
+
+    /// Checks whether the loaded scripts' sigsets still match the app's, which
+    /// may not be the case after e.g. a chain rollback.
+    pub async fn is_consistent(&self, app_client_addr: &str) -> Result<bool> {
+        let sigsets = Self::app_sigsets(app_client_addr).await?;
+        Ok(self.scripts.is_consistent_with(&sigsets))
+    }
+
+    /// Discards the loaded scripts and re-derives them from the store's file
+    /// against the app's current sigsets.
+    pub async fn rebuild(&mut self, app_client_addr: &str) -> Result<()> {
+        let (scripts, file) = Self::load(&self.path, app_client_addr, self.scripts.network).await?;
+        self.scripts = scripts;
+        self.file = file;
+
+        Ok(())
+    }
+
+    pub fn insert(&mut self, dest: Dest, sigset: &SignatorySet) -> Result<()> {
+        if self.scripts.insert(dest.clone(), sigset)? {
+            Self::write(&mut self.file, &dest, sigset.index())?;
+        }
+
+        Ok(())
+    }
+
+    /// Flushes any buffered writes and syncs the file to disk.
+    pub fn flush(&mut self) -> Result<()> {
+        self.file.flush()?;
+        self.file.sync_data()?;
+        Ok(())
+    }
+
+    fn write(file: &mut File, dest: &Dest, sigset_index: u32) -> Result<()> {
+        // write each line with a single call so that entries are never
+        // interleaved, and sync so that they survive a crash
+        let line = format!("{},{}\n", dest.to_base64()?, sigset_index);
+        file.write_all(line.as_bytes())?;
+        file.sync_data()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_bitcoin_client;
+    use bitcoincore_rpc_async::RpcApi as RpcApiAsync;
+    use bitcoind::BitcoinD;
+
+    #[tokio::test]
+    async fn relayer_fetch_batch() {
+        let bitcoind = BitcoinD::new(bitcoind::downloaded_exe_path().unwrap()).unwrap();
+        let rpc_url = bitcoind.rpc_url();
+        let cookie_file = bitcoind.params.cookie_file.clone();
+        let btc_client = test_bitcoin_client(rpc_url.clone(), cookie_file.clone()).await;
+
+        let address = btc_client.get_new_address(None, None).await.unwrap();
+        btc_client.generate_to_address(30, &address).await.unwrap();
+
+        let relayer_client = test_bitcoin_client(rpc_url.clone(), cookie_file.clone()).await;
+
+        btc_client.generate_to_address(25, &address).await.unwrap();
+        let relayer = Relayer::new(relayer_client, "http://localhost:26657".to_string());
+
+        let block_hash = btc_client.get_block_hash(30).await.unwrap();
+        let headers = relayer.get_header_batch(block_hash).await.unwrap();
+
+        assert_eq!(headers.len(), 25);
+
+        for (i, header) in headers.iter().enumerate() {
+            let height = 31 + i;
+            let btc_hash = btc_client.get_block_hash(height as u64).await.unwrap();
+            let btc_header = btc_client.get_block_header(&btc_hash).await.unwrap();
+
+            assert_eq!(header.block_hash(), btc_header.block_hash());
+            assert_eq!(header.bits(), btc_header.bits);
+            assert_eq!(header.target(), btc_header.target());
+            assert_eq!(header.work(), btc_header.work());
+        }
+    }
+
+    fn test_deposit_tx() -> Transaction {
+        Transaction {
+            version: 2,
+            lock_time: bitcoin::PackedLockTime(0),
+            input: vec![],
+            output: vec![bitcoin::TxOut {
+                value: 100_000,
+                script_pubkey: Default::default(),
+            }],
+        }
+    }
+
+    /// A block containing [test_deposit_tx], whose header is not on any chain.
+    fn test_deposit_block() -> Block {
+        let mut block = Block {
+            header: bitcoin::BlockHeader {
+                version: 1,
+                prev_blockhash: BlockHash::all_zeros(),
+                merkle_root: TxMerkleNode::all_zeros(),
+                time: 0,
+                bits: 0,
+                nonce: 0,
+            },
+            txdata: vec![test_deposit_tx()],
+        };
+        block.header.merkle_root = block.compute_merkle_root().unwrap();
+        block
+    }
+
+    fn test_output_match(dest: Dest) -> OutputMatch {
+        OutputMatch {
+            sigset_index: 0,
+            vout: 0,
+            dest,
+            memo: None,
+        }
+    }
+
+    async fn relay_test_deposit(relayer: &Relayer, dest: Dest) -> Result<DepositRelayOutcome> {
+        relayer
+            .maybe_relay_deposit(
+                &test_deposit_tx(),
+                100,
+                DEFAULT_DEPOSIT_CONFIRMATIONS,
+                &test_deposit_block(),
+                test_output_match(dest),
+                false,
+                Arc::new(Mutex::new(DepositIndex::new())),
+            )
+            .await
+    }
+
+    /// Creates a relayer backed by the given bitcoind, with no app running at
+    /// its app address so that any app query fails.
+    async fn test_relayer(bitcoind: &BitcoinD) -> Relayer {
+        let rpc_url = bitcoind.rpc_url();
+        let cookie_file = bitcoind.params.cookie_file.clone();
+        let relayer_client = test_bitcoin_client(rpc_url, cookie_file).await;
+
+        Relayer::new(relayer_client, "http://localhost:1".to_string())
+    }
+
+    #[tokio::test]
+    async fn full_node_network_mismatch() {
+        let bitcoind = BitcoinD::new(bitcoind::downloaded_exe_path().unwrap()).unwrap();
+
+        let relayer = test_relayer(&bitcoind)
+            .await
+            .with_network(bitcoin::Network::Bitcoin);
+        let err = relayer.connect().await.err().unwrap();
+        assert!(err
+            .to_string()
+            .contains("Full node is on regtest but the relayer is configured for bitcoin"));
+
+        let relayer = test_relayer(&bitcoind)
+            .await
+            .with_network(bitcoin::Network::Regtest);
+        let chain = relayer.full_node_chain().await.unwrap();
+        check_full_node_network(&chain, relayer.network).unwrap();
+
+        assert!(check_full_node_network("main", bitcoin::Network::Bitcoin).is_ok());
+        assert!(check_full_node_network("test", bitcoin::Network::Signet).is_err());
+        assert!(check_full_node_network("unknown", bitcoin::Network::Bitcoin).is_err());
+    }
+
+    #[tokio::test]
+    async fn skip_marked_processed() {
+        let bitcoind = BitcoinD::new(bitcoind::downloaded_exe_path().unwrap()).unwrap();
+        let mut relayer = test_relayer(&bitcoind).await;
+
+        let dest = Dest::NativeAccount {
+            address: Default::default(),
+        };
+        assert!(relay_test_deposit(&relayer, dest.clone()).await.is_err());
+
+        relayer.mark_processed(test_deposit_tx().txid(), 0);
+        let outcome = relay_test_deposit(&relayer, dest).await.unwrap();
+        assert_eq!(outcome, DepositRelayOutcome::MarkedProcessed);
+    }
+
+    #[tokio::test]
+    async fn wait_for_deposit() {
+        let bitcoind = BitcoinD::new(bitcoind::downloaded_exe_path().unwrap()).unwrap();
+        let mut relayer = test_relayer(&bitcoind).await;
+        let txid = test_deposit_tx().txid();
+
+        let start = Instant::now();
+        let err = relayer
+            .wait_for_deposit(txid, 0, Duration::from_millis(100))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Timed out"));
+        assert!(start.elapsed() >= Duration::from_millis(100));
+
+        relayer.mark_processed(txid, 0);
+        let outcome = relayer
+            .wait_for_deposit(txid, 0, Duration::from_millis(100))
+            .await
+            .unwrap();
+        assert_eq!(outcome, DepositRelayOutcome::MarkedProcessed);
+    }
+
+    #[tokio::test]
+    async fn confirmation_policy() {
+        let bitcoind = BitcoinD::new(bitcoind::downloaded_exe_path().unwrap()).unwrap();
+        let relayer = test_relayer(&bitcoind)
+            .await
+            .with_confirmation_policy(|sigset_index| if sigset_index >= 5 { 6 } else { 2 });
+        assert_eq!(relayer.confirmations_required_for(4), 2);
+        assert_eq!(relayer.confirmations_required_for(5), 6);
+
+        let relay = |sigset_index, confirmations| {
+            let mut output = test_output_match(Dest::RewardPool);
+            output.sigset_index = sigset_index;
+            let relayer = &relayer;
+            async move {
+                relayer
+                    .maybe_relay_deposit(
+                        &test_deposit_tx(),
+                        100,
+                        confirmations,
+                        &test_deposit_block(),
+                        output,
+                        false,
+                        Arc::new(Mutex::new(DepositIndex::new())),
+                    )
+                    .await
+            }
+        };
+
+        let outcome = relay(5, 3).await.unwrap();
+        assert_eq!(outcome, DepositRelayOutcome::AwaitingConfirmations);
+        let outcome = relay(4, 1).await.unwrap();
+        assert_eq!(outcome, DepositRelayOutcome::AwaitingConfirmations);
+
+        // passes the confirmation check, then fails without a running app
+        assert!(relay(4, 3).await.is_err());
+        assert!(relay(5, 6).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn skip_by_dest_policy() {
+        let bitcoind = BitcoinD::new(bitcoind::downloaded_exe_path().unwrap()).unwrap();
+        let relayer = test_relayer(&bitcoind)
+            .await
+            .with_dest_validator(|dest| !matches!(dest, Dest::RewardPool));
+
+        let outcome = relay_test_deposit(&relayer, Dest::RewardPool)
+            .await
+            .unwrap();
+        assert_eq!(outcome, DepositRelayOutcome::SkippedByPolicy);
+
+        // allowed destinations proceed to the (unreachable) app
+        let dest = Dest::NativeAccount {
+            address: Default::default(),
+        };
+        assert!(relay_test_deposit(&relayer, dest).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn rejection_json_body() {
+        use warp::Filter;
+
+        let filter = warp::any()
+            .and_then(|| async {
+                Err::<Json, _>(warp::reject::custom(Error::InvalidDepositAddress))
+            })
+            .recover(handle_rejection);
+
+        let res = warp::test::request().reply(&filter).await;
+        assert_eq!(res.status(), 400);
+        assert_eq!(res.headers()["content-type"], "application/json");
+
+        let body: ErrorResponse = serde_json::from_slice(res.body()).unwrap();
+        assert_eq!(body.error, "Invalid Deposit Address");
+        assert_eq!(body.code, 400);
+    }
+
+    #[tokio::test]
+    async fn warp_rejection_cause() {
+        use warp::Filter;
+
+        let rejection = warp::test::request()
+            .method("GET")
+            .filter(&warp::post())
+            .await
+            .unwrap_err();
+        let err = Error::from(rejection);
+        assert_eq!(err.status_code(), 405);
+        assert!(err.to_string().contains("MethodNotAllowed"), "{}", err);
+
+        let err = Error::from(warp::reject::not_found());
+        assert_eq!(err.status_code(), 404);
+        let err = Error::from(warp::reject::custom(Error::InvalidDepositAddress));
+        assert_eq!(err.status_code(), 400);
+        assert!(err.to_string().contains("InvalidDepositAddress"), "{}", err);
+
+        let filter = warp::post().map(warp::reply).recover(handle_rejection);
+        let res = warp::test::request().method("GET").reply(&filter).await;
+        assert_eq!(res.status(), 405);
+    }
+
+    #[test]
+    fn header_sync_eta_estimate() {
+        let start = Instant::now();
+        let mut samples = VecDeque::new();
+        samples.push_back((start, 250));
+        samples.push_back((start + Duration::from_secs(10), 250));
+        assert_eq!(header_sync_eta(&samples, 1_000), None);
+
+        samples.push_back((start + Duration::from_secs(20), 250));
+        // 500 headers in 20 seconds
+        assert_eq!(
+            header_sync_eta(&samples, 1_000),
+            Some(Duration::from_secs(40))
+        );
+        assert_eq!(header_sync_eta(&samples, 0), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn checkpoint_tx_status() {
+        assert_eq!(
+            CheckpointTxStatus::new(true, None),
+            CheckpointTxStatus::InMempool
+        );
+        assert_eq!(
+            CheckpointTxStatus::new(false, Some(123)),
+            CheckpointTxStatus::Confirmed { height: 123 }
+        );
+        assert_eq!(
+            CheckpointTxStatus::new(false, None),
+            CheckpointTxStatus::Dropped
+        );
+    }
+
+    #[tokio::test]
+    async fn batch_deposit_outpoints() {
+        let tx = test_deposit_tx();
+        let mut other_tx = test_deposit_tx();
+        other_tx.version = 1;
+
+        let candidates: Vec<_> = (0..3)
+            .map(|vout| {
+                let mut output = test_output_match(Dest::RewardPool);
+                output.vout = vout;
+                (&tx, output)
+            })
+            .chain(std::iter::once((
+                &other_tx,
+                test_output_match(Dest::RewardPool),
+            )))
+            .collect();
+
+        let outpoints = deposit_outpoints(&candidates);
+        assert_eq!(
+            outpoints,
+            vec![
+                (tx.txid().into_inner(), 0),
+                (tx.txid().into_inner(), 1),
+                (tx.txid().into_inner(), 2),
+                (other_tx.txid().into_inner(), 0),
+            ]
+        );
+
+        // the block's outpoints are checked against the app with one query
+        let app_processed: HashSet<_> = [outpoints[1], outpoints[3]].into_iter().collect();
+        let calls = AtomicUsize::new(0);
+        let processed = batch_processed_outpoints(outpoints.clone(), |batch| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            let result = batch
+                .iter()
+                .map(|outpoint| app_processed.contains(outpoint))
+                .collect();
+            async move { Ok(result) }
+        })
+        .await
+        .unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(processed, app_processed);
+
+        let processed = batch_processed_outpoints(vec![], |_| async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Ok(vec![])
+        })
+        .await
+        .unwrap();
+        assert!(processed.is_empty());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        let err = batch_processed_outpoints(outpoints, |_| async { Ok(vec![true]) })
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("got 1 results"));
+    }
+
+    #[test]
+    fn merkle_proof() {
+        let txids: Vec<_> = (1..=3).map(|i| Txid::from_inner([i; 32])).collect();
+        let proof = PartialMerkleTree::from_txids(&txids, &[false, true, false]);
+        let merkle_root = proof.extract_matches(&mut vec![], &mut vec![]).unwrap();
+
+        assert!(check_merkle_proof(&proof, merkle_root, txids[1]).unwrap());
+        assert!(!check_merkle_proof(&proof, merkle_root, txids[0]).unwrap());
+
+        // flip a byte of the first hash in the proof
+        let mut proof_bytes = vec![];
+        proof.consensus_encode(&mut proof_bytes).unwrap();
+        proof_bytes[5] ^= 1;
+        let tampered = PartialMerkleTree::consensus_decode(&mut proof_bytes.as_slice()).unwrap();
+
+        let err = check_merkle_proof(&tampered, merkle_root, txids[1]).unwrap_err();
+        assert!(matches!(err, Error::BitcoinMerkleBlockError));
+    }
+
+    #[test]
+    fn local_proof_verification() {
+        let txids: Vec<_> = (1..=4).map(|i| Txid::from_inner([i; 32])).collect();
+        let proof = PartialMerkleTree::from_txids(&txids, &[false, false, true, false]);
+        let merkle_root = proof.extract_matches(&mut vec![], &mut vec![]).unwrap();
+
+        verify_deposit_merkle_proof(&proof, merkle_root, txids[2]).unwrap();
+
+        // a valid proof which doesn't include the deposit
+        let err = verify_deposit_merkle_proof(&proof, merkle_root, txids[0]).unwrap_err();
+        assert!(matches!(err, Error::BitcoinMerkleBlockError));
+
+        // a proof whose hashes were tampered with by the full node
+        let mut proof_bytes = vec![];
+        proof.consensus_encode(&mut proof_bytes).unwrap();
+        let last = proof_bytes.len() - 3;
+        proof_bytes[last] ^= 0xff;
+        let tampered = PartialMerkleTree::consensus_decode(&mut proof_bytes.as_slice()).unwrap();
+        let err = verify_deposit_merkle_proof(&tampered, merkle_root, txids[2]).unwrap_err();
+        assert!(matches!(err, Error::BitcoinMerkleBlockError));
+
+        // a proof for a different block
+        let other_root = TxMerkleNode::from_inner([0; 32]);
+        let err = verify_deposit_merkle_proof(&proof, other_root, txids[2]).unwrap_err();
+        assert!(matches!(err, Error::BitcoinMerkleBlockError));
+    }
+
+    #[test]
+    fn sidechain_proof_verification() {
+        use bitcoin::util::uint::Uint256;
+
+        let work_header = |merkle_root, nonce| {
+            let header = bitcoin::BlockHeader {
+                version: 1,
+                prev_blockhash: BlockHash::all_zeros(),
+                merkle_root,
+                time: 0,
+                bits: 0,
+                nonce,
+            };
+            WorkHeader::new(
+                WrappedHeader::from_header(&header, 100),
+                Uint256::from_u64(0).unwrap(),
+            )
+        };
+
+        let txids: Vec<_> = (1..=4).map(|i| Txid::from_inner([i; 32])).collect();
+        let proof = PartialMerkleTree::from_txids(&txids, &[false, false, true, false]);
+        let merkle_root = proof.extract_matches(&mut vec![], &mut vec![]).unwrap();
+        let sidechain_header = work_header(merkle_root, 0);
+        let block_hash = sidechain_header.block_hash();
+
+        verify_deposit_against_sidechain(&proof, txids[2], block_hash, 100, &sidechain_header)
+            .unwrap();
+
+        // a full node on a fork finds the deposit in a block the sidechain
+        // doesn't have, even though the node's proof matches its own header
+        let forked_header = work_header(merkle_root, 1);
+        let err = verify_deposit_against_sidechain(
+            &proof,
+            txids[2],
+            forked_header.block_hash(),
+            100,
+            &sidechain_header,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("is not the sidechain's block"));
+
+        // a lying full node serves a proof for a made-up set of transactions
+        // under the sidechain's block hash, which would have matched a header
+        // fetched from the same node
+        let fake_txids = vec![txids[2], Txid::from_inner([9; 32])];
+        let fake_proof = PartialMerkleTree::from_txids(&fake_txids, &[true, false]);
+        let err = verify_deposit_against_sidechain(
+            &fake_proof,
+            txids[2],
+            block_hash,
+            100,
+            &sidechain_header,
+        )
+        .unwrap_err();
+        assert!(matches!(err, Error::BitcoinMerkleBlockError));
+    }
+
+    #[test]
+    fn checkpoint_funded_deposit() {
+        let checkpoint_tx = test_deposit_tx();
+        let checkpoint_txids: HashSet<_> = vec![checkpoint_tx.txid()].into_iter().collect();
+
+        let mut tx = test_deposit_tx();
+        assert!(!spends_checkpoint(&tx, &checkpoint_txids));
+
+        tx.input.push(bitcoin::TxIn {
+            previous_output: bitcoin::OutPoint::new(Txid::from_inner([1; 32]), 0),
+            ..Default::default()
+        });
+        assert!(!spends_checkpoint(&tx, &checkpoint_txids));
+
+        tx.input.push(bitcoin::TxIn {
+            previous_output: bitcoin::OutPoint::new(checkpoint_tx.txid(), 1),
+            ..Default::default()
+        });
+        assert!(spends_checkpoint(&tx, &checkpoint_txids));
+        assert!(!spends_checkpoint(&tx, &HashSet::new()));
+    }
+
+    #[test]
+    fn inconsistent_watched_scripts() {
+        let mut scripts = WatchedScripts::new();
+        assert!(scripts.is_consistent_with(&BTreeMap::new()));
+
+        scripts
+            .sigsets
+            .insert(0, (SignatorySet::default(), vec![Dest::RewardPool]));
+        assert!(!scripts.is_consistent_with(&BTreeMap::new()));
+
+        let mut app_sigsets = BTreeMap::new();
+        app_sigsets.insert(0, SignatorySet::default());
+        assert!(scripts.is_consistent_with(&app_sigsets));
+
+        let mut sigset = SignatorySet::default();
+        sigset.create_time = 123;
+        app_sigsets.insert(0, sigset);
+        assert!(!scripts.is_consistent_with(&app_sigsets));
+    }
+
+    fn test_sigset(index: u32, create_time: u64) -> SignatorySet {
+        let secret = bitcoin::secp256k1::SecretKey::from_slice(&[index as u8 + 1; 32]).unwrap();
+        let pubkey: crate::bitcoin::threshold_sig::Pubkey =
+            bitcoin::secp256k1::PublicKey::from_secret_key(
+                &bitcoin::secp256k1::Secp256k1::new(),
+                &secret,
+            )
+            .into();
+
+        SignatorySet {
+            index,
+            create_time,
+            present_vp: 100,
+            possible_vp: 100,
+            signatories: vec![Signatory {
+                pubkey,
+                voting_power: 100,
+            }],
+        }
+    }
+
+    #[test]
+    fn unannounced_deposits() {
+        let now = time_now();
+        let sigsets: BTreeMap<_, _> = (0..2).map(|i| (i, test_sigset(i, now))).collect();
+        let dest = Dest::Bitcoin {
+            data: Adapter::new(bitcoin::Script::new_op_return(&[1])),
+        };
+
+        let mut scripts = WatchedScripts::new();
+        assert!(scripts.unannounced_scripts(&sigsets).unwrap().is_empty());
+
+        // the destination was only announced for sigset 0
+        scripts.insert(dest.clone(), &sigsets[&0]).unwrap();
+        let announced = sigsets[&0]
+            .output_script(&dest.commitment_bytes().unwrap(), SIGSET_THRESHOLD)
+            .unwrap();
+        let unannounced = sigsets[&1]
+            .output_script(&dest.commitment_bytes().unwrap(), SIGSET_THRESHOLD)
+            .unwrap();
+        let candidates = scripts.unannounced_scripts(&sigsets).unwrap();
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[&unannounced].1, 1);
+        assert!(!candidates.contains_key(&announced));
+
+        let tx = bitcoin::Transaction {
+            version: 2,
+            lock_time: bitcoin::PackedLockTime(0),
+            input: vec![],
+            output: vec![
+                bitcoin::TxOut {
+                    value: 10_000,
+                    script_pubkey: announced,
+                },
+                bitcoin::TxOut {
+                    value: 20_000,
+                    script_pubkey: unannounced,
+                },
+            ],
+        };
+        let block = Block {
+            header: bitcoin::BlockHeader {
+                version: 1,
+                prev_blockhash: BlockHash::all_zeros(),
+                merkle_root: bitcoin::TxMerkleNode::all_zeros(),
+                time: 0,
+                bits: 0x207fffff,
+                nonce: 0,
+            },
+            txdata: vec![tx.clone()],
+        };
+
+        let found = match_unannounced(&block, 100, &candidates);
+        assert_eq!(found.len(), 1);
+        assert_eq!((found[0].txid, found[0].vout), (tx.txid(), 1));
+        assert_eq!(found[0].amount, 20_000);
+        assert_eq!(found[0].sigset_index, 1);
+        assert_eq!(found[0].height, 100);
+        assert!(matches!(found[0].dest, Dest::Bitcoin { .. }));
+    }
+
+    #[test]
+    fn watched_script_export() {
+        let now = time_now();
+        let mut scripts = WatchedScripts::new();
+        assert!(scripts.export().is_empty());
+        assert!(scripts.addresses_for_sigset(0).is_none());
+
+        let dests: Vec<_> = (0..3)
+            .map(|i| Dest::Bitcoin {
+                data: Adapter::new(bitcoin::Script::new_op_return(&[i])),
+            })
+            .collect();
+        let sigsets: Vec<_> = (0..2).map(|i| test_sigset(i, now)).collect();
+        for dest in dests.iter() {
+            scripts.insert(dest.clone(), &sigsets[0]).unwrap();
+        }
+        scripts.insert(dests[0].clone(), &sigsets[1]).unwrap();
+
+        let address = |dest: &Dest, sigset: &SignatorySet| {
+            let script = sigset
+                .output_script(&dest.commitment_bytes().unwrap(), SIGSET_THRESHOLD)
+                .unwrap();
+            bitcoin::Address::from_script(&script, crate::bitcoin::NETWORK).unwrap()
+        };
+        let expected = address(&dests[0], &sigsets[1]);
+        assert_eq!(
+            scripts.addresses_for_sigset(1),
+            Some(vec![expected.clone()])
+        );
+        let for_sigset_0 = scripts.addresses_for_sigset(0).unwrap();
+        assert_eq!(for_sigset_0.len(), 3);
+        for dest in dests.iter() {
+            assert!(for_sigset_0.contains(&address(dest, &sigsets[0])));
+        }
+        assert!(scripts.addresses_for_sigset(2).is_none());
+
+        let exported = scripts.export();
+        assert_eq!(exported.len(), 4);
+        assert_eq!(exported.last().unwrap(), &(expected, 1));
+        assert!(exported[..3].iter().all(|(_, index)| *index == 0));
+
+        for (script, address, index) in scripts.iter_scripts() {
+            assert_eq!(&address.script_pubkey(), script);
+            assert_eq!(scripts.get(script).unwrap().1, index);
+        }
+    }
+
+    #[test]
+    fn watched_script_network() {
+        let sigset = test_sigset(0, time_now());
+        let mut scripts = WatchedScripts::for_network(bitcoin::Network::Regtest);
+        scripts.insert(Dest::RewardPool, &sigset).unwrap();
+        let (_, address, _) = scripts.iter_scripts().next().unwrap();
+        assert!(address.to_string().starts_with("bcrt1"));
+
+        let mut scripts = WatchedScripts::for_network(bitcoin::Network::Signet);
+        scripts.insert(Dest::RewardPool, &sigset).unwrap();
+        let (_, signet_address, _) = scripts.iter_scripts().next().unwrap();
+        assert!(signet_address.to_string().starts_with("tb1"));
+
+        let mainnet =
+            bitcoin::Address::from_script(&address.script_pubkey(), bitcoin::Network::Bitcoin)
+                .unwrap()
+                .to_string();
+        assert_eq!(
+            parse_network_address(&mainnet, bitcoin::Network::Bitcoin).unwrap(),
+            mainnet.parse::<bitcoin::Address>().unwrap()
+        );
+        let err = parse_network_address(&mainnet, bitcoin::Network::Regtest).unwrap_err();
+        assert!(
+            err.to_string().contains("is not a regtest address"),
+            "{}",
+            err
+        );
+        assert!(parse_network_address(&address.to_string(), bitcoin::Network::Regtest).is_ok());
+        // testnet and signet share address prefixes
+        assert!(
+            parse_network_address(&signet_address.to_string(), bitcoin::Network::Testnet).is_ok()
+        );
+        assert!(parse_network_address("not an address", bitcoin::Network::Regtest).is_err());
+    }
+
+    #[test]
+    fn dump_watched_scripts() {
+        let mut scripts = WatchedScripts::new();
+        let sigsets = [test_sigset(0, 1_000), test_sigset(1, 2_000)];
+        scripts.insert(Dest::RewardPool, &sigsets[0]).unwrap();
+        scripts.insert(Dest::RewardPool, &sigsets[1]).unwrap();
+
+        let mut report = vec![];
+        scripts.dump(&mut report, 500).unwrap();
+        let report = String::from_utf8(report).unwrap();
+
+        assert!(report.starts_with("2 watched scripts\n"));
+        for (sigset, timeout) in sigsets.iter().zip(["1500", "2500"]) {
+            let script = sigset
+                .output_script(
+                    &Dest::RewardPool.commitment_bytes().unwrap(),
+                    SIGSET_THRESHOLD,
+                )
+                .unwrap();
+            let address = bitcoin::Address::from_script(&script, crate::bitcoin::NETWORK).unwrap();
+            let entry = format!(
+                "{}\n\tdest={}\n\tsigset_index={}\n\tdeposit_timeout={}\n",
+                address,
+                Dest::RewardPool,
+                sigset.index(),
+                timeout
+            );
+            assert!(
+                report.contains(&entry),
+                "missing {:?} in {:?}",
+                entry,
+                report
+            );
+        }
+        assert!(report.find("sigset_index=0").unwrap() < report.find("sigset_index=1").unwrap());
+    }
+
+    #[test]
+    fn remove_expired() {
+        let now = time_now();
+        let mut scripts = WatchedScripts::new();
+        for (index, age) in [(0, 500), (1, 300), (2, 100), (3, 0)] {
+            let sigset = test_sigset(index, now - age);
+            scripts.insert(Dest::RewardPool, &sigset).unwrap();
+            let dest = Dest::Bitcoin {
+                data: Adapter::new(bitcoin::Script::new_op_return(&[index as u8])),
+            };
+            scripts.insert(dest, &sigset).unwrap();
+        }
+        assert_eq!(scripts.len(), 8);
+        assert_eq!(scripts.sigsets.len(), 4);
+
+        scripts.remove_expired(200).unwrap();
+        assert_eq!(scripts.len(), 4);
+        assert_eq!(
+            scripts.sigsets.keys().copied().collect::<Vec<_>>(),
+            vec![2, 3]
+        );
+        assert_eq!(scripts.oldest_sigset(), Some(2));
+
+        scripts.remove_expired(1000).unwrap();
+        assert_eq!(scripts.len(), 4);
+
+        scripts.remove_expired(0).unwrap();
+        assert!(scripts.is_empty());
+        assert!(scripts.sigsets.is_empty());
+        assert_eq!(scripts.oldest_sigset(), None);
+    }
+
+    #[test]
+    fn deposit_scan_failures() {
+        let mut scan = DepositScan::new(BlockHash::from_inner([0; 32]), 0, 1);
+        let txid = test_deposit_tx().txid();
+
+        scan.record(txid, 0, Ok(DepositRelayOutcome::Relayed));
+        scan.record(txid, 1, Err(Error::Relayer("relay failed".to_string())));
+        scan.record(txid, 2, Ok(DepositRelayOutcome::AlreadyProcessed));
+        scan.record(
+            txid,
+            3,
+            Err(Error::Relayer(
+                "Deposit amount is below minimum".to_string(),
+            )),
+        );
+
+        assert_eq!(
+            scan.outcomes,
+            vec![
+                (txid, 0, DepositRelayOutcome::Relayed),
+                (txid, 2, DepositRelayOutcome::AlreadyProcessed),
+                (txid, 3, DepositRelayOutcome::BelowMinimum),
+            ]
+        );
+        assert_eq!(scan.failures.len(), 1);
+        assert_eq!(scan.failures[0].1, 1);
+        assert!(scan.failures[0].2.contains("relay failed"));
+    }
+
+    #[test]
+    fn op_return_memo() {
+        use bitcoin::blockdata::opcodes::all::OP_RETURN;
+        use bitcoin::blockdata::script::Builder;
+
+        let op_return = |data: &[u8]| bitcoin::TxOut {
+            value: 0,
+            script_pubkey: Builder::new()
+                .push_opcode(OP_RETURN)
+                .push_slice(data)
+                .into_script(),
+        };
+
+        let mut tx = test_deposit_tx();
+        assert_eq!(deposit_memo(&tx), None);
+
+        // unrecognized and malformed OP_RETURNs are skipped
+        tx.output.push(op_return(b"other protocol"));
+        tx.output.push(op_return(b"nomic:\xff\xfe"));
+        tx.output.push(bitcoin::TxOut {
+            value: 0,
+            script_pubkey: bitcoin::Script::from(vec![OP_RETURN.to_u8(), 0x4c]),
+        });
+        assert_eq!(deposit_memo(&tx), None);
+
+        tx.output.push(op_return(b"nomic:invoice 42"));
+        assert_eq!(deposit_memo(&tx), Some("invoice 42".to_string()));
+
+        let mut output = test_output_match(Dest::RewardPool);
+        output.memo = deposit_memo(&tx);
+        let info = DepositInfo::new(&tx, &output, 100, 100);
+        assert_eq!(info.memo.as_deref(), Some("invoice 42"));
+    }
+
+    #[test]
+    fn idle_backoff() {
+        let mut backoff = IdleBackoff::new(Duration::from_secs(2), Duration::from_secs(20));
+
+        let intervals: Vec<_> = (0..6).map(|_| backoff.idle().as_secs()).collect();
+        assert_eq!(intervals, vec![2, 4, 8, 16, 20, 20]);
+
+        backoff.reset();
+        assert_eq!(backoff.idle(), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn watched_script_parse_errors() {
+        let dest = Dest::RewardPool.to_base64().unwrap();
+        let (parsed, index) = WatchedScriptStore::parse_line(1, &format!("{},7", dest)).unwrap();
+        assert!(matches!(parsed, Dest::RewardPool));
+        assert_eq!(index, 7);
+
+        let err = WatchedScriptStore::parse_line(3, &format!("{},x", dest)).unwrap_err();
+        assert!(err.to_string().contains("sigset index on line 3"));
+
+        let err = WatchedScriptStore::parse_line(4, "no comma").unwrap_err();
+        assert!(err.to_string().contains("line 4: \"no comma\""));
+
+        let long_line = format!("{},1", "!".repeat(100));
+        let err = WatchedScriptStore::parse_line(5, &long_line).unwrap_err();
+        assert!(err.to_string().contains("address on line 5"));
+        assert!(err.to_string().contains("..."));
+    }
+
+    #[test]
+    fn watched_script_torn_write() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("watched-addrs.csv");
+        let mut file = File::create(&path).unwrap();
+        WatchedScriptStore::write(&mut file, &Dest::RewardPool, 12).unwrap();
+        WatchedScriptStore::write(&mut file, &Dest::RewardPool, 34).unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let entries = WatchedScriptStore::parse_contents(&contents);
+        let indexes: Vec<_> = entries.iter().map(|(_, index)| *index).collect();
+        assert_eq!(indexes, vec![12, 34]);
+
+        // a write cut short before its newline is skipped rather than loaded
+        // with a truncated sigset index
+        let torn = &contents[..contents.len() - 2];
+        let entries = WatchedScriptStore::parse_contents(torn);
+        let indexes: Vec<_> = entries.iter().map(|(_, index)| *index).collect();
+        assert_eq!(indexes, vec![12]);
+
+        let corrupt = format!("no comma\n{}", contents);
+        assert_eq!(WatchedScriptStore::parse_contents(&corrupt).len(), 2);
+    }
+
+    #[test]
+    fn latency_stats() {
+        let mut samples = LatencySamples::default();
+        assert!(samples.stats().is_none());
+
+        for ms in (1..=100).rev() {
+            samples.record(Duration::from_millis(ms));
+        }
+        let stats = samples.stats().unwrap();
+        assert_eq!(stats.p50, Duration::from_millis(50));
+        assert_eq!(stats.p95, Duration::from_millis(95));
+        assert_eq!(stats.max, Duration::from_millis(100));
+
+        // only the most recent samples are kept
+        samples.record(Duration::from_millis(500));
+        assert_eq!(samples.0.len(), MAX_LATENCY_SAMPLES);
+        assert_eq!(samples.stats().unwrap().max, Duration::from_millis(500));
+    }
+
+    #[test]
+    fn deposit_reorg_window() {
+        // the tip advanced on the same branch
+        let min = DEFAULT_MIN_RESCAN_WINDOW;
+        assert_eq!(reorg_depth(100, 100), None);
+        assert_eq!(deposit_scan_window(100, 103, min), min);
+
+        // the tip moved to a branch forking 3 blocks below the previous tip
+        assert_eq!(reorg_depth(100, 97), Some(3));
+        assert_eq!(deposit_scan_window(97, 101, min), min);
+        assert_eq!(deposit_scan_window(97, 101, 2), 4);
+
+        // a window longer than the minimum covers every block on the new
+        // branch
+        assert_eq!(deposit_scan_window(1000, 3000, min), 2000);
+        assert_eq!(reorg_depth(2500, 1000), Some(1500));
+
+        // catching up on many headers is bounded
+        assert_eq!(deposit_scan_window(1000, 10_000, min), MAX_RESCAN_WINDOW);
+        assert_eq!(deposit_scan_window(1000, 10_000, 5000), 5000);
+    }
+
+    #[test]
+    fn checkpoint_broadcasts() {
+        let mut broadcasts = CheckpointBroadcasts::new(6);
+        let txids: Vec<_> = (1..=4).map(|i| Txid::from_inner([i; 32])).collect();
+
+        // unconfirmed or shallow transactions are rebroadcast each round
+        assert!(broadcasts.needs_broadcast(txids[0], None));
+        assert!(broadcasts.needs_broadcast(txids[0], None));
+        assert!(broadcasts.needs_broadcast(txids[1], Some(5)));
+        assert!(!broadcasts.is_settled(&txids[1]));
+
+        // deeply confirmed transactions are settled for good
+        assert!(!broadcasts.needs_broadcast(txids[2], Some(6)));
+        assert!(broadcasts.is_settled(&txids[2]));
+        assert!(!broadcasts.needs_broadcast(txids[2], None));
+
+        // e.g. the node reported the transaction as already mined
+        broadcasts.settle(txids[3]);
+        assert!(!broadcasts.needs_broadcast(txids[3], None));
+    }
+
+    #[test]
+    fn settled_checkpoints_persistence() {
+        let dir = tempfile::tempdir().unwrap();
+        let txids: Vec<_> = (1..=3).map(|i| Txid::from_inner([i; 32])).collect();
+
+        let mut broadcasts = CheckpointBroadcasts::new(6);
+        broadcasts.open(dir.path()).unwrap();
+        assert!(!broadcasts.needs_broadcast(txids[0], Some(6)));
+        broadcasts.settle(txids[1]);
+        broadcasts.skip(txids[2]);
+
+        let mut reopened = CheckpointBroadcasts::new(6);
+        reopened.open(dir.path()).unwrap();
+        assert!(reopened.is_settled(&txids[0]));
+        assert!(reopened.is_settled(&txids[1]));
+        // transactions skipped in dry run mode are checked again
+        assert!(!reopened.is_settled(&txids[2]));
+        assert!(reopened.needs_broadcast(txids[2], None));
+
+        // settling is only written once per transaction
+        reopened.settle(txids[0]);
+        let contents = std::fs::read_to_string(dir.path().join(SETTLED_CHECKPOINTS_FILE)).unwrap();
+        assert_eq!(contents.lines().count(), 2);
+    }
+
+    #[test]
+    fn header_relay_report() {
+        let mut report = HeaderRelayReport {
+            from_height: 101,
+            to_height: 350,
+            count: 250,
+            new_tip: BlockHash::all_zeros(),
+            fullnode_height: 1_000,
+        };
+        assert_eq!(report.blocks_behind(), 650);
+
+        // the full node's tip may have been reorged below the batch
+        report.fullnode_height = 300;
+        assert_eq!(report.blocks_behind(), 0);
+    }
+
+    #[tokio::test]
+    async fn checkpoint_dry_run() {
+        let bitcoind = BitcoinD::new(bitcoind::downloaded_exe_path().unwrap()).unwrap();
+        let mut relayer = test_relayer(&bitcoind).await.with_dry_run(true);
+
+        let tx = test_deposit_tx();
+        let txid = tx.txid();
+        let mut tx_bytes = vec![];
+        tx.consensus_encode(&mut tx_bytes).unwrap();
+
+        relayer.log_dry_run_checkpoint(txid, &tx_bytes);
+        assert!(relayer.checkpoint_broadcasts.is_settled(&txid));
+        assert!(!relayer.checkpoint_broadcasts.needs_broadcast(txid, None));
+    }
+
+    #[tokio::test]
+    async fn checkpoint_broadcast_rejection() {
+        let bitcoind = BitcoinD::new(bitcoind::downloaded_exe_path().unwrap()).unwrap();
+        let (send, mut recv) = tokio::sync::mpsc::channel(8);
+        let mut relayer = test_relayer(&bitcoind).await.with_event_sender(send);
+        let txid = Txid::from_inner([1; 32]);
+
+        let rejection = || {
+            Error::Relayer(
+                "RPC error response: non-mandatory-script-verify-flag (Signature must be zero for failed CHECK(MULTI)SIG operation)".to_string(),
+            )
+        };
+        relayer
+            .handle_checkpoint_broadcast_error(txid, rejection())
+            .unwrap();
+        match recv.try_recv().unwrap() {
+            RelayerEvent::CheckpointRejected {
+                txid: rejected,
+                reason,
+            } => {
+                assert_eq!(rejected, txid);
+                assert!(reason.contains("non-mandatory-script-verify-flag"));
+            }
+            event => panic!("expected CheckpointRejected event, got {:?}", event),
+        }
+        assert!(!relayer.checkpoint_broadcasts.is_settled(&txid));
+
+        // repeated rejections of the same transaction are only reported once
+        relayer
+            .handle_checkpoint_broadcast_error(txid, rejection())
+            .unwrap();
+        assert!(recv.try_recv().is_err());
+
+        let benign = Error::Relayer("Transaction already in block chain".to_string());
+        relayer
+            .handle_checkpoint_broadcast_error(txid, benign)
+            .unwrap();
+        assert!(relayer.checkpoint_broadcasts.is_settled(&txid));
+        assert!(recv.try_recv().is_err());
+
+        let other = Error::Relayer("connection refused".to_string());
+        assert!(relayer
+            .handle_checkpoint_broadcast_error(txid, other)
+            .is_err());
+
+        assert_eq!(
+            classify_broadcast_error("mandatory-script-verify-flag-failed"),
+            BroadcastError::Invalid
+        );
+        assert_eq!(
+            classify_broadcast_error("scriptsig-not-pushonly"),
+            BroadcastError::Invalid
+        );
+        assert_eq!(
+            classify_broadcast_error("bad-txns-inputs-missingorspent"),
+            BroadcastError::Settled
+        );
+    }
+
+    #[test]
+    fn retry_backoff() {
+        let base = Duration::from_secs(2);
+        let max = Duration::from_secs(20);
+        let mut backoff = Backoff::new(base, max, 3.0);
+
+        for expected in [2, 6, 18, 20, 20] {
+            let expected = Duration::from_secs(expected);
+            let delay = backoff.next_delay();
+            assert!(delay <= expected);
+            assert!(delay >= expected / 2);
+        }
+
+        backoff.reset();
+        assert!(backoff.next_delay() <= base);
+
+        // a long-running attempt resets the delay
+        backoff.next_delay();
+        backoff.next_delay();
+        assert!(backoff.after_attempt(Duration::from_secs(1)) > base);
+        assert!(backoff.after_attempt(max) <= base);
+    }
+
+    #[test]
+    fn deposit_scan_range() {
+        let tip = BlockHash::from_inner([0; 32]);
+
+        let scan = DepositScan::new(tip, 2000, 1100);
+        assert_eq!(scan.range, (901, 2000));
+        assert_eq!(scan.range.1 - scan.range.0 + 1, 1100);
+
+        // near genesis, fewer blocks than requested exist
+        let scan = DepositScan::new(tip, 5, 6);
+        assert_eq!(scan.range, (0, 5));
+
+        let scan = DepositScan::new(tip, 5, 1);
+        assert_eq!(scan.range, (5, 5));
+    }
+
+    #[tokio::test]
+    async fn rpc_latency_stats() {
+        let bitcoind = BitcoinD::new(bitcoind::downloaded_exe_path().unwrap()).unwrap();
+        let relayer = test_relayer(&bitcoind).await;
+
+        for ms in [10, 20, 30] {
+            relayer
+                .timed_rpc("get_block", tokio::time::sleep(Duration::from_millis(ms)))
+                .await;
+        }
+        let stats = relayer.rpc_latency_stats();
+        let get_block = stats["get_block"];
+        assert!(get_block.p50 >= Duration::from_millis(20));
+        assert!(get_block.max >= Duration::from_millis(30));
+        assert!(!stats.contains_key("get_tx_out_proof"));
+    }
+
+    #[test]
+    fn relayed_outpoints() {
+        let dir = tempfile::tempdir().unwrap();
+        let txid = test_deposit_tx().txid();
+        let other_txid = Txid::from_inner([1; 32]);
+
+        let mut relayed = RelayedOutpoints::open(dir.path()).unwrap();
+        assert!(!relayed.contains(txid, 0));
+        relayed.insert(txid, 0, 3, 100).unwrap();
+        relayed.insert(other_txid, 1, 5, 200).unwrap();
+        assert!(relayed.contains(txid, 0));
+        assert!(!relayed.contains(txid, 1));
+
+        let mut relayed = RelayedOutpoints::open(dir.path()).unwrap();
+        assert!(relayed.contains(txid, 0));
+        assert!(relayed.contains(other_txid, 1));
+
+        relayed.prune(4).unwrap();
+        assert!(!relayed.contains(txid, 0));
+        assert!(relayed.contains(other_txid, 1));
+
+        let relayed = RelayedOutpoints::open(dir.path()).unwrap();
+        assert!(!relayed.contains(txid, 0));
+        assert!(relayed.contains(other_txid, 1));
+    }
+
+    #[test]
+    fn relayed_outpoints_height_pruning() {
+        let dir = tempfile::tempdir().unwrap();
+        let txid = |i: u8| Txid::from_inner([i; 32]);
+
+        // a line without a height is skipped as corrupt
+        std::fs::write(
+            dir.path().join(RELAYED_OUTPOINTS_FILE),
+            format!("{},0,1\n", txid(0)),
+        )
+        .unwrap();
+        let mut relayed = RelayedOutpoints::open(dir.path()).unwrap();
+        assert!(!relayed.contains(txid(0), 0));
+        for i in 1..=10 {
+            relayed.insert(txid(i), 0, 1, i as u32 * 100).unwrap();
+        }
+        assert_eq!(relayed.len(), 10);
+
+        relayed.prune_below_height(500).unwrap();
+        assert_eq!(relayed.len(), 6);
+        for i in 1..5 {
+            assert!(!relayed.contains(txid(i), 0));
+        }
+        for i in 5..=10 {
+            assert!(relayed.contains(txid(i), 0));
+        }
+
+        let relayed = RelayedOutpoints::open(dir.path()).unwrap();
+        assert_eq!(relayed.len(), 6);
+        assert!(!relayed.contains(txid(4), 0));
+        assert!(relayed.contains(txid(5), 0));
+    }
+
+    #[tokio::test]
+    async fn btc_client_failover() {
+        let bitcoind = BitcoinD::new(bitcoind::downloaded_exe_path().unwrap()).unwrap();
+        let cookie_file = bitcoind.params.cookie_file.clone();
+        let unreachable =
+            test_bitcoin_client("http://localhost:1".to_string(), cookie_file.clone()).await;
+        let reachable = test_bitcoin_client(bitcoind.rpc_url(), cookie_file).await;
+
+        let relayer = Relayer::new(unreachable, "http://localhost:1".to_string())
+            .with_fallback_btc_clients(vec![reachable]);
+        assert_eq!(relayer.active_btc_client(), 0);
+
+        let err: Error = relayer
+            .btc_client()
+            .await
+            .get_block_count()
+            .await
+            .unwrap_err()
+            .into();
+        assert!(is_rpc_connection_error(&err));
+        relayer.fail_over_on(&err);
+        assert_eq!(relayer.active_btc_client(), 1);
+        relayer.btc_client().await.get_block_count().await.unwrap();
+
+        // error responses from a reachable node don't cause a failover
+        let err: Error = relayer
+            .btc_client()
+            .await
+            .get_block_hash(1_000_000)
+            .await
+            .unwrap_err()
+            .into();
+        assert!(!is_rpc_connection_error(&err));
+        relayer.fail_over_on(&err);
+        assert_eq!(relayer.active_btc_client(), 1);
+
+        // failing over from the last node wraps around to the primary
+        assert_eq!(relayer.btc_client.fail_over(), 0);
+        assert!(!is_rpc_connection_error(&Error::Relayer(
+            "Relayer error".to_string()
+        )));
+    }
+
+    #[tokio::test]
+    async fn shutdown_signal() {
+        let bitcoind = BitcoinD::new(bitcoind::downloaded_exe_path().unwrap()).unwrap();
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        let mut relayer = test_relayer(&bitcoind)
+            .await
+            .with_shutdown_signal(shutdown_rx)
+            .with_retry_backoff(Duration::from_secs(60), Duration::from_secs(60), 1.0);
+
+        let signal = async {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            shutdown_tx.send(true).unwrap();
+        };
+        let relay = async { join!(relayer.start_checkpoint_relay(), signal).0 };
+        tokio::time::timeout(Duration::from_secs(5), relay)
+            .await
+            .unwrap()
+            .unwrap();
+
+        // without a configured signal, the signal never resolves
+        let relayer = test_relayer(&bitcoind).await;
+        let never = tokio::time::timeout(Duration::from_millis(50), relayer.shutdown_signal());
+        assert!(never.await.is_err());
+    }
+
+    #[test]
+    fn status_tracker() {
+        let tracker = StatusTracker::default();
+        let shared = tracker.clone();
+        let progress = tracker.progress();
+        assert!(progress.last_header_relay_at.is_none());
+        assert!(progress.last_deposit.is_none());
+
+        let txid = Txid::from_inner([1; 32]);
+        shared.record_header_relay();
+        shared.record_watched_scripts(3);
+        shared.record_deposit(txid, 1, 10_000);
+
+        let progress = tracker.progress();
+        assert!(progress.last_header_relay_at.unwrap() >= time_now() - 1);
+        assert_eq!(progress.watched_scripts, Some(3));
+        let deposit = progress.last_deposit.clone().unwrap();
+        assert_eq!(
+            (deposit.txid, deposit.vout, deposit.sats),
+            (txid, 1, 10_000)
+        );
+
+        let status = RelayerStatus {
+            sidechain_tip: None,
+            fullnode_tip: Some(TipStatus {
+                height: 100,
+                hash: BlockHash::from_inner([2; 32]),
+            }),
+            progress,
+        };
+        let json = serde_json::to_value(&status).unwrap();
+        assert!(json["sidechain_tip"].is_null());
+        assert_eq!(json["fullnode_tip"]["height"], 100);
+        assert_eq!(json["watched_scripts"], 3);
+        assert_eq!(json["last_deposit"]["sats"], 10_000);
+    }
+
+    #[test]
+    fn heartbeat_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("header-relay.heartbeat");
+
+        touch_heartbeat(&path).unwrap();
+        let first = std::fs::metadata(&path).unwrap().modified().unwrap();
+
+        std::thread::sleep(Duration::from_millis(20));
+        touch_heartbeat(&path).unwrap();
+        let second = std::fs::metadata(&path).unwrap().modified().unwrap();
+        assert!(second > first);
+    }
+
+    #[test]
+    fn deposit_wal() {
+        let dir = tempfile::tempdir().unwrap();
+        let tx = test_deposit_tx();
+        let txid = tx.txid();
+        let proof =
+            PartialMerkleTree::from_txids(&[Txid::from_inner([1; 32]), txid], &[false, true]);
+        let deposit = PendingDeposit {
+            tx,
+            height: 123,
+            proof: proof.clone(),
+            vout: 0,
+            sigset_index: 4,
+            dest: Dest::RewardPool,
+        };
+
+        // the relayer crashes after fetching the proof, before the app call
+        // completes
+        let mut wal = DepositWal::open(dir.path()).unwrap();
+        assert!(wal.pending().is_empty());
+        wal.record(&deposit).unwrap();
+        drop(wal);
+
+        let mut wal = DepositWal::open(dir.path()).unwrap();
+        let pending = wal.pending();
+        assert_eq!(pending.len(), 1);
+        let resumed = &pending[0];
+        assert_eq!(resumed.tx, deposit.tx);
+        assert_eq!(resumed.proof, proof);
+        assert_eq!(
+            (resumed.vout, resumed.height, resumed.sigset_index),
+            (0, 123, 4)
+        );
+        assert!(matches!(resumed.dest, Dest::RewardPool));
+
+        // once the app has processed it, the entry is truncated
+        wal.remove(txid, 0).unwrap();
+        assert!(wal.pending().is_empty());
+        let wal = DepositWal::open(dir.path()).unwrap();
+        assert!(wal.pending().is_empty());
+    }
+
+    #[tokio::test]
+    async fn deposit_relayed_event() {
+        let bitcoind = BitcoinD::new(bitcoind::downloaded_exe_path().unwrap()).unwrap();
+        let (send, mut recv) = tokio::sync::mpsc::channel(8);
+        let relayer = test_relayer(&bitcoind).await.with_event_sender(send);
+
+        let tx = test_deposit_tx();
+        let txid = tx.txid();
+        let deposit = PendingDeposit {
+            tx,
+            height: 123,
+            proof: PartialMerkleTree::from_txids(&[txid], &[true]),
+            vout: 0,
+            sigset_index: 4,
+            dest: Dest::RewardPool,
+        };
+
+        relayer
+            .settle_deposit(&deposit, DepositRelayOutcome::Relayed)
+            .unwrap();
+        let RelayerEvent::DepositRelayed(relayed) = recv.try_recv().unwrap() else {
+            panic!("expected DepositRelayed event");
+        };
+        assert_eq!(relayed.txid, txid);
+        assert_eq!(relayed.vout, 0);
+        assert_eq!(relayed.amount, 100_000);
+        assert_eq!(relayed.height, 123);
+        assert_eq!(relayed.sigset_index, 4);
+        assert!(matches!(relayed.dest, Dest::RewardPool));
+
+        // deposits relayed by someone else are not reported
+        relayer
+            .settle_deposit(&deposit, DepositRelayOutcome::AlreadyProcessed)
+            .unwrap();
+        relayer
+            .settle_deposit(&deposit, DepositRelayOutcome::BelowMinimum)
+            .unwrap();
+        assert!(recv.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn deposit_relay_race() {
+        let bitcoind = BitcoinD::new(bitcoind::downloaded_exe_path().unwrap()).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let mut relayer = test_relayer(&bitcoind).await;
+        relayer.relayed_outpoints = Some(std::sync::Mutex::new(
+            RelayedOutpoints::open(dir.path()).unwrap(),
+        ));
+        relayer.deposit_wal = Some(std::sync::Mutex::new(DepositWal::open(dir.path()).unwrap()));
+
+        let tx = test_deposit_tx();
+        let txid = tx.txid();
+        let deposit = PendingDeposit {
+            tx,
+            height: 123,
+            proof: PartialMerkleTree::from_txids(&[txid], &[true]),
+            vout: 0,
+            sigset_index: 4,
+            dest: Dest::RewardPool,
+        };
+        relayer
+            .deposit_wal
+            .as_ref()
+            .unwrap()
+            .lock()
+            .unwrap()
+            .record(&deposit)
+            .unwrap();
+
+        // the app rejects the deposit since another relayer relayed it first
+        let err = Error::Orga(orga::Error::App(
+            "Output has already been relayed".to_string(),
+        ));
+        let outcome = classify_deposit_error(&err.to_string()).unwrap();
+        assert_eq!(outcome, DepositRelayOutcome::AlreadyProcessed);
+        let outcome = relayer.settle_deposit(&deposit, outcome).unwrap();
+        assert_eq!(outcome, DepositRelayOutcome::AlreadyProcessed);
+
+        assert!(relayer.is_relayed(txid, 0));
+        assert!(relayer
+            .deposit_wal
+            .as_ref()
+            .unwrap()
+            .lock()
+            .unwrap()
+            .pending()
+            .is_empty());
+        assert!(relayer.status.progress().last_deposit.is_none());
+        assert_eq!(relayer.status.progress().relayed_outpoints, Some(1));
+
+        assert_eq!(
+            classify_deposit_error("Deposit amount is below minimum"),
+            Some(DepositRelayOutcome::BelowMinimum)
+        );
+        assert_eq!(
+            classify_deposit_error("Output script does not match signer set"),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn local_merkle_proof() {
+        let bitcoind = BitcoinD::new(bitcoind::downloaded_exe_path().unwrap()).unwrap();
+        let btc_client =
+            test_bitcoin_client(bitcoind.rpc_url(), bitcoind.params.cookie_file.clone()).await;
+        let address = btc_client.get_new_address(None, None).await.unwrap();
+        btc_client.generate_to_address(1, &address).await.unwrap();
+
+        // matches the full node's proof
+        let hash = btc_client.get_best_block_hash().await.unwrap();
+        let block = btc_client.get_block(&hash).await.unwrap();
+        let txid = block.txdata[0].txid();
+        let proof_bytes = btc_client
+            .get_tx_out_proof(&[txid], Some(&hash))
+            .await
+            .unwrap();
+        let node_proof = ::bitcoin::MerkleBlock::consensus_decode(&mut proof_bytes.as_slice())
+            .unwrap()
+            .txn;
+        assert_eq!(super::local_merkle_proof(&block, txid).unwrap(), node_proof);
+
+        // proves any transaction of a larger block
+        let mut block = test_deposit_block();
+        block.txdata = (0..7)
+            .map(|i| {
+                let mut tx = test_deposit_tx();
+                tx.lock_time = bitcoin::PackedLockTime(i);
+                tx
+            })
+            .collect();
+        block.header.merkle_root = block.compute_merkle_root().unwrap();
+        for tx in block.txdata.iter() {
+            let proof = super::local_merkle_proof(&block, tx.txid()).unwrap();
+            verify_deposit_merkle_proof(&proof, block.header.merkle_root, tx.txid()).unwrap();
+        }
+
+        // falls back to the full node if the block can't prove the transaction
+        assert!(super::local_merkle_proof(&block, Txid::from_inner([1; 32])).is_none());
+        let txid = block.txdata[3].txid();
+        block.header.merkle_root = TxMerkleNode::all_zeros();
+        assert!(super::local_merkle_proof(&block, txid).is_none());
+    }
+
+    #[test]
+    fn deep_reorg() {
+        check_reorg_depth(5, 100).unwrap();
+        check_reorg_depth(99, 100).unwrap();
+
+        let err = check_reorg_depth(100, 100).unwrap_err();
+        assert!(err.to_string().contains("Reorg exceeds app limit"));
+    }
+
+    #[test]
+    fn next_checkpoint_estimate() {
+        let time = next_checkpoint_time(1_700_000_000, 60 * 5).unwrap();
+        assert_eq!(
+            time.duration_since(UNIX_EPOCH).unwrap(),
+            Duration::from_secs(1_700_000_300)
+        );
+
+        let time = next_checkpoint_time(1_700_000_000, 0).unwrap();
+        assert_eq!(time, UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+        assert!(next_checkpoint_time(u64::MAX, 300).is_none());
+    }
+
+    #[tokio::test]
+    async fn next_checkpoint_unavailable() {
+        let bitcoind = BitcoinD::new(bitcoind::downloaded_exe_path().unwrap()).unwrap();
+        let relayer = test_relayer(&bitcoind).await;
+        assert!(relayer
+            .estimate_next_checkpoint_time()
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn header_batch_connects() {
+        let tip = BlockHash::from_inner([1; 32]);
+        let header = |prev_blockhash| bitcoin::BlockHeader {
+            version: 1,
+            prev_blockhash,
+            merkle_root: bitcoin::TxMerkleNode::from_inner([0; 32]),
+            time: 0,
+            bits: 0x207fffff,
+            nonce: 0,
+        };
+
+        let connected = vec![WrappedHeader::from_header(&header(tip), 11)];
+        check_batch_connects(&connected, tip).unwrap();
+        check_batch_connects(&[], tip).unwrap();
+
+        let disconnected = vec![WrappedHeader::from_header(
+            &header(BlockHash::from_inner([2; 32])),
+            11,
+        )];
+        let err = check_batch_connects(&disconnected, tip).unwrap_err();
+        assert!(matches!(err, Error::Header(_)));
+        assert!(err.to_string().contains("batch does not connect to tip"));
+    }
+
+    #[test]
+    fn header_batch_chunks() {
+        let header = bitcoin::BlockHeader {
+            version: 1,
+            prev_blockhash: BlockHash::from_inner([0; 32]),
+            merkle_root: bitcoin::TxMerkleNode::from_inner([0; 32]),
+            time: 0,
+            bits: 0x207fffff,
+            nonce: 0,
+        };
+        let batch: Vec<_> = (0..600)
+            .map(|height| WrappedHeader::from_header(&header, height))
+            .collect();
+
+        let chunks = header_chunks(batch, 250);
+        let lens: Vec<_> = chunks.iter().map(Vec::len).collect();
+        assert_eq!(lens, vec![250, 250, 100]);
+        assert_eq!(chunks[1][0].height(), 250);
+        assert_eq!(chunks[2].last().unwrap().height(), 599);
+
+        assert_eq!(header_chunks(vec![], 250).len(), 0);
+    }
+
+    #[test]
+    fn full_node_missing_tip() {
+        let hash = BlockHash::from_inner([1; 32]);
+
+        // full node is syncing and hasn't reached the tip yet
+        check_full_node_chain(hash, 100, 50).unwrap();
+
+        // full node is past the tip's height but doesn't have it
+        let err = check_full_node_chain(hash, 100, 150).unwrap_err();
+        assert!(err.to_string().contains("does not have sidechain tip"));
+    }
+
+    #[tokio::test]
+    async fn verify_full_node_chain_startup() {
+        let bitcoind = BitcoinD::new(bitcoind::downloaded_exe_path().unwrap()).unwrap();
+        let relayer = test_relayer(&bitcoind).await;
+
+        let genesis = relayer.btc_client().await.get_block_hash(0).await.unwrap();
+        assert!(relayer.full_node_has_block(&genesis).await.unwrap());
+        let missing = BlockHash::from_inner([1; 32]);
+        assert!(!relayer.full_node_has_block(&missing).await.unwrap());
+
+        // an unreachable full node is an error to retry, not a missing block
+        let unreachable = test_bitcoin_client(
+            "http://localhost:1".to_string(),
+            bitcoind.params.cookie_file.clone(),
+        )
+        .await;
+        let unreachable = Relayer::new(unreachable, "http://localhost:1".to_string());
+        let err = unreachable.full_node_has_block(&genesis).await.unwrap_err();
+        assert!(!is_block_not_found(&err));
+
+        // the sidechain (at localhost:1) is unreachable, so startup keeps
+        // retrying until shutdown
+        let (shutdown_tx, shutdown) = watch::channel(false);
+        let relayer = relayer
+            .with_retry_backoff(Duration::from_millis(10), Duration::from_millis(50), 2.0)
+            .with_shutdown_signal(shutdown);
+        let connect = relayer.connect();
+        tokio::pin!(connect);
+        assert!(
+            tokio::time::timeout(Duration::from_millis(500), &mut connect)
+                .await
+                .is_err()
+        );
+        shutdown_tx.send(true).unwrap();
+        connect.await.unwrap();
+    }
+
+    #[test]
+    fn deposit_detected_event() {
+        let dest = Dest::NativeAccount {
+            address: Default::default(),
+        };
+        let tx = Transaction {
+            version: 2,
+            lock_time: bitcoin::PackedLockTime(0),
+            input: vec![],
+            output: vec![
+                bitcoin::TxOut {
+                    value: 1_000,
+                    script_pubkey: Default::default(),
+                },
+                bitcoin::TxOut {
+                    value: 546,
+                    script_pubkey: Default::default(),
+                },
+            ],
+        };
+        let output = OutputMatch {
+            sigset_index: 3,
+            vout: 1,
+            dest,
+            memo: None,
+        };
+
+        let (send, mut recv) = tokio::sync::mpsc::channel(1);
+        emit_event(
+            Some(&send),
+            RelayerEvent::DepositDetected(DepositInfo::new(&tx, &output, 100, 105)),
+        );
+        // channel is full, the event is dropped rather than blocking
+        emit_event(
+            Some(&send),
+            RelayerEvent::DepositDetected(DepositInfo::new(&tx, &output, 100, 105)),
+        );
+
+        let RelayerEvent::DepositDetected(info) = recv.try_recv().unwrap() else {
+            panic!("expected DepositDetected event");
+        };
+        assert_eq!(info.txid, tx.txid());
+        assert_eq!(info.vout, 1);
+        assert_eq!(info.amount, 546);
+        assert_eq!(info.sigset_index, 3);
+        assert_eq!(info.height, 100);
+        assert_eq!(info.confirmations, 6);
+        assert!(recv.try_recv().is_err());
+    }
+
+    #[test]
+    fn header_height_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(HEADER_HEIGHT_FILE);
+
+        assert_eq!(load_header_height(&path).unwrap(), None);
+
+        store_header_height(&path, 123).unwrap();
+        assert_eq!(load_header_height(&path).unwrap(), Some(123));
+
+        store_header_height(&path, 456).unwrap();
+        assert_eq!(load_header_height(&path).unwrap(), Some(456));
+    }
+
+    #[tokio::test]
+    async fn last_n_blocks() {
+        let bitcoind = BitcoinD::new(bitcoind::downloaded_exe_path().unwrap()).unwrap();
+        let btc_client =
+            test_bitcoin_client(bitcoind.rpc_url(), bitcoind.params.cookie_file.clone()).await;
+        let address = btc_client.get_new_address(None, None).await.unwrap();
+        btc_client.generate_to_address(40, &address).await.unwrap();
+        let relayer = test_relayer(&bitcoind).await;
+
+        let tip = btc_client.get_block_hash(40).await.unwrap();
+        let blocks = relayer.last_n_blocks(30, tip).await.unwrap();
+        assert_eq!(blocks.len(), 30);
+        for (i, block) in blocks.iter().enumerate() {
+            let hash = btc_client.get_block_hash(40 - i as u64).await.unwrap();
+            assert_eq!(block.block_hash(), hash);
+        }
+
+        // stops at genesis
+        let blocks = relayer.last_n_blocks(100, tip).await.unwrap();
+        assert_eq!(blocks.len(), 41);
+        assert_eq!(
+            blocks.last().unwrap().header.prev_blockhash,
+            BlockHash::all_zeros()
+        );
+    }
+
+    #[tokio::test]
+    async fn inflight_deposit_limit() {
+        let bitcoind = BitcoinD::new(bitcoind::downloaded_exe_path().unwrap()).unwrap();
+        let relayer = test_relayer(&bitcoind).await.with_max_inflight_deposits(2);
+        assert_eq!(relayer.inflight_deposits.available_permits(), 2);
+
+        // a mock relay counting how many deposits of a burst are in flight
+        let inflight = AtomicUsize::new(0);
+        let max_inflight = AtomicUsize::new(0);
+        let deposits: Vec<u32> = (0..20).collect();
+        let relayed = with_permits(&relayer.inflight_deposits, deposits.clone(), |vout| {
+            let inflight = &inflight;
+            let max_inflight = &max_inflight;
+            async move {
+                let n = inflight.fetch_add(1, Ordering::SeqCst) + 1;
+                max_inflight.fetch_max(n, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                inflight.fetch_sub(1, Ordering::SeqCst);
+                vout
+            }
+        })
+        .await;
+
+        assert_eq!(relayed, deposits);
+        assert_eq!(max_inflight.load(Ordering::SeqCst), 2);
+        assert_eq!(relayer.inflight_deposits.available_permits(), 2);
+
+        // a limit of 0 is raised to 1 rather than blocking relaying forever
+        let relayer = relayer.with_max_inflight_deposits(0);
+        assert_eq!(relayer.inflight_deposits.available_permits(), 1);
+    }
+
+    #[tokio::test]
+    async fn common_ancestor() {
+        let bitcoind = BitcoinD::new(bitcoind::downloaded_exe_path().unwrap()).unwrap();
+        let btc_client =
+            test_bitcoin_client(bitcoind.rpc_url(), bitcoind.params.cookie_file.clone()).await;
+        let address = btc_client.get_new_address(None, None).await.unwrap();
+        btc_client.generate_to_address(200, &address).await.unwrap();
+        let relayer = test_relayer(&bitcoind).await;
+        let header_fetches = || {
+            let latencies = relayer.rpc_latencies.lock().unwrap();
+            latencies
+                .get("get_block_header_info")
+                .map_or(0, |samples| samples.0.len())
+        };
+
+        // blocks on the main chain far below the tip are resolved from their
+        // heights rather than by walking back 140 headers
+        let a = btc_client.get_block_hash(150).await.unwrap();
+        let b = btc_client.get_block_hash(10).await.unwrap();
+        let ancestor = relayer.common_ancestor(a, b).await.unwrap();
+        assert_eq!(ancestor.hash, b);
+        let ancestor = relayer.common_ancestor(b, a).await.unwrap();
+        assert_eq!(ancestor.hash, b);
+        assert_eq!(header_fetches(), 4);
+
+        // a stale branch is walked back until it rejoins the main chain
+        let stale_tip = btc_client.get_block_hash(200).await.unwrap();
+        let fork = btc_client.get_block_hash(196).await.unwrap();
+        btc_client
+            .call::<serde_json::Value>("invalidateblock", &[serde_json::json!(fork.to_string())])
+            .await
+            .unwrap();
+        let new_address = btc_client.get_new_address(None, None).await.unwrap();
+        btc_client
+            .generate_to_address(10, &new_address)
+            .await
+            .unwrap();
+        let new_tip = btc_client.get_best_block_hash().await.unwrap();
+        assert_ne!(btc_client.get_block_hash(200).await.unwrap(), stale_tip);
+
+        let ancestor = relayer.common_ancestor(new_tip, stale_tip).await.unwrap();
+        assert_eq!(ancestor.height, 195);
+        assert_eq!(ancestor.hash, btc_client.get_block_hash(195).await.unwrap());
+        // the two tips, then the 5 stale headers down to the ancestor
+        assert_eq!(header_fetches(), 4 + 2 + 5);
+    }
+
+    #[tokio::test]
+    async fn block_cache() {
+        let bitcoind = BitcoinD::new(bitcoind::downloaded_exe_path().unwrap()).unwrap();
+        let btc_client =
+            test_bitcoin_client(bitcoind.rpc_url(), bitcoind.params.cookie_file.clone()).await;
+        let address = btc_client.get_new_address(None, None).await.unwrap();
+        btc_client.generate_to_address(20, &address).await.unwrap();
+        let relayer = test_relayer(&bitcoind).await.with_block_cache_size(8);
+        let block_fetches = || relayer.rpc_latencies.lock().unwrap()["get_block"].0.len();
+
+        let tip = btc_client.get_best_block_hash().await.unwrap();
+        relayer.last_n_blocks(10, tip).await.unwrap();
+        assert_eq!(block_fetches(), 10);
+
+        // only blocks deep enough to be safe from reorgs (heights 11 to 15)
+        // are cached
+        let deep = btc_client.get_block_hash(15).await.unwrap();
+        let shallow = btc_client.get_block_hash(16).await.unwrap();
+        {
+            let cache = relayer.block_cache.lock().unwrap();
+            assert!(cache.contains(&deep));
+            assert!(!cache.contains(&shallow));
+        }
+
+        // repeated fetches of cached blocks don't hit the full node
+        let blocks = relayer.last_n_blocks(10, tip).await.unwrap();
+        assert_eq!(blocks.len(), 10);
+        assert_eq!(block_fetches(), 15);
+        relayer.get_block(deep, 6).await.unwrap();
+        assert_eq!(block_fetches(), 15);
+
+        // the least recently used entry is evicted
+        let block = blocks[0].clone();
+        let hashes: Vec<_> = (1..=3).map(|i| BlockHash::from_inner([i; 32])).collect();
+        let mut cache = BlockCache::new(2);
+        cache.insert(hashes[0], block.clone());
+        cache.insert(hashes[1], block.clone());
+        assert!(cache.get(&hashes[0]).is_some());
+        cache.insert(hashes[2], block.clone());
+        assert!(cache.contains(&hashes[0]));
+        assert!(!cache.contains(&hashes[1]));
+        assert!(cache.contains(&hashes[2]));
+
+        let mut disabled = BlockCache::new(0);
+        disabled.insert(hashes[0], block);
+        assert!(!disabled.contains(&hashes[0]));
+    }
 
     #[tokio::test]
     async fn relayer_seek_uneven_batch() {
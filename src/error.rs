@@ -61,6 +61,12 @@ pub enum Error {
     #[error(transparent)]
     Ed(#[from] ed::Error),
     #[error("{0}")]
+    EthereumConsensus(String),
+    #[error("{0}")]
+    EthereumSsz(String),
+    #[error(transparent)]
+    LightClient(#[from] LightClientError),
+    #[error("{0}")]
     Relayer(String),
     #[error("{0}")]
     Signer(String),
@@ -68,22 +74,94 @@ pub enum Error {
     TaprootBuilder(#[from] bitcoin::util::taproot::TaprootBuilderError),
     #[error(transparent)]
     Io(#[from] std::io::Error),
-    #[error("Warp Rejection")]
-    WarpRejection(),
+    #[error("Warp Rejection: {cause}")]
+    WarpRejection { status: u16, cause: String },
     #[error("Unknown Error")]
     Unknown,
 }
 
+/// The ways the Ethereum light client can reject a bootstrap or update, so
+/// relayers can tell a stale update apart from an invalid one.
+#[derive(thiserror::Error, Debug)]
+pub enum LightClientError {
+    #[error("Invalid bootstrap: {0}")]
+    InvalidBootstrap(String),
+    #[error("update has empty finalized header")]
+    EmptyFinalizedHeader,
+    #[error("insufficient sync committee participation ({participants} of {committee_size})")]
+    InsufficientParticipation {
+        participants: usize,
+        committee_size: usize,
+    },
+    #[error("now is before genesis")]
+    BeforeGenesis,
+    #[error("{0} is required")]
+    MissingNextSyncCommittee(&'static str),
+    /// The update is well-formed but does not advance the client, e.g. it is
+    /// older than the finalized header or from an unexpected period.
+    #[error("Invalid update: {0}")]
+    StaleUpdate(String),
+    #[error("Invalid update: {0}")]
+    InvalidSignature(String),
+    #[error("Invalid update: {0}")]
+    InvalidProof(String),
+    #[error("Invalid update: {0}")]
+    InvalidUpdate(String),
+}
+
 #[cfg(feature = "full")]
 impl From<warp::Rejection> for Error {
-    fn from(_: warp::Rejection) -> Self {
-        Error::WarpRejection()
+    fn from(rejection: warp::Rejection) -> Self {
+        Error::WarpRejection {
+            status: rejection_status_code(&rejection).as_u16(),
+            cause: format!("{:?}", rejection),
+        }
+    }
+}
+
+/// Returns the HTTP status code matching the cause of a warp rejection, or
+/// `400 Bad Request` for rejections from custom filters.
+#[cfg(feature = "full")]
+pub fn rejection_status_code(rejection: &warp::Rejection) -> warp::http::StatusCode {
+    use warp::http::StatusCode;
+    use warp::reject::{MethodNotAllowed, PayloadTooLarge, UnsupportedMediaType};
+
+    if rejection.is_not_found() {
+        StatusCode::NOT_FOUND
+    } else if rejection.find::<MethodNotAllowed>().is_some() {
+        StatusCode::METHOD_NOT_ALLOWED
+    } else if rejection.find::<PayloadTooLarge>().is_some() {
+        StatusCode::PAYLOAD_TOO_LARGE
+    } else if rejection.find::<UnsupportedMediaType>().is_some() {
+        StatusCode::UNSUPPORTED_MEDIA_TYPE
+    } else {
+        StatusCode::BAD_REQUEST
     }
 }
 
 #[cfg(feature = "full")]
 impl warp::reject::Reject for Error {}
 
+#[cfg(feature = "full")]
+impl Error {
+    /// Returns the HTTP status code to respond with when this error is
+    /// returned from an HTTP API.
+    pub fn status_code(&self) -> warp::http::StatusCode {
+        use warp::http::StatusCode;
+
+        match self {
+            Error::Address(_)
+            | Error::BitcoinAddress(_)
+            | Error::Ed(_)
+            | Error::InvalidDepositAddress => StatusCode::BAD_REQUEST,
+            Error::WarpRejection { status, .. } => {
+                StatusCode::from_u16(*status).unwrap_or(StatusCode::BAD_REQUEST)
+            }
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
 impl From<Error> for orga::Error {
     fn from(err: Error) -> Self {
         if let Error::Orga(err) = err {
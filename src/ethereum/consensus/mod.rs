@@ -1,20 +1,24 @@
 use std::{
+    collections::{BTreeMap, VecDeque},
     fmt::Display,
     ops::{Deref, DerefMut},
+    panic::{catch_unwind, AssertUnwindSafe},
     str::FromStr,
 };
 
 use bitcoin::{consensus::encode, network};
 use ed::{Decode, Encode, Terminated};
 use helios_consensus_core::{
-    apply_bootstrap, apply_finality_update, apply_update, expected_current_slot,
+    apply_bootstrap, apply_finality_update, apply_generic_update, apply_update,
+    errors::ConsensusError,
+    expected_current_slot,
     types::{
         bls::{PublicKey as HeliosPublicKey, Signature as HeliosSignature},
         Bootstrap as HeliosBootstrap, FinalityUpdate as HeliosFinalityUpdate, Forks, GenericUpdate,
         Header as HeliosHeader, LightClientStore, SyncAggregate as HeliosSyncAggregate,
         SyncCommittee as HeliosSyncCommittee, Update as HeliosUpdate,
     },
-    verify_bootstrap, verify_finality_update, verify_update,
+    verify_bootstrap, verify_finality_update, verify_generic_update, verify_update,
 };
 use orga::{
     call::FieldCall, encoding::LengthVec, migrate::Migrate, orga, query::FieldQuery, state::State,
@@ -25,62 +29,955 @@ use ssz::{Decode as SszDecode, Encode as SszEncode};
 use ssz_types::{Bitfield, FixedVector};
 use tree_hash::TreeHash;
 
-use crate::error::Result;
+use crate::error::{Error, LightClientError, Result};
+
+pub use nomic_light_client_core::{
+    epoch_of_slot, period_of_slot, slots_per_period, Fork, EPOCHS_PER_SYNC_COMMITTEE_PERIOD,
+    SECONDS_PER_SLOT, SLOTS_PER_EPOCH, SYNC_COMMITTEE_SIZE,
+};
 
 #[cfg(feature = "ethereum-full")]
 pub mod relayer;
 
+/// The length of an encoded [Header].
+pub const HEADER_ENCODED_LEN: usize = 8 + 8 + 32 + 32 + 32;
+/// The length of an encoded [SyncCommittee] (512 pubkeys plus the aggregate
+/// pubkey).
+pub const SYNC_COMMITTEE_ENCODED_LEN: usize = SYNC_COMMITTEE_SIZE * 48 + 48;
+/// The length of an encoded [Network].
+pub const NETWORK_ENCODED_LEN: usize = 32 + 4 + 8 + 5 * 8 + 4 * 4;
+/// The length of a [Network] encoded before the fork schedule was added to it.
+const LEGACY_NETWORK_ENCODED_LEN: usize = 32 + 4 + 8;
+
+/// The maximum length of an update's finality branch, the depth of the
+/// finalized checkpoint root in the `BeaconState` of the latest supported
+/// fork.
+pub const MAX_FINALITY_BRANCH_LEN: usize = Fork::Electra.finalized_root_gindex().ilog2() as usize;
+/// The maximum length of an update's next sync committee branch, the depth of
+/// the next sync committee in the `BeaconState` of the latest supported fork.
+pub const MAX_SYNC_COMMITTEE_BRANCH_LEN: usize =
+    Fork::Electra.next_sync_committee_gindex().ilog2() as usize;
+
+/// Verifies an SSZ merkle branch from `leaf` at `generalized_index` up to
+/// `root`. The branch is ordered from the leaf's sibling upwards.
+pub fn verify_merkle_branch(
+    leaf: Bytes32,
+    branch: &[Bytes32],
+    generalized_index: u64,
+    root: Bytes32,
+) -> Result<bool> {
+    let branch: Vec<[u8; 32]> = branch.iter().map(|node| node.0).collect();
+    nomic_light_client_core::verify_merkle_branch(&leaf.0, &branch, generalized_index, &root.0)
+        .map_err(|e| Error::EthereumConsensus(e.to_string()))
+}
+
+/// The maximum number of finalized headers retained in the in-memory
+/// finalized-history buffer.
+pub const MAX_FINALIZED_HISTORY: usize = 256;
+
+/// The default number of slots the optimistic header may be ahead of the
+/// finalized header before the head is considered to be diverging (four
+/// epochs, twice the normal finality delay).
+pub const DEFAULT_DIVERGENCE_THRESHOLD: u64 = 4 * 32;
+
+/// The number of slots an update's signature slot may be ahead of the slot
+/// expected from the current time before it is rejected outright as from the
+/// future. Updates within this tolerance of a skewed clock are still verified
+/// against the current slot, so they fail as not yet valid and can be retried.
+pub const MAX_FUTURE_SLOTS: u64 = 2;
+
+/// The number of sync committee periods for which a best update is kept.
+pub const MAX_BEST_UPDATES: usize = 4;
+
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct LightClient {
     lcs: LightClientStore,
     network: Network,
+    /// Recently finalized headers, oldest first. This is kept in memory only
+    /// and is not part of the encoded state.
+    #[serde(skip)]
+    finalized_history: VecDeque<Header>,
+    /// The optimistic-finality gap, in slots, above which the head is
+    /// considered to be diverging. Not part of the encoded state.
+    #[serde(skip)]
+    divergence_threshold: Option<u64>,
+    /// The fork schedule derived from `network`, built on first use and
+    /// cleared whenever `network` changes. Not part of the encoded state.
+    #[serde(skip)]
+    forks: Option<Forks>,
+    /// The proof of the current finalized header from the last applied update.
+    last_finality_proof: Option<FinalityAttestation>,
+    /// The header this client was bootstrapped from and the branch proving its
+    /// current sync committee, used to serve bootstraps. Not part of the
+    /// encoded state.
+    #[serde(skip)]
+    bootstrap_branch: Option<(Header, LengthVec<u8, Bytes32>)>,
+    /// The time, in seconds, at which the last update was applied.
+    last_update_at: Option<u64>,
+    /// The minimum number of sync committee members which must have signed
+    /// an update for it to be applied. Not part of the encoded state.
+    #[serde(skip)]
+    min_participants: Option<usize>,
+    /// The update with the most sync committee participation seen for each
+    /// recent signature period, kept to cross a period boundary when no live
+    /// finality update arrives. Not part of the encoded state.
+    #[serde(skip)]
+    best_updates: BTreeMap<u64, Update>,
 }
 
 impl LightClient {
+    /// The length of the encoding of a [LightClient] in the worst case, when
+    /// both the current and next sync committees are present.
+    pub const MAX_ENCODED_LEN: usize = HEADER_ENCODED_LEN // finalized header
+        + SYNC_COMMITTEE_ENCODED_LEN // current sync committee
+        + 1 + SYNC_COMMITTEE_ENCODED_LEN // optional next sync committee
+        + HEADER_ENCODED_LEN // optimistic header
+        + 8 + 8 // previous and current max active participants
+        + NETWORK_ENCODED_LEN
+        + 1 + FinalityAttestation::MAX_ENCODED_LEN // optional last finality proof
+        + 1 + 8; // optional last update time
+
+    /// The names of the fields making up the encoded state, in encoding order.
+    pub const ENCODED_FIELDS: [&'static str; 9] = [
+        "finalized_header",
+        "current_sync_committee",
+        "next_sync_committee",
+        "optimistic_header",
+        "previous_max_active_participants",
+        "current_max_active_participants",
+        "network",
+        "last_finality_proof",
+        "last_update_at",
+    ];
+
     pub fn new(bootstrap: Bootstrap, network: Network) -> Result<Self> {
-        let bootstrap = bootstrap.into();
+        network.validate()?;
+
+        let bootstrap_branch = (
+            bootstrap.header.clone(),
+            bootstrap.current_sync_committee_branch.clone(),
+        );
+        let fork = bootstrap.verify_branch(&network)?;
+        let bootstrap: HeliosBootstrap = bootstrap.into();
+
+        // helios checks the committee branch with Deneb's generalized index,
+        // so later bootstraps rely on the check above alone
+        if fork < Fork::Electra {
+            verify_bootstrap(&bootstrap, bootstrap.header.tree_hash_root())
+                .map_err(|e| LightClientError::InvalidBootstrap(e.to_string()))?;
+        }
 
-        verify_bootstrap(&bootstrap, bootstrap.header.tree_hash_root())
-            .map_err(|e| orga::Error::App(format!("Invalid bootstrap: {}", e.to_string())))?;
+        let mut client = LightClient {
+            lcs: LightClientStore::default(),
+            network,
+            finalized_history: VecDeque::new(),
+            divergence_threshold: None,
+            forks: None,
+            last_finality_proof: None,
+            bootstrap_branch: Some(bootstrap_branch),
+            last_update_at: None,
+            min_participants: None,
+            best_updates: BTreeMap::new(),
+        };
+        client.apply_checked(|lcs| apply_bootstrap(lcs, &bootstrap))?;
+
+        Ok(client)
+    }
+
+    /// Fetches the bootstrap for the given checkpoint block root from a beacon
+    /// node's light client API, and constructs a verified client from it.
+    #[cfg(feature = "ethereum-full")]
+    pub async fn bootstrap_from_rpc(
+        beacon_url: &str,
+        checkpoint_root: Bytes32,
+        network: Network,
+    ) -> Result<Self> {
+        let client = relayer::RpcClient::new(beacon_url.to_string());
+        let bootstrap = client.bootstrap(checkpoint_root.clone()).await?.data;
+
+        let header_root = bootstrap.header.tree_hash_root();
+        let header_root: &[u8] = header_root.as_ref();
+        if header_root != checkpoint_root.0.as_slice() {
+            return Err(Error::EthereumConsensus(format!(
+                "Bootstrap header does not match checkpoint root {}",
+                checkpoint_root
+            )));
+        }
 
-        let mut lcs = LightClientStore::default();
-        apply_bootstrap(&mut lcs, &bootstrap);
+        Self::new(bootstrap, network)
+    }
+
+    /// Verifies and applies a light client update.
+    ///
+    /// Near a fork boundary, an update's attested header may be from a later
+    /// fork than its finalized header, and its signature from a later fork
+    /// still. The signing domain is derived from the fork active at
+    /// `signature_slot - 1`, while the merkle branches are checked with the
+    /// generalized indices of the attested header's fork, since both prove
+    /// against the attested state (see [Update::verify_branches]).
+    pub fn update(&mut self, update: Update, now_seconds: u64) -> Result<UpdateResult> {
+        let genesis_vals_root = self.network.genesis_vals_root.clone();
+        self.update_with_genesis_root(update, now_seconds, genesis_vals_root)
+    }
+
+    /// Like [LightClient::update], but verifies the update's signature against
+    /// the given genesis validators root rather than the network's, e.g. to
+    /// test updates from another network. The stored network is unchanged.
+    pub fn update_with_genesis_root(
+        &mut self,
+        update: Update,
+        now_seconds: u64,
+        genesis_vals_root: Bytes32,
+    ) -> Result<UpdateResult> {
+        let committees_before = self.committee_keys();
+        let attestation = FinalityAttestation {
+            attested_header: update.attested_header.clone(),
+            finalized_header: update.finalized_header.clone(),
+            finality_branch: update.finality_branch.clone(),
+            sync_aggregate: update.sync_aggregate.clone(),
+            signature_slot: update.signature_slot,
+        };
+
+        match self.verify_with_genesis_root(update, now_seconds, genesis_vals_root)? {
+            VerifiedUpdate::Full(update) => {
+                self.apply_checked(|lcs| apply_update(lcs, &update))?;
+            }
+            VerifiedUpdate::Finality(update) => {
+                self.apply_checked(|lcs| apply_finality_update(lcs, &update))?;
+            }
+            VerifiedUpdate::Generic(update) => {
+                self.apply_checked(|lcs| {
+                    apply_generic_update(lcs, &update);
+                })?;
+            }
+        }
 
-        Ok(LightClient { lcs, network })
+        self.record_finalized_header();
+        if attestation.finalized_header.slot == self.slot() {
+            self.last_finality_proof = Some(attestation);
+        }
+        self.last_update_at = Some(now_seconds);
+
+        Ok(UpdateResult {
+            committee_rotated: self.committee_keys() != committees_before,
+            new_finalized_slot: self.slot(),
+        })
     }
 
-    pub fn update(&mut self, update: Update, now_seconds: u64) -> Result<()> {
-        let expected_slot = (now_seconds - self.network.genesis_time) / 12;
-        let mut forks = Forks::default();
-        forks.deneb.fork_version = (&self.network.deneb_fork_version.to_le_bytes()).into();
-        let genesis_root = (&self.network.genesis_vals_root.0).into();
+    /// Verifies an update against the store without applying it, returning
+    /// it converted for helios.
+    fn verify_with_genesis_root(
+        &mut self,
+        mut update: Update,
+        now_seconds: u64,
+        genesis_vals_root: Bytes32,
+    ) -> Result<VerifiedUpdate> {
+        update.check_branch_lengths()?;
+
+        // some beacon APIs return a zeroed next sync committee rather than
+        // omitting it for finality-only updates
+        if update.has_empty_next_sync_committee() {
+            update.next_sync_committee = None;
+            update.next_sync_committee_branch = None;
+        }
 
-        if update.next_sync_committee.is_some() {
+        // some beacon nodes omit the finalized header, which would otherwise
+        // fail verification with an opaque error
+        if update.finalized_header.is_empty()? {
+            return Err(LightClientError::EmptyFinalizedHeader.into());
+        }
+
+        let attested_fork = update.verify_branches(&self.network)?;
+
+        if let Some(min_participants) = self.min_participants {
+            let participants = update.sync_aggregate.participants();
+            if participants < min_participants {
+                return Err(LightClientError::InsufficientParticipation {
+                    participants,
+                    committee_size: SYNC_COMMITTEE_SIZE,
+                }
+                .into());
+            }
+        }
+
+        let expected_slot = now_seconds
+            .checked_sub(self.network.genesis_time)
+            .ok_or(LightClientError::BeforeGenesis)?
+            / SECONDS_PER_SLOT;
+        if update.signature_slot > expected_slot + MAX_FUTURE_SLOTS {
+            return Err(Error::EthereumConsensus(format!(
+                "update from the future (signature slot {}, current slot {})",
+                update.signature_slot, expected_slot
+            )));
+        }
+        let signature_epoch = epoch_of_slot(update.signature_slot.saturating_sub(1));
+        let signature_fork = self.network.fork_at_epoch(signature_epoch);
+        let forks = self.forks.get_or_insert_with(|| (&self.network).into());
+        let electra_forks;
+        let forks = if signature_fork == Fork::Electra {
+            // the pinned helios version has no Electra fork, so its version is
+            // substituted for Deneb's to compute the signing domain
+            let mut f = forks.clone();
+            f.deneb.fork_version = (&self.network.electra_fork_version.to_le_bytes()).into();
+            electra_forks = f;
+            &electra_forks
+        } else {
+            &*forks
+        };
+        let genesis_root = (&genesis_vals_root.0).into();
+
+        if attested_fork >= Fork::Electra {
+            // helios checks branches with Deneb's generalized indices, so the
+            // branches checked above are withheld from its verification. They
+            // are still passed when applying, which only tests their presence.
+            let update: GenericUpdate = if update.next_sync_committee.is_some() {
+                (&HeliosUpdate::try_from(update)?).into()
+            } else {
+                (&HeliosFinalityUpdate::from(update)).into()
+            };
+            let unproven = GenericUpdate {
+                finality_branch: None,
+                next_sync_committee_branch: None,
+                ..update.clone()
+            };
+            verify_generic_update(&unproven, expected_slot, &self.lcs, genesis_root, forks)
+                .map_err(|e| update_error(e.downcast_ref(), e.to_string()))?;
+            Ok(VerifiedUpdate::Generic(update))
+        } else if update.next_sync_committee.is_some() {
             let update: HeliosUpdate = update.try_into().unwrap();
-            verify_update(&update, expected_slot, &self.lcs, genesis_root, &forks)
-                .map_err(|e| orga::Error::App(format!("Invalid update: {}", e.to_string())))?;
-            apply_update(&mut self.lcs, &update);
+            verify_update(&update, expected_slot, &self.lcs, genesis_root, forks)
+                .map_err(|e| update_error(e.downcast_ref(), e.to_string()))?;
+            Ok(VerifiedUpdate::Full(update))
         } else {
             let update: HeliosFinalityUpdate = update.into();
-            verify_finality_update(&update, expected_slot, &self.lcs, genesis_root, &forks)
-                .map_err(|e| orga::Error::App(format!("Invalid update: {}", e.to_string())))?;
-            apply_finality_update(&mut self.lcs, &update);
+            verify_finality_update(&update, expected_slot, &self.lcs, genesis_root, forks)
+                .map_err(|e| update_error(e.downcast_ref(), e.to_string()))?;
+            Ok(VerifiedUpdate::Finality(update))
+        }
+    }
+
+    /// Keeps `update` as the best update for its signature period if it has
+    /// more sync committee participation than the one already kept, returning
+    /// whether it was kept. Updates which would be kept are first verified
+    /// against the store as with [LightClient::update], so a forged update
+    /// claiming full participation can't displace a real one. Only the latest
+    /// [MAX_BEST_UPDATES] periods are kept.
+    pub fn insert_best_update(&mut self, update: Update, now_seconds: u64) -> Result<bool> {
+        if !self.is_better_update(&update) {
+            return Ok(false);
+        }
+
+        let genesis_vals_root = self.network.genesis_vals_root.clone();
+        self.verify_with_genesis_root(update.clone(), now_seconds, genesis_vals_root)?;
+
+        Ok(self.keep_best_update(update))
+    }
+
+    /// Whether `update` would replace the best update kept for its period.
+    fn is_better_update(&self, update: &Update) -> bool {
+        let period = period_of_slot(update.signature_slot);
+        if period < period_of_slot(self.lcs.finalized_header.slot) {
+            return false;
+        }
+
+        self.best_updates.get(&period).map_or(true, |best| {
+            best.sync_aggregate.participants() < update.sync_aggregate.participants()
+        })
+    }
+
+    /// Keeps an already verified update if it is better than the one kept for
+    /// its period, pruning the oldest periods.
+    fn keep_best_update(&mut self, update: Update) -> bool {
+        if !self.is_better_update(&update) {
+            return false;
+        }
+
+        let period = period_of_slot(update.signature_slot);
+        self.best_updates.insert(period, update);
+        while self.best_updates.len() > MAX_BEST_UPDATES {
+            self.best_updates.pop_first();
+        }
+
+        true
+    }
+
+    /// Applies the best update kept for the finalized header's period, to
+    /// advance into the next period when no live finality update is available.
+    /// The update is fully verified as with [LightClient::update]. Kept updates
+    /// for periods before the new finalized period are dropped.
+    pub fn force_advance(&mut self, now_seconds: u64) -> Result<UpdateResult> {
+        let period = period_of_slot(self.lcs.finalized_header.slot);
+        let update = self.best_updates.get(&period).cloned().ok_or_else(|| {
+            Error::EthereumConsensus(format!("No best update kept for period {}", period))
+        })?;
+
+        let res = self.update(update, now_seconds)?;
+        let finalized_period = period_of_slot(self.lcs.finalized_header.slot);
+        self.best_updates = self.best_updates.split_off(&finalized_period);
+        if finalized_period == period {
+            self.best_updates.remove(&period);
+        }
+
+        Ok(res)
+    }
+
+    /// Applies a sequence of updates all-or-nothing: if any update fails,
+    /// `self` is left unchanged and the error names the index of the failed
+    /// update. Returns the number of updates applied.
+    pub fn update_batch(&mut self, updates: Vec<Update>, now_seconds: u64) -> Result<usize> {
+        let mut client = self.clone();
+        let len = updates.len();
+        for (i, update) in updates.into_iter().enumerate() {
+            client.update(update, now_seconds).map_err(|err| {
+                Error::EthereumConsensus(format!("Update {} of batch failed: {}", i, err))
+            })?;
+        }
+
+        *self = client;
+        Ok(len)
+    }
+
+    /// Checks that the light client store is internally consistent, to reject
+    /// maliciously-assembled stores imported from external snapshots before
+    /// trusting them. Since the store does not retain the branches proving its
+    /// sync committees, this cannot verify the committees themselves, only that
+    /// they are usable for the periods of the store's headers.
+    pub fn validate_store(&self) -> Result<()> {
+        self.check_integrity()?;
+
+        let finalized_period = period_of_slot(self.lcs.finalized_header.slot);
+        let optimistic_period = period_of_slot(self.lcs.optimistic_header.slot);
+        // the store's committees only cover the finalized header's period and
+        // the one after it
+        if optimistic_period > finalized_period + 1
+            || (optimistic_period > finalized_period && self.lcs.next_sync_committee.is_none())
+        {
+            return Err(Error::EthereumConsensus(format!(
+                "Optimistic header period {} is not covered by the committees for period {}",
+                optimistic_period, finalized_period
+            )));
+        }
+
+        if let Some(next) = &self.lcs.next_sync_committee {
+            if next.aggregate_pubkey.as_ssz_bytes()
+                == self
+                    .lcs
+                    .current_sync_committee
+                    .aggregate_pubkey
+                    .as_ssz_bytes()
+            {
+                return Err(Error::EthereumConsensus(
+                    "Next sync committee is identical to the current one".to_string(),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks lightweight invariants which hold for any store built through
+    /// bootstrapping and updates, to catch corrupted state when loading.
+    fn check_integrity(&self) -> orga::Result<()> {
+        let corrupt = |msg: &str| {
+            Err(orga::Error::App(format!(
+                "Corrupt light client state: {}",
+                msg
+            )))
+        };
+
+        if self
+            .lcs
+            .current_sync_committee
+            .aggregate_pubkey
+            .as_ssz_bytes()
+            .iter()
+            .all(|b| *b == 0)
+        {
+            return corrupt("current sync committee is empty");
+        }
+        if self.lcs.optimistic_header.slot < self.lcs.finalized_header.slot {
+            return corrupt("optimistic header is behind finalized header");
         }
+        if self.lcs.previous_max_active_participants > SYNC_COMMITTEE_SIZE as u64
+            || self.lcs.current_max_active_participants > SYNC_COMMITTEE_SIZE as u64
+        {
+            return corrupt("participation exceeds sync committee size");
+        }
+        if let Some(attestation) = &self.last_finality_proof {
+            if attestation.finalized_header.tree_hash_root()
+                != self.lcs.finalized_header.tree_hash_root()
+            {
+                return corrupt("finality proof is not for the finalized header");
+            }
+            if attestation.finality_branch.len() > MAX_FINALITY_BRANCH_LEN {
+                return corrupt("finality proof branch is too long");
+            }
+        }
+
+        Ok(())
+    }
 
+    /// The SSZ-encoded aggregate pubkeys of the current and next sync
+    /// committees, used to cheaply detect committee changes.
+    fn committee_keys(&self) -> (Vec<u8>, Option<Vec<u8>>) {
+        (
+            self.lcs
+                .current_sync_committee
+                .aggregate_pubkey
+                .as_ssz_bytes(),
+            self.lcs
+                .next_sync_committee
+                .as_ref()
+                .map(|sc| sc.aggregate_pubkey.as_ssz_bytes()),
+        )
+    }
+
+    /// Runs one of helios's apply functions against a copy of the store,
+    /// committing the result only if it completes. The apply functions assume
+    /// their input was already verified and panic on internal invariant
+    /// failures, which we convert into an error so the store is left untouched.
+    fn apply_checked<F>(&mut self, apply: F) -> Result<()>
+    where
+        F: FnOnce(&mut LightClientStore),
+    {
+        let mut lcs = self.lcs.clone();
+        let lcs = catch_unwind(AssertUnwindSafe(move || {
+            apply(&mut lcs);
+            lcs
+        }))
+        .map_err(|_| Error::EthereumConsensus("Failed to apply update to store".to_string()))?;
+
+        self.lcs = lcs;
         Ok(())
     }
 
+    fn record_finalized_header(&mut self) {
+        let header = Header(self.lcs.finalized_header.clone());
+        if self
+            .finalized_history
+            .back()
+            .is_some_and(|last| last.slot >= header.slot)
+        {
+            return;
+        }
+
+        self.finalized_history.push_back(header);
+        while self.finalized_history.len() > MAX_FINALIZED_HISTORY {
+            self.finalized_history.pop_front();
+        }
+    }
+
+    /// Returns the recently finalized headers observed by this client, oldest
+    /// first.
+    pub fn finalized_history(&self) -> impl Iterator<Item = &Header> {
+        self.finalized_history.iter()
+    }
+
+    /// Removes all entries from the finalized-history buffer whose slot is
+    /// below `older_than_slot`, bounding the buffer by age rather than count.
+    pub fn prune_finalized_history(&mut self, older_than_slot: u64) {
+        while self
+            .finalized_history
+            .front()
+            .is_some_and(|header| header.slot < older_than_slot)
+        {
+            self.finalized_history.pop_front();
+        }
+    }
+
+    /// Sets the optimistic-finality gap, in slots, above which
+    /// [LightClient::is_head_diverging] returns true. Defaults to
+    /// [DEFAULT_DIVERGENCE_THRESHOLD].
+    pub fn with_divergence_threshold(mut self, slots: u64) -> Self {
+        self.divergence_threshold = Some(slots);
+        self
+    }
+
+    /// Rejects updates signed by fewer than the given number of sync committee
+    /// members, even if they would otherwise be valid. Counts above
+    /// [SYNC_COMMITTEE_SIZE] are capped to it. By default, only the minimum of
+    /// one participant enforced by helios applies.
+    pub fn with_min_participants(mut self, participants: usize) -> Self {
+        self.min_participants = Some(participants.min(SYNC_COMMITTEE_SIZE));
+        self
+    }
+
+    /// The number of slots the optimistic header is ahead of the finalized
+    /// header.
+    pub fn optimistic_finality_gap(&self) -> u64 {
+        self.lcs
+            .optimistic_header
+            .slot
+            .saturating_sub(self.lcs.finalized_header.slot)
+    }
+
+    /// Whether the optimistic header has advanced further past the finalized
+    /// header than the configured threshold, e.g. during a non-finalizing
+    /// period, in which case the tracked head may yet be reorged.
+    pub fn is_head_diverging(&self) -> bool {
+        let threshold = self
+            .divergence_threshold
+            .unwrap_or(DEFAULT_DIVERGENCE_THRESHOLD);
+        self.optimistic_finality_gap() > threshold
+    }
+
+    /// Returns the components of the last applied update's proof that the
+    /// current finalized header was finalized, so a verifier holding the sync
+    /// committee can check it independently. Returns `None` if the finalized
+    /// header did not come from an applied update, e.g. after bootstrapping or
+    /// migrating a store encoded before the proof was persisted.
+    pub fn finality_attestation(&self) -> Option<FinalityAttestation> {
+        self.last_finality_proof.clone()
+    }
+
+    /// The fraction of the sync committee which signed the last applied update
+    /// proving the current finalized header, if any.
+    pub fn participation(&self) -> Option<f64> {
+        self.last_finality_proof.as_ref().map(|attestation| {
+            attestation.sync_aggregate.participants() as f64 / SYNC_COMMITTEE_SIZE as f64
+        })
+    }
+
+    /// The time, in seconds, at which the last update was applied, if any has
+    /// been since bootstrapping.
+    pub fn last_update_at(&self) -> Option<u64> {
+        self.last_update_at
+    }
+
+    /// The slot expected to be current at the given time, based on the
+    /// network's genesis time.
+    pub fn current_slot(&self, now_seconds: u64) -> u64 {
+        now_seconds.saturating_sub(self.network.genesis_time) / SECONDS_PER_SLOT
+    }
+
+    /// A serializable snapshot of the client's headers and sync committees,
+    /// e.g. for serving from a status API.
+    pub fn state_summary(&self) -> StateSummary {
+        StateSummary {
+            finalized_header: self.finalized_header(),
+            optimistic_header: self.optimistic_header(),
+            current_committee_root: self.lcs.current_sync_committee.tree_hash_root().0.into(),
+            next_committee_root: self
+                .lcs
+                .next_sync_committee
+                .as_ref()
+                .map(|sc| sc.tree_hash_root().0.into()),
+            participation: self.participation(),
+            period: period_of_slot(self.slot()),
+        }
+    }
+
+    /// Packages the finalized header and current sync committee as a
+    /// [Bootstrap] for serving to new peers.
+    ///
+    /// The store does not retain the beacon state needed to compute a sync
+    /// committee branch, so this is only possible while the finalized header is
+    /// still the one this instance was bootstrapped from. Otherwise, an error
+    /// is returned.
+    pub fn to_bootstrap(&self) -> Result<Bootstrap> {
+        let finalized_header = Header(self.lcs.finalized_header.clone());
+        match &self.bootstrap_branch {
+            Some((header, branch)) if header.encode()? == finalized_header.encode()? => {
+                Ok(Bootstrap {
+                    header: finalized_header,
+                    current_sync_committee: SyncCommittee(self.lcs.current_sync_committee.clone()),
+                    current_sync_committee_branch: branch.clone(),
+                })
+            }
+            _ => Err(Error::EthereumConsensus(
+                "Sync committee branch for the finalized header is not retained".to_string(),
+            )),
+        }
+    }
+
     pub fn slot(&self) -> u64 {
         self.lcs.finalized_header.slot
     }
 
+    /// The fork active at the finalized header's slot.
+    pub fn current_fork(&self) -> Fork {
+        self.network.fork_at_epoch(epoch_of_slot(self.slot()))
+    }
+
     pub fn state_root(&self) -> Bytes32 {
         self.lcs.finalized_header.state_root.0.into()
     }
 
+    /// Verifies a Merkle-Patricia trie proof that `receipt_rlp` is the receipt
+    /// at `receipt_index` of an execution block with the given
+    /// `receipts_root`, e.g. to prove a bridge contract's logs were emitted.
+    ///
+    /// This only checks the receipt against `receipt_root`. The caller must
+    /// have established that `receipt_root` belongs to a finalized execution
+    /// block, e.g. with [LightClient::verify_state_proof].
+    pub fn verify_receipt(
+        &self,
+        receipt_root: Bytes32,
+        receipt_index: u64,
+        receipt_rlp: &[u8],
+        proof: &[Vec<u8>],
+    ) -> Result<()> {
+        crate::ethereum::proofs::verify_receipt_proof(
+            receipt_root.0,
+            receipt_index,
+            receipt_rlp,
+            proof,
+        )
+    }
+
+    /// Verifies an SSZ merkle proof that `leaf` is committed at
+    /// `generalized_index` in the finalized header's `BeaconState`, e.g. to
+    /// read execution-layer values anchored to the light client. Returns an
+    /// error if the branch length doesn't match the index's depth.
+    pub fn verify_state_proof(
+        &self,
+        leaf: Bytes32,
+        branch: &[Bytes32],
+        generalized_index: u64,
+    ) -> Result<bool> {
+        verify_merkle_branch(leaf, branch, generalized_index, self.state_root())
+    }
+
+    /// The aggregate BLS public key of the current sync committee.
+    pub fn current_aggregate_pubkey(&self) -> PublicKey {
+        self.lcs
+            .current_sync_committee
+            .aggregate_pubkey
+            .clone()
+            .into()
+    }
+
+    /// The aggregate BLS public key of the next sync committee, if known.
+    pub fn next_aggregate_pubkey(&self) -> Option<PublicKey> {
+        self.lcs
+            .next_sync_committee
+            .as_ref()
+            .map(|sc| sc.aggregate_pubkey.clone().into())
+    }
+
+    pub fn finalized_header(&self) -> Header {
+        Header(self.lcs.finalized_header.clone())
+    }
+
+    pub fn optimistic_header(&self) -> Header {
+        Header(self.lcs.optimistic_header.clone())
+    }
+
+    pub fn optimistic_slot(&self) -> u64 {
+        self.lcs.optimistic_header.slot
+    }
+
+    /// Reads the field named by `query`.
+    pub fn query_field(&self, query: LightClientQuery) -> LightClientField {
+        match query {
+            LightClientQuery::FinalizedHeader => {
+                LightClientField::FinalizedHeader(self.finalized_header())
+            }
+            LightClientQuery::OptimisticHeader => {
+                LightClientField::OptimisticHeader(self.optimistic_header())
+            }
+            LightClientQuery::CurrentAggregatePubkey => {
+                LightClientField::CurrentAggregatePubkey(self.current_aggregate_pubkey())
+            }
+            LightClientQuery::Slot => LightClientField::Slot(self.slot()),
+        }
+    }
+
+    pub fn optimistic_state_root(&self) -> Bytes32 {
+        self.lcs.optimistic_header.state_root.0.into()
+    }
+
+    /// How many seconds have passed since the start of the finalized header's
+    /// slot, for deciding when to fetch a fresh finality update.
+    pub fn age_seconds(&self, now_seconds: u64) -> u64 {
+        let slot_time = self.network.genesis_time + self.slot() * SECONDS_PER_SLOT;
+        now_seconds.saturating_sub(slot_time)
+    }
+
     pub fn light_client_store(&self) -> &LightClientStore {
         &self.lcs
     }
+
+    /// The length of this client's encoded state, for storage budgeting. This
+    /// varies by about 24KB depending on whether the next sync committee is
+    /// known, and by the size of the last finality proof, and never exceeds
+    /// [LightClient::MAX_ENCODED_LEN].
+    pub fn store_bytes_len(&self) -> usize {
+        let mut len = Self::MAX_ENCODED_LEN - FinalityAttestation::MAX_ENCODED_LEN - 8;
+        if self.lcs.next_sync_committee.is_none() {
+            len -= SYNC_COMMITTEE_ENCODED_LEN;
+        }
+        if let Some(attestation) = &self.last_finality_proof {
+            len += attestation.encoded_len();
+        }
+        if self.last_update_at.is_some() {
+            len += 8;
+        }
+        len
+    }
+
+    /// Produces a compact binary patch which transforms this client's state
+    /// into `newer`'s, containing only the fields which differ. When only
+    /// finality advanced, this is a small fraction of the full encoding.
+    pub fn diff(&self, newer: &LightClient) -> Result<Vec<u8>> {
+        let mut flags = 0u8;
+        let mut body = vec![];
+
+        let fields = self.diff_fields()?.into_iter();
+        let newer_fields = newer.diff_fields()?.into_iter();
+        for (i, (field, newer_field)) in fields.zip(newer_fields).enumerate() {
+            if field != newer_field {
+                flags |= 1 << i;
+                body.extend(newer_field);
+            }
+        }
+
+        let mut patch = vec![flags];
+        patch.extend(body);
+        Ok(patch)
+    }
+
+    /// Applies a patch produced by [LightClient::diff]. The patch is applied
+    /// to a copy of the client which is only kept if the whole patch decodes
+    /// and the result passes the same integrity checks as a loaded store, so
+    /// a failed patch leaves this client unchanged.
+    pub fn apply_diff(&mut self, patch: &[u8]) -> Result<()> {
+        let (&flags, mut input) = patch
+            .split_first()
+            .ok_or_else(|| Error::EthereumConsensus("Empty light client diff".to_string()))?;
+        if flags == 0 && input.is_empty() {
+            return Ok(());
+        }
+
+        let mut client = self.clone();
+        if flags & DIFF_FINALIZED_HEADER != 0 {
+            client.lcs.finalized_header = Header::decode(&mut input)?.into_inner();
+        }
+        if flags & DIFF_CURRENT_SYNC_COMMITTEE != 0 {
+            client.lcs.current_sync_committee = SyncCommittee::decode(&mut input)?.into_inner();
+        }
+        if flags & DIFF_NEXT_SYNC_COMMITTEE != 0 {
+            client.lcs.next_sync_committee =
+                Option::<SyncCommittee>::decode(&mut input)?.map(|sc| sc.into_inner());
+        }
+        if flags & DIFF_OPTIMISTIC_HEADER != 0 {
+            client.lcs.optimistic_header = Header::decode(&mut input)?.into_inner();
+        }
+        if flags & DIFF_PARTICIPATION_AND_NETWORK != 0 {
+            client.lcs.previous_max_active_participants = u64::decode(&mut input)?;
+            client.lcs.current_max_active_participants = u64::decode(&mut input)?;
+            client.network = Network::decode(&mut input)?;
+            client.network.validate()?;
+            client.forks = None;
+        }
+        if flags & DIFF_FINALITY_PROOF != 0 {
+            client.last_finality_proof = Option::<FinalityAttestation>::decode(&mut input)?;
+        }
+        if flags & DIFF_LAST_UPDATE_AT != 0 {
+            client.last_update_at = Option::<u64>::decode(&mut input)?;
+        }
+
+        if !input.is_empty() {
+            return Err(Error::EthereumConsensus(
+                "Unexpected trailing bytes in light client diff".to_string(),
+            ));
+        }
+        client.check_integrity()?;
+
+        // the in-memory proofs and candidate updates were checked against the
+        // old state and may not hold for the patched one
+        client.bootstrap_branch = None;
+        client.best_updates.clear();
+        client.record_finalized_header();
+
+        *self = client;
+        Ok(())
+    }
+
+    /// The encoded state, split into the fields which can be independently
+    /// patched by [LightClient::diff], in flag bit order.
+    fn diff_fields(&self) -> Result<[Vec<u8>; 7]> {
+        let mut finalized_header = vec![];
+        encode_header(&self.lcs.finalized_header, &mut finalized_header)?;
+
+        let mut current_sync_committee = vec![];
+        encode_sync_committee(
+            &self.lcs.current_sync_committee,
+            &mut current_sync_committee,
+        )?;
+
+        let mut next_sync_committee = vec![];
+        self.lcs
+            .next_sync_committee
+            .as_ref()
+            .map(|_| ())
+            .encode_into(&mut next_sync_committee)?;
+        if let Some(sc) = &self.lcs.next_sync_committee {
+            encode_sync_committee(sc, &mut next_sync_committee)?;
+        }
+
+        let mut optimistic_header = vec![];
+        encode_header(&self.lcs.optimistic_header, &mut optimistic_header)?;
+
+        let mut rest = vec![];
+        self.lcs
+            .previous_max_active_participants
+            .encode_into(&mut rest)?;
+        self.lcs
+            .current_max_active_participants
+            .encode_into(&mut rest)?;
+        self.network.encode_into(&mut rest)?;
+
+        let mut finality_proof = vec![];
+        self.last_finality_proof.encode_into(&mut finality_proof)?;
+
+        let mut last_update_at = vec![];
+        self.last_update_at.encode_into(&mut last_update_at)?;
+
+        Ok([
+            finalized_header,
+            current_sync_committee,
+            next_sync_committee,
+            optimistic_header,
+            rest,
+            finality_proof,
+            last_update_at,
+        ])
+    }
+}
+
+const DIFF_FINALIZED_HEADER: u8 = 1 << 0;
+const DIFF_CURRENT_SYNC_COMMITTEE: u8 = 1 << 1;
+const DIFF_NEXT_SYNC_COMMITTEE: u8 = 1 << 2;
+const DIFF_OPTIMISTIC_HEADER: u8 = 1 << 3;
+const DIFF_PARTICIPATION_AND_NETWORK: u8 = 1 << 4;
+const DIFF_FINALITY_PROOF: u8 = 1 << 5;
+const DIFF_LAST_UPDATE_AT: u8 = 1 << 6;
+
+/// The outcome of successfully applying an update to a [LightClient].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UpdateResult {
+    /// Whether the update changed the current or next sync committee.
+    pub committee_rotated: bool,
+    /// The finalized slot after applying the update.
+    pub new_finalized_slot: u64,
+}
+
+/// A summary of a [LightClient]'s state, as returned by
+/// [LightClient::state_summary].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StateSummary {
+    pub finalized_header: Header,
+    pub optimistic_header: Header,
+    /// The SSZ hash tree root of the current sync committee.
+    pub current_committee_root: Bytes32,
+    /// The SSZ hash tree root of the next sync committee, if known.
+    pub next_committee_root: Option<Bytes32>,
+    /// See [LightClient::participation].
+    pub participation: Option<f64>,
+    /// The sync committee period of the finalized header.
+    #[serde(with = "u64_string")]
+    pub period: u64,
 }
 
 impl State for LightClient {
@@ -88,9 +985,12 @@ impl State for LightClient {
         Ok(())
     }
 
-    fn field_keyop(_field_name: &str) -> Option<orga::describe::KeyOp> {
-        // TODO
-        None
+    fn field_keyop(field_name: &str) -> Option<orga::describe::KeyOp> {
+        // the light client is stored as a single encoded value rather than in
+        // child stores, so every field lives at the client's own key
+        LightClient::ENCODED_FIELDS
+            .contains(&field_name)
+            .then(|| orga::describe::KeyOp::Append(vec![]))
     }
 
     fn flush<W: std::io::Write>(self, out: &mut W) -> orga::Result<()> {
@@ -98,7 +998,9 @@ impl State for LightClient {
     }
 
     fn load(store: orga::prelude::Store, bytes: &mut &[u8]) -> orga::Result<Self> {
-        Ok(Self::decode(bytes)?)
+        let client = Self::decode(bytes)?;
+        client.check_integrity()?;
+        Ok(client)
     }
 }
 
@@ -108,7 +1010,29 @@ impl Migrate for LightClient {
         _dest: orga::prelude::Store,
         bytes: &mut &[u8],
     ) -> orga::Result<Self> {
-        Ok(Self::decode(bytes)?)
+        let lcs = decode_store(&mut *bytes)?;
+        // clients stored before the fork schedule was added to the network
+        // end with the shorter legacy encoding, and clients stored before the
+        // finality proof and update time were persisted end with the network
+        let network = if bytes.len() == LEGACY_NETWORK_ENCODED_LEN {
+            LegacyNetwork::decode(&mut *bytes)?.into()
+        } else {
+            Network::decode(&mut *bytes)?
+        };
+        let last_finality_proof = if bytes.is_empty() {
+            None
+        } else {
+            Option::<FinalityAttestation>::decode(&mut *bytes)?
+        };
+        let last_update_at = if bytes.is_empty() {
+            None
+        } else {
+            Option::<u64>::decode(&mut *bytes)?
+        };
+
+        let client = LightClient::from_store(lcs, network, last_finality_proof, last_update_at);
+        client.check_integrity()?;
+        Ok(client)
     }
 }
 
@@ -120,14 +1044,36 @@ impl FieldCall for LightClient {
     }
 }
 
+/// The fields of a [LightClient] which can be queried by orga clients.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Encode, Decode)]
+pub enum LightClientQuery {
+    FinalizedHeader,
+    OptimisticHeader,
+    CurrentAggregatePubkey,
+    Slot,
+}
+
 impl FieldQuery for LightClient {
-    type FieldQuery = ();
+    type FieldQuery = LightClientQuery;
 
-    fn field_query(&self, query: ()) -> orga::Result<()> {
-        Err(orga::Error::App("FieldQuery not supported".to_string()))
+    fn field_query(&self, query: LightClientQuery) -> orga::Result<()> {
+        // as with orga's derived queries, the field is read so the query's
+        // proof covers it, and clients read the value back from the proven
+        // state rather than from the query's result
+        self.query_field(query);
+        Ok(())
     }
 }
 
+/// The value of a field read by a [LightClientQuery].
+#[derive(Clone, Debug)]
+pub enum LightClientField {
+    FinalizedHeader(Header),
+    OptimisticHeader(Header),
+    CurrentAggregatePubkey(PublicKey),
+    Slot(u64),
+}
+
 impl Deref for LightClient {
     type Target = LightClientStore;
 
@@ -146,62 +1092,108 @@ impl Encode for LightClient {
     fn encode_into<W: std::io::Write>(&self, dest: &mut W) -> ed::Result<()> {
         encode_header(&self.finalized_header, dest)?;
         encode_sync_committee(&self.current_sync_committee, dest)?;
-        self.next_sync_committee
-            .as_ref()
-            .map(|_| ())
-            .encode_into(dest)?;
-        if let Some(sc) = &self.next_sync_committee {
-            encode_sync_committee(sc, dest)?;
+        match &self.lcs.next_sync_committee {
+            Some(sc) => {
+                1u8.encode_into(dest)?;
+                encode_sync_committee(sc, dest)?;
+            }
+            None => 0u8.encode_into(dest)?,
         }
         encode_header(&self.optimistic_header, dest)?;
         self.lcs
             .previous_max_active_participants
             .encode_into(dest)?;
         self.lcs.current_max_active_participants.encode_into(dest)?;
-        self.network.encode_into(dest)
+        self.network.encode_into(dest)?;
+        self.last_finality_proof.encode_into(dest)?;
+        self.last_update_at.encode_into(dest)
     }
 
     fn encoding_length(&self) -> ed::Result<usize> {
-        // TODO: remove need for copying
-        Ok(Header(self.lcs.finalized_header.clone()).encoding_length()?
-            + SyncCommittee(self.lcs.current_sync_committee.clone()).encoding_length()?
-            + self
-                .lcs
-                .next_sync_committee
-                .clone()
-                .map(SyncCommittee)
-                .encoding_length()?
-            + Header(self.lcs.optimistic_header.clone()).encoding_length()?
-            + self
-                .lcs
-                .previous_max_active_participants
-                .encoding_length()?
-            + self.lcs.current_max_active_participants.encoding_length()?
-            + self.network.encoding_length()?)
+        Ok(self.store_bytes_len())
     }
 }
 
 impl Decode for LightClient {
     fn decode<R: std::io::Read>(mut input: R) -> ed::Result<Self> {
-        let finalized_header = Header::decode(&mut input)?;
-        let current_sync_committee = SyncCommittee::decode(&mut input)?;
-        let next_sync_committee = Option::<SyncCommittee>::decode(&mut input)?;
-        let optimistic_header = Header::decode(&mut input)?;
-        let previous_max_active_participants = u64::decode(&mut input)?;
-        let current_max_active_participants = u64::decode(&mut input)?;
+        let lcs = decode_store(&mut input)?;
         let network = Network::decode(&mut input)?;
+        let last_finality_proof = Option::<FinalityAttestation>::decode(&mut input)?;
+        let last_update_at = Option::<u64>::decode(&mut input)?;
 
-        Ok(LightClient {
-            lcs: LightClientStore {
-                finalized_header: finalized_header.into_inner(),
-                current_sync_committee: current_sync_committee.into_inner(),
-                next_sync_committee: next_sync_committee.map(|sc| sc.into_inner()),
-                optimistic_header: optimistic_header.into_inner(),
-                previous_max_active_participants,
-                current_max_active_participants,
-            },
+        Ok(LightClient::from_store(
+            lcs,
             network,
-        })
+            last_finality_proof,
+            last_update_at,
+        ))
+    }
+}
+
+/// Classifies an update verification error from helios by its
+/// [ConsensusError] variant, so relayers can tell a stale update apart from an
+/// invalid one. Errors which aren't a [ConsensusError] (e.g. from decoding
+/// keys) are always treated as invalid.
+fn update_error(err: Option<&ConsensusError>, msg: String) -> LightClientError {
+    match err {
+        Some(ConsensusError::InvalidSignature) => LightClientError::InvalidSignature(msg),
+        Some(
+            ConsensusError::InvalidFinalityProof
+            | ConsensusError::InvalidNextSyncCommitteeProof
+            | ConsensusError::InvalidCurrentSyncCommitteeProof
+            | ConsensusError::InvalidHeaderHash(..),
+        ) => LightClientError::InvalidProof(msg),
+        Some(
+            ConsensusError::NotRelevant
+            | ConsensusError::InvalidTimestamp
+            | ConsensusError::InvalidPeriod
+            | ConsensusError::CheckpointTooOld,
+        ) => LightClientError::StaleUpdate(msg),
+        _ => LightClientError::InvalidUpdate(msg),
+    }
+}
+
+/// Decodes the [LightClientStore] part of an encoded [LightClient], which
+/// precedes its network.
+fn decode_store<R: std::io::Read>(mut input: R) -> ed::Result<LightClientStore> {
+    let finalized_header = Header::decode(&mut input)?;
+    let current_sync_committee = SyncCommittee::decode(&mut input)?;
+    let next_sync_committee = Option::<SyncCommittee>::decode(&mut input)?;
+    let optimistic_header = Header::decode(&mut input)?;
+    let previous_max_active_participants = u64::decode(&mut input)?;
+    let current_max_active_participants = u64::decode(&mut input)?;
+
+    Ok(LightClientStore {
+        finalized_header: finalized_header.into_inner(),
+        current_sync_committee: current_sync_committee.into_inner(),
+        next_sync_committee: next_sync_committee.map(|sc| sc.into_inner()),
+        optimistic_header: optimistic_header.into_inner(),
+        previous_max_active_participants,
+        current_max_active_participants,
+    })
+}
+
+impl LightClient {
+    /// A client with the given decoded state, and none of the in-memory state
+    /// which isn't persisted.
+    fn from_store(
+        lcs: LightClientStore,
+        network: Network,
+        last_finality_proof: Option<FinalityAttestation>,
+        last_update_at: Option<u64>,
+    ) -> Self {
+        LightClient {
+            lcs,
+            network,
+            finalized_history: VecDeque::new(),
+            divergence_threshold: None,
+            forks: None,
+            last_finality_proof,
+            bootstrap_branch: None,
+            last_update_at,
+            min_participants: None,
+            best_updates: BTreeMap::new(),
+        }
     }
 }
 
@@ -212,21 +1204,197 @@ pub struct Network {
     pub genesis_vals_root: Bytes32,
     pub deneb_fork_version: u32,
     pub genesis_time: u64,
+    pub altair_fork_epoch: u64,
+    pub bellatrix_fork_epoch: u64,
+    pub capella_fork_epoch: u64,
+    pub deneb_fork_epoch: u64,
+    pub electra_fork_epoch: u64,
+    pub altair_fork_version: u32,
+    pub bellatrix_fork_version: u32,
+    pub capella_fork_version: u32,
+    pub electra_fork_version: u32,
 }
 
+/// An update which passed verification against a client's store, converted
+/// for applying with helios.
+enum VerifiedUpdate {
+    Full(HeliosUpdate),
+    Finality(HeliosFinalityUpdate),
+    /// An update whose branches were verified here rather than by helios.
+    Generic(GenericUpdate),
+}
+
+/// The encoding of a [Network] before the fork schedule was added to it.
+#[derive(Encode, Decode)]
+struct LegacyNetwork {
+    genesis_vals_root: Bytes32,
+    deneb_fork_version: u32,
+    genesis_time: u64,
+}
+
+impl From<LegacyNetwork> for Network {
+    /// Fills in the fork schedule missing from the legacy encoding with
+    /// mainnet's, the only network clients were deployed on with it.
+    fn from(legacy: LegacyNetwork) -> Self {
+        Network {
+            genesis_vals_root: legacy.genesis_vals_root,
+            deneb_fork_version: legacy.deneb_fork_version,
+            genesis_time: legacy.genesis_time,
+            ..Network::ethereum_mainnet()
+        }
+    }
+}
+
+impl From<&Network> for Forks {
+    fn from(network: &Network) -> Self {
+        let mut forks = Forks::default();
+        forks.altair.epoch = network.altair_fork_epoch;
+        forks.altair.fork_version = (&network.altair_fork_version.to_le_bytes()).into();
+        forks.bellatrix.epoch = network.bellatrix_fork_epoch;
+        forks.bellatrix.fork_version = (&network.bellatrix_fork_version.to_le_bytes()).into();
+        forks.capella.epoch = network.capella_fork_epoch;
+        forks.capella.fork_version = (&network.capella_fork_version.to_le_bytes()).into();
+        forks.deneb.epoch = network.deneb_fork_epoch;
+        forks.deneb.fork_version = (&network.deneb_fork_version.to_le_bytes()).into();
+        forks
+    }
+}
+
+/// The earliest plausible genesis time (2015-01-01T00:00:00Z), in seconds.
+const MIN_GENESIS_TIME: u64 = 1_420_070_400;
+/// How far in the future (in seconds) a genesis time may be, to allow
+/// configuring networks shortly before they launch.
+const MAX_GENESIS_TIME_LEAD: u64 = 365 * 24 * 60 * 60;
+
 impl Network {
-    pub fn ethereum_mainnet() -> Self {
+    /// Checks that the network parameters are plausible, catching
+    /// misconfiguration such as a genesis time given in milliseconds.
+    pub fn validate(&self) -> Result<()> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+
+        if self.genesis_time < MIN_GENESIS_TIME
+            || self.genesis_time > now.max(MIN_GENESIS_TIME) + MAX_GENESIS_TIME_LEAD
+        {
+            return Err(Error::EthereumConsensus(format!(
+                "Implausible genesis time {}, expected Unix seconds",
+                self.genesis_time
+            )));
+        }
+
+        if self.deneb_fork_version == 0 {
+            return Err(Error::EthereumConsensus(
+                "Deneb fork version must be non-zero".to_string(),
+            ));
+        }
+
+        if self.genesis_vals_root.0 == [0; 32] {
+            return Err(Error::EthereumConsensus(
+                "Genesis validators root must be non-zero".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Constructs a network from its genesis parameters and the version and
+    /// activation epoch of each fork, ordered from Altair to Electra. Fork
+    /// versions are given as integers whose little-endian bytes are the
+    /// version, e.g. `4` for `0x04000000`.
+    pub fn with_forks(
+        genesis_vals_root: Bytes32,
+        genesis_time: u64,
+        fork_versions: [u32; 5],
+        fork_epochs: [u64; 5],
+    ) -> Self {
         Network {
-            genesis_vals_root: "0x4b363db94e286120d76eb905340fdd4e54bfe9f06bf33ff6cf5ad27f511bfe95"
+            genesis_vals_root,
+            genesis_time,
+            altair_fork_version: fork_versions[0],
+            bellatrix_fork_version: fork_versions[1],
+            capella_fork_version: fork_versions[2],
+            deneb_fork_version: fork_versions[3],
+            electra_fork_version: fork_versions[4],
+            altair_fork_epoch: fork_epochs[0],
+            bellatrix_fork_epoch: fork_epochs[1],
+            capella_fork_epoch: fork_epochs[2],
+            deneb_fork_epoch: fork_epochs[3],
+            electra_fork_epoch: fork_epochs[4],
+        }
+    }
+
+    /// Returns the version of the given fork.
+    pub fn fork_version(&self, fork: Fork) -> u32 {
+        match fork {
+            Fork::Altair => self.altair_fork_version,
+            Fork::Bellatrix => self.bellatrix_fork_version,
+            Fork::Capella => self.capella_fork_version,
+            Fork::Deneb => self.deneb_fork_version,
+            Fork::Electra => self.electra_fork_version,
+        }
+    }
+
+    /// Returns the fork active at the given epoch. Epochs before Altair are
+    /// reported as Altair, since the light client protocol does not exist
+    /// before it.
+    pub fn fork_at_epoch(&self, epoch: u64) -> Fork {
+        Fork::at_epoch(
+            [
+                self.altair_fork_epoch,
+                self.bellatrix_fork_epoch,
+                self.capella_fork_epoch,
+                self.deneb_fork_epoch,
+                self.electra_fork_epoch,
+            ],
+            epoch,
+        )
+    }
+
+    pub fn ethereum_mainnet() -> Self {
+        let network = Network::with_forks(
+            "0x4b363db94e286120d76eb905340fdd4e54bfe9f06bf33ff6cf5ad27f511bfe95"
                 .parse()
                 .unwrap(),
-            deneb_fork_version: 4,
-            genesis_time: 1606824023,
-        }
+            1606824023,
+            [1, 2, 3, 4, 5],
+            [74240, 144896, 194048, 269568, 364032],
+        );
+        network.validate().expect("Invalid mainnet parameters");
+        network
     }
 }
 
+/// A compact proof that a beacon block header, and so its state root, was
+/// finalized: the finalized header's merkle branch into the attested header's
+/// state, and the sync committee's signature over the attested header.
 #[derive(Clone, Debug, Encode, Decode, Serialize, Deserialize)]
+pub struct FinalityAttestation {
+    pub attested_header: Header,
+    pub finalized_header: Header,
+    pub finality_branch: LengthVec<u8, Bytes32>,
+    pub sync_aggregate: SyncAggregate,
+    #[serde(with = "u64_string")]
+    pub signature_slot: u64,
+}
+
+impl FinalityAttestation {
+    /// The length of an encoded [FinalityAttestation] with the longest
+    /// supported finality branch.
+    pub const MAX_ENCODED_LEN: usize = 2 * HEADER_ENCODED_LEN // attested and finalized headers
+        + 1 + 32 * MAX_FINALITY_BRANCH_LEN // finality branch
+        + 64 + 96 // sync aggregate
+        + 8; // signature slot
+
+    /// The length of this attestation's encoding.
+    pub fn encoded_len(&self) -> usize {
+        Self::MAX_ENCODED_LEN
+            - 32 * MAX_FINALITY_BRANCH_LEN.saturating_sub(self.finality_branch.len())
+    }
+}
+
+#[derive(Clone, Debug, Encode, Serialize, Deserialize)]
 pub struct Update {
     #[serde(deserialize_with = "wrapped_header::deserialize")]
     pub attested_header: Header,
@@ -240,15 +1408,118 @@ pub struct Update {
     pub signature_slot: u64,
 }
 
+impl Update {
+    /// Rejects updates whose merkle branches are longer than the supported
+    /// `BeaconState` depth, before doing any verification work on them.
+    pub fn check_branch_lengths(&self) -> Result<()> {
+        if self.finality_branch.len() > MAX_FINALITY_BRANCH_LEN {
+            return Err(LightClientError::InvalidUpdate(format!(
+                "Finality branch has {} nodes, expected at most {}",
+                self.finality_branch.len(),
+                MAX_FINALITY_BRANCH_LEN
+            ))
+            .into());
+        }
+        if let Some(branch) = &self.next_sync_committee_branch {
+            if branch.len() > MAX_SYNC_COMMITTEE_BRANCH_LEN {
+                return Err(LightClientError::InvalidUpdate(format!(
+                    "Next sync committee branch has {} nodes, expected at most {}",
+                    branch.len(),
+                    MAX_SYNC_COMMITTEE_BRANCH_LEN
+                ))
+                .into());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Verifies the update's finality and next sync committee branches,
+    /// returning the fork of the attested header. Both branches prove against
+    /// the attested header's state, so they are checked with that fork's
+    /// generalized indices regardless of which fork the finalized header is
+    /// in.
+    pub fn verify_branches(&self, network: &Network) -> Result<Fork> {
+        let fork = network.fork_at_epoch(epoch_of_slot(self.attested_header.slot));
+        let state_root: Bytes32 = self.attested_header.state_root.0.into();
+
+        let finalized_root: Bytes32 = self.finalized_header.tree_hash_root().0.into();
+        let gindex = fork.finalized_root_gindex();
+        if !verify_merkle_branch(
+            finalized_root,
+            &self.finality_branch,
+            gindex,
+            state_root.clone(),
+        )? {
+            return Err(LightClientError::InvalidProof(format!(
+                "Finality branch does not match the {:?} attested state",
+                fork
+            ))
+            .into());
+        }
+
+        if let (Some(committee), Some(branch)) =
+            (&self.next_sync_committee, &self.next_sync_committee_branch)
+        {
+            let committee_root: Bytes32 = committee.tree_hash_root().0.into();
+            let gindex = fork.next_sync_committee_gindex();
+            if !verify_merkle_branch(committee_root, branch, gindex, state_root)? {
+                return Err(LightClientError::InvalidProof(format!(
+                    "Next sync committee branch does not match the {:?} attested state",
+                    fork
+                ))
+                .into());
+            }
+        }
+
+        Ok(fork)
+    }
+
+    /// Whether the update carries a zeroed next sync committee with a zeroed
+    /// or absent branch, which is equivalent to not carrying one.
+    fn has_empty_next_sync_committee(&self) -> bool {
+        let Some(committee) = &self.next_sync_committee else {
+            return false;
+        };
+        let branch_empty = self
+            .next_sync_committee_branch
+            .as_ref()
+            .map_or(true, |branch| branch.iter().all(|node| node.0 == [0; 32]));
+
+        committee.is_empty() && branch_empty
+    }
+}
+
+impl Decode for Update {
+    fn decode<R: std::io::Read>(mut input: R) -> ed::Result<Self> {
+        let update = Update {
+            attested_header: Decode::decode(&mut input)?,
+            next_sync_committee: Decode::decode(&mut input)?,
+            next_sync_committee_branch: Decode::decode(&mut input)?,
+            finalized_header: Decode::decode(&mut input)?,
+            finality_branch: Decode::decode(&mut input)?,
+            sync_aggregate: Decode::decode(&mut input)?,
+            signature_slot: Decode::decode(&mut input)?,
+        };
+        update.check_branch_lengths().map_err(|err| {
+            ed::Error::from(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                err.to_string(),
+            ))
+        })?;
+
+        Ok(update)
+    }
+}
+
 impl TryFrom<Update> for HeliosUpdate {
     type Error = crate::error::Error;
 
     fn try_from(value: Update) -> Result<Self> {
         let attested_header = value.attested_header.into_inner();
-        let next_sync_committee = value
-            .next_sync_committee
-            .map(|sc| sc.into_inner())
-            .ok_or_else(|| orga::Error::App("next_sync_committee is required".to_string()))?;
+        let next_sync_committee = value.next_sync_committee.map(|sc| sc.into_inner()).ok_or(
+            LightClientError::MissingNextSyncCommittee("next_sync_committee"),
+        )?;
         let next_sync_committee_branch = value
             .next_sync_committee_branch
             .map(|branch| {
@@ -257,9 +1528,9 @@ impl TryFrom<Update> for HeliosUpdate {
                     .map(|b| b.into_inner().into())
                     .collect()
             })
-            .ok_or_else(|| {
-                orga::Error::App("next_sync_committee_branch is required".to_string())
-            })?;
+            .ok_or(LightClientError::MissingNextSyncCommittee(
+                "next_sync_committee_branch",
+            ))?;
         let finalized_header = value.finalized_header.into_inner();
         let finality_branch = Vec::from(value.finality_branch)
             .into_iter()
@@ -311,12 +1582,61 @@ mod u64_string {
         serializer.serialize_str(&value.to_string())
     }
 
-    pub fn deserialize<'de, D>(deserializer: D) -> Result<u64, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        let val: String = serde::Deserialize::deserialize(deserializer)?;
-        val.parse().map_err(D::Error::custom)
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<u64, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let val: String = serde::Deserialize::deserialize(deserializer)?;
+        val.parse().map_err(D::Error::custom)
+    }
+}
+
+/// Deserializes 32 bytes from `0x`-prefixed or bare hex, left-padding hex
+/// shorter than 64 characters with zeros, or from an array of 32 numbers.
+mod bytes32_lenient {
+    use serde::de::{Error, SeqAccess, Visitor};
+    use serde::Deserializer;
+    use std::fmt;
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<[u8; 32], D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(Bytes32Visitor)
+    }
+
+    struct Bytes32Visitor;
+
+    impl<'de> Visitor<'de> for Bytes32Visitor {
+        type Value = [u8; 32];
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a hex string of up to 32 bytes or an array of 32 bytes")
+        }
+
+        fn visit_str<E: Error>(self, value: &str) -> Result<[u8; 32], E> {
+            let hex = value.strip_prefix("0x").unwrap_or(value);
+            if hex.is_empty() || hex.len() > 64 {
+                return Err(E::invalid_length(hex.len(), &self));
+            }
+            let padded = format!("{:0>64}", hex);
+            let mut bytes = [0; 32];
+            hex::decode_to_slice(padded, &mut bytes).map_err(E::custom)?;
+            Ok(bytes)
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<[u8; 32], A::Error> {
+            let mut bytes = [0; 32];
+            for (i, byte) in bytes.iter_mut().enumerate() {
+                *byte = seq
+                    .next_element()?
+                    .ok_or_else(|| A::Error::invalid_length(i, &self))?;
+            }
+            if seq.next_element::<u8>()?.is_some() {
+                return Err(A::Error::invalid_length(33, &self));
+            }
+            Ok(bytes)
+        }
     }
 }
 
@@ -357,6 +1677,31 @@ pub struct Bootstrap {
     pub current_sync_committee_branch: LengthVec<u8, Bytes32>,
 }
 
+impl Bootstrap {
+    /// Verifies the bootstrap's current sync committee branch with the
+    /// generalized index of its header's fork, returning the fork.
+    pub fn verify_branch(&self, network: &Network) -> Result<Fork> {
+        let fork = network.fork_at_epoch(epoch_of_slot(self.header.slot));
+        let committee_root: Bytes32 = self.current_sync_committee.tree_hash_root().0.into();
+        let state_root: Bytes32 = self.header.state_root.0.into();
+        let gindex = fork.current_sync_committee_gindex();
+        if !verify_merkle_branch(
+            committee_root,
+            &self.current_sync_committee_branch,
+            gindex,
+            state_root,
+        )? {
+            return Err(LightClientError::InvalidBootstrap(format!(
+                "Current sync committee branch does not match the {:?} state",
+                fork
+            ))
+            .into());
+        }
+
+        Ok(fork)
+    }
+}
+
 impl From<Bootstrap> for HeliosBootstrap {
     fn from(value: Bootstrap) -> Self {
         let header = value.header.into_inner();
@@ -382,6 +1727,11 @@ impl Header {
     pub fn into_inner(self) -> HeliosHeader {
         self.0
     }
+
+    /// Whether every field of the header is zero.
+    pub fn is_empty(&self) -> Result<bool> {
+        Ok(self.encode()?.iter().all(|b| *b == 0))
+    }
 }
 
 impl Deref for Header {
@@ -404,7 +1754,7 @@ impl Encode for Header {
     }
 
     fn encoding_length(&self) -> ed::Result<usize> {
-        Ok(8 + 8 + 32 + 32 + 32)
+        Ok(HEADER_ENCODED_LEN)
     }
 }
 
@@ -444,6 +1794,24 @@ impl SyncCommittee {
     pub fn into_inner(self) -> HeliosSyncCommittee {
         self.0
     }
+
+    /// Decodes a sync committee from its standard SSZ encoding, as used by
+    /// other Ethereum tooling (rather than the `ed` encoding used for state).
+    pub fn from_ssz_bytes(bytes: &[u8]) -> Result<Self> {
+        let sc = HeliosSyncCommittee::from_ssz_bytes(bytes)
+            .map_err(|e| Error::EthereumSsz(format!("Invalid sync committee: {:?}", e)))?;
+        Ok(SyncCommittee(sc))
+    }
+
+    /// Encodes the sync committee in its standard SSZ encoding.
+    pub fn to_ssz_bytes(&self) -> Vec<u8> {
+        self.0.as_ssz_bytes()
+    }
+
+    /// Whether every public key in the committee is zero.
+    pub fn is_empty(&self) -> bool {
+        self.to_ssz_bytes().iter().all(|b| *b == 0)
+    }
 }
 
 impl Deref for SyncCommittee {
@@ -466,7 +1834,7 @@ impl Encode for SyncCommittee {
     }
 
     fn encoding_length(&self) -> ed::Result<usize> {
-        Ok(512 * 48 + 48)
+        Ok(SYNC_COMMITTEE_ENCODED_LEN)
     }
 }
 
@@ -505,6 +1873,11 @@ impl SyncAggregate {
     pub fn into_inner(self) -> HeliosSyncAggregate {
         self.0
     }
+
+    /// The number of sync committee members who signed.
+    pub fn participants(&self) -> usize {
+        self.0.sync_committee_bits.num_set_bits()
+    }
 }
 
 impl Deref for SyncAggregate {
@@ -541,13 +1914,15 @@ pub fn encode_sync_aggregate<W: std::io::Write>(
 
 impl Decode for SyncAggregate {
     fn decode<R: std::io::Read>(mut input: R) -> ed::Result<Self> {
-        let sync_committee_bits = Vec::<u8>::decode(&mut input)?;
+        // the bits are read as a fixed-length field rather than as a Vec, which
+        // would consume the rest of the input
+        let mut sync_committee_bits = [0u8; SYNC_COMMITTEE_SIZE / 8];
+        input.read_exact(&mut sync_committee_bits)?;
         let sync_committee_signature = Signature::decode(&mut input)?.into_inner();
 
         Ok(SyncAggregate(HeliosSyncAggregate {
             sync_committee_bits: Bitfield::from_ssz_bytes(&sync_committee_bits)
-                // TODO: pass through error
-                .map_err(|e| ed::Error::UnexpectedByte(34))?,
+                .map_err(|e| ssz_decode_error("sync committee bits", e))?,
             sync_committee_signature,
         }))
     }
@@ -600,6 +1975,13 @@ impl Encode for PublicKey {
     }
 }
 
+/// Converts an SSZ decoding error into an [ed::Error] which carries the reason
+/// the bytes were rejected.
+fn ssz_decode_error(what: &str, err: ssz::DecodeError) -> ed::Error {
+    let err = Error::EthereumSsz(format!("Invalid {}: {:?}", what, err));
+    std::io::Error::new(std::io::ErrorKind::InvalidData, err.to_string()).into()
+}
+
 fn encode_public_key<W: std::io::Write>(pk: &HeliosPublicKey, dest: &mut W) -> ed::Result<()> {
     pk.as_ssz_bytes().encode_into(dest)
 }
@@ -608,9 +1990,8 @@ impl Decode for PublicKey {
     fn decode<R: std::io::Read>(mut input: R) -> ed::Result<Self> {
         let mut bytes = [0u8; 48];
         input.read_exact(&mut bytes)?;
-        // TODO: pass through error
-        let value =
-            HeliosPublicKey::from_ssz_bytes(&bytes).map_err(|e| ed::Error::UnexpectedByte(33))?;
+        let value = HeliosPublicKey::from_ssz_bytes(&bytes)
+            .map_err(|e| ssz_decode_error("BLS public key", e))?;
         Ok(PublicKey(value))
     }
 }
@@ -670,9 +2051,8 @@ impl Decode for Signature {
     fn decode<R: std::io::Read>(mut input: R) -> ed::Result<Self> {
         let mut bytes = [0u8; 96];
         input.read_exact(&mut bytes)?;
-        // TODO: pass through error
-        let value =
-            HeliosSignature::from_ssz_bytes(&bytes).map_err(|e| ed::Error::UnexpectedByte(33))?;
+        let value = HeliosSignature::from_ssz_bytes(&bytes)
+            .map_err(|e| ssz_decode_error("BLS signature", e))?;
         Ok(Signature(value))
     }
 }
@@ -681,7 +2061,13 @@ impl Terminated for Signature {}
 
 #[derive(Clone, Debug, Default, Encode, Decode, Serialize, Deserialize)]
 #[serde(transparent)]
-pub struct Bytes32(#[serde(with = "SerHex::<StrictPfx>")] pub [u8; 32]);
+pub struct Bytes32(
+    #[serde(
+        serialize_with = "SerHex::<StrictPfx>::serialize",
+        deserialize_with = "bytes32_lenient::deserialize"
+    )]
+    pub [u8; 32],
+);
 
 impl Bytes32 {
     pub fn into_inner(self) -> [u8; 32] {
@@ -721,6 +2107,23 @@ mod tests {
 
     use super::*;
 
+    /// The bootstrap, sync committee updates and finality update in
+    /// `test_fixtures.json`, which follow on from each other on mainnet.
+    pub(super) fn fixtures() -> (Bootstrap, Vec<Update>, Update) {
+        let fixtures = include_str!("test_fixtures.json");
+        let (bootstrap, updates, finality_update): (
+            Response<Bootstrap>,
+            Vec<Response<Update>>,
+            Response<Update>,
+        ) = serde_json::from_str(fixtures).unwrap();
+
+        (
+            bootstrap.data,
+            updates.into_iter().map(|u| u.data).collect(),
+            finality_update.data,
+        )
+    }
+
     #[test]
     fn encode_decode() {
         let pk = PublicKey(HeliosPublicKey::default());
@@ -747,19 +2150,1304 @@ mod tests {
 
     #[tokio::test]
     async fn update() {
-        let fixtures = include_str!("test_fixtures.json");
-        let (bootstrap, updates, finality_update): (
-            Response<Bootstrap>,
-            Vec<Response<Update>>,
-            Response<Update>,
-        ) = serde_json::from_str(fixtures).unwrap();
+        let (bootstrap, updates, finality_update) = fixtures();
 
-        let mut client = LightClient::new(bootstrap.data, Network::ethereum_mainnet()).unwrap();
+        let mut client = LightClient::new(bootstrap, Network::ethereum_mainnet()).unwrap();
         for update in updates {
-            client.update(update.data, 1727740110).unwrap();
+            let res = client.update(update, 1727740110).unwrap();
+            assert!(res.committee_rotated);
+            assert_eq!(res.new_finalized_slot, client.slot());
         }
-        client.update(finality_update.data, 1727740110).unwrap();
+        let res = client.update(finality_update, 1727740110).unwrap();
+        assert!(!res.committee_rotated);
+        assert_eq!(res.new_finalized_slot, 10076224);
 
         assert_eq!(client.lcs.finalized_header.slot, 10076224);
     }
+
+    #[test]
+    fn update_batch() {
+        let (bootstrap, mut updates, finality_update) = fixtures();
+        updates.push(finality_update);
+
+        let mut client = LightClient::new(bootstrap, Network::ethereum_mainnet()).unwrap();
+        let slot = client.slot();
+
+        // a failure at the end of the batch discards the earlier updates
+        let mut bad_batch = updates.clone();
+        bad_batch.last_mut().unwrap().finalized_header = Header::default();
+        let err = client.update_batch(bad_batch, 1727740110).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains(&format!("Update {} of batch failed", updates.len() - 1)));
+        assert_eq!(client.slot(), slot);
+        assert!(client.last_update_at().is_none());
+
+        let len = updates.len();
+        assert_eq!(client.update_batch(updates, 1727740110).unwrap(), len);
+        assert_eq!(client.slot(), 10076224);
+    }
+
+    #[test]
+    fn sync_committee_ssz() {
+        let mut sc = SyncCommittee::default();
+        let pk_bytes: Vec<_> = (0..48).collect();
+        sc.0.aggregate_pubkey = HeliosPublicKey::from_ssz_bytes(&pk_bytes).unwrap();
+
+        let ssz_bytes = sc.to_ssz_bytes();
+        assert_eq!(ssz_bytes.len(), 512 * 48 + 48);
+        assert_eq!(&ssz_bytes[512 * 48..], pk_bytes.as_slice());
+
+        let decoded = SyncCommittee::from_ssz_bytes(&ssz_bytes).unwrap();
+        assert_eq!(decoded.to_ssz_bytes(), ssz_bytes);
+        assert_eq!(decoded.encode().unwrap(), sc.encode().unwrap());
+
+        let ed_decoded = SyncCommittee::decode(sc.encode().unwrap().as_slice()).unwrap();
+        assert_eq!(ed_decoded.to_ssz_bytes(), ssz_bytes);
+
+        let err = SyncCommittee::from_ssz_bytes(&ssz_bytes[1..]).unwrap_err();
+        assert!(matches!(err, Error::EthereumSsz(_)));
+        assert!(err.to_string().contains("Invalid sync committee"));
+
+        let err = ssz_decode_error(
+            "BLS public key",
+            ssz::DecodeError::BytesInvalid("bad".into()),
+        );
+        assert!(err.to_string().contains("Invalid BLS public key"));
+    }
+
+    #[test]
+    fn apply_failure() {
+        let mut client = LightClient::default();
+        client.lcs.finalized_header.slot = 123;
+
+        let err = client
+            .apply_checked(|lcs| {
+                lcs.finalized_header.slot = 456;
+                panic!("invariant violated");
+            })
+            .unwrap_err();
+        assert!(matches!(err, Error::EthereumConsensus(_)));
+        assert_eq!(client.slot(), 123);
+
+        client
+            .apply_checked(|lcs| lcs.finalized_header.slot = 789)
+            .unwrap();
+        assert_eq!(client.slot(), 789);
+    }
+
+    #[tokio::test]
+    async fn bootstrap_from_rpc() {
+        use warp::Filter;
+
+        let fixtures: serde_json::Value =
+            serde_json::from_str(include_str!("test_fixtures.json")).unwrap();
+        let bootstrap_json = fixtures[0].clone();
+        let bootstrap: Response<Bootstrap> =
+            serde_json::from_value(bootstrap_json.clone()).unwrap();
+        let root: Bytes32 = bootstrap.data.header.tree_hash_root().0.into();
+
+        let route = warp::path!("eth" / "v1" / "beacon" / "light_client" / "bootstrap" / String)
+            .map(move |_root| warp::reply::json(&bootstrap_json));
+        let (addr, server) = warp::serve(route).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+        let url = format!("http://{}", addr);
+
+        let client = LightClient::bootstrap_from_rpc(&url, root, Network::ethereum_mainnet())
+            .await
+            .unwrap();
+        assert_eq!(client.slot(), bootstrap.data.header.slot);
+
+        let wrong_root = Bytes32([1; 32]);
+        assert!(
+            LightClient::bootstrap_from_rpc(&url, wrong_root, Network::ethereum_mainnet())
+                .await
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn diff_round_trip() {
+        let (bootstrap, updates, finality_update) = fixtures();
+
+        let a = LightClient::new(bootstrap, Network::ethereum_mainnet()).unwrap();
+        let mut b = a.clone();
+        for update in updates {
+            b.update(update, 1727740110).unwrap();
+        }
+
+        let patch = a.diff(&b).unwrap();
+        let mut patched = a.clone();
+        patched.apply_diff(&patch).unwrap();
+        assert_eq!(patched.encode().unwrap(), b.encode().unwrap());
+
+        // a finality-only update produces a much smaller patch
+        let mut c = b.clone();
+        c.update(finality_update, 1727740110).unwrap();
+        let patch = b.diff(&c).unwrap();
+        assert!(patch.len() < 1_000);
+        b.apply_diff(&patch).unwrap();
+        assert_eq!(b.encode().unwrap(), c.encode().unwrap());
+
+        assert_eq!(c.diff(&c).unwrap(), vec![0]);
+    }
+
+    #[test]
+    fn diff_rejected_atomically() {
+        let (bootstrap, updates, finality_update) = fixtures();
+
+        let a = LightClient::new(bootstrap, Network::ethereum_mainnet()).unwrap();
+        let mut b = a.clone();
+        for update in updates {
+            b.update(update, 1727740110).unwrap();
+        }
+        b.update(finality_update, 1727740110).unwrap();
+        let patch = a.diff(&b).unwrap();
+        let original = a.encode().unwrap();
+
+        // a truncated patch fails partway through without touching the client
+        let mut client = a.clone();
+        client.apply_diff(&patch[..patch.len() - 1]).unwrap_err();
+        assert_eq!(client.encode().unwrap(), original);
+        assert!(client.to_bootstrap().is_ok());
+
+        let mut trailing = patch.clone();
+        trailing.push(0);
+        client.apply_diff(&trailing).unwrap_err();
+        assert_eq!(client.encode().unwrap(), original);
+
+        // a well-formed patch producing a corrupt store is rejected
+        let mut corrupt = b.clone();
+        corrupt.lcs.optimistic_header.slot = corrupt.lcs.finalized_header.slot - 1;
+        let patch = a.diff(&corrupt).unwrap();
+        client.apply_diff(&patch).unwrap_err();
+        assert_eq!(client.encode().unwrap(), original);
+
+        // a patch can't pair the finalized header with another header's proof
+        let mut corrupt = b.clone();
+        corrupt.last_finality_proof = Some(FinalityAttestation {
+            finalized_header: a.finalized_header(),
+            ..b.finality_attestation().unwrap()
+        });
+        let patch = a.diff(&corrupt).unwrap();
+        client.apply_diff(&patch).unwrap_err();
+        assert_eq!(client.encode().unwrap(), original);
+
+        // an applied patch drops proofs which only held for the old state, and
+        // carries over the persisted finality proof
+        let patch = a.diff(&b).unwrap();
+        client.apply_diff(&patch).unwrap();
+        assert!(client.bootstrap_branch.is_none());
+        assert_eq!(client.finalized_history().last().unwrap().slot, b.slot());
+        assert_eq!(
+            client.finality_attestation().unwrap().encode().unwrap(),
+            b.finality_attestation().unwrap().encode().unwrap()
+        );
+    }
+
+    #[test]
+    fn store_bytes_len() {
+        let mut client = LightClient::default();
+        assert_eq!(client.store_bytes_len(), client.encode().unwrap().len());
+        assert_eq!(
+            client.store_bytes_len(),
+            LightClient::MAX_ENCODED_LEN - SYNC_COMMITTEE_ENCODED_LEN
+        );
+
+        client.lcs.next_sync_committee = Some(Default::default());
+        assert_eq!(client.store_bytes_len(), client.encode().unwrap().len());
+        assert_eq!(
+            client.store_bytes_len(),
+            LightClient::MAX_ENCODED_LEN - FinalityAttestation::MAX_ENCODED_LEN - 8
+        );
+
+        client.last_finality_proof = Some(FinalityAttestation {
+            attested_header: Header::default(),
+            finalized_header: Header::default(),
+            finality_branch: vec![Bytes32::default(); MAX_FINALITY_BRANCH_LEN]
+                .try_into()
+                .unwrap(),
+            sync_aggregate: SyncAggregate::default(),
+            signature_slot: 0,
+        });
+        client.last_update_at = Some(0);
+        assert_eq!(client.store_bytes_len(), client.encode().unwrap().len());
+        assert_eq!(client.store_bytes_len(), LightClient::MAX_ENCODED_LEN);
+    }
+
+    #[test]
+    fn verify_receipt() {
+        use ethereum_triedb::{keccak::KeccakHasher, EIP1186Layout, StorageProof};
+        use primitive_types::H256;
+        use trie_db::{Recorder, Trie, TrieDBBuilder, TrieDBMutBuilder, TrieMut};
+        type Layout = EIP1186Layout<KeccakHasher>;
+
+        // TODO: add a mainnet receipt, receipts root and proof next to
+        // test_fixtures.json, captured from an archive node
+
+        // build a receipts trie of typed (EIP-1559) receipts with a few logs
+        // each, keyed by the RLP-encoded transaction index
+        let receipts: Vec<Vec<u8>> = (0..40u64)
+            .map(|i| {
+                let mut stream = rlp::RlpStream::new_list(4);
+                stream.append(&1u8);
+                stream.append(&(21_000 * (i + 1)));
+                stream.append(&vec![0u8; 256]);
+                stream.begin_list(i as usize % 3);
+                for log in 0..i % 3 {
+                    stream.begin_list(3);
+                    stream.append(&vec![log as u8; 20]);
+                    stream.begin_list(1).append(&vec![0xdd; 32]);
+                    stream.append(&vec![i as u8; 64]);
+                }
+                [vec![2], stream.out().to_vec()].concat()
+            })
+            .collect();
+        let mut db = StorageProof::new(vec![]).into_memory_db::<KeccakHasher>();
+        let mut root = H256::default();
+        {
+            let mut trie = TrieDBMutBuilder::<Layout>::new(&mut db, &mut root).build();
+            for (i, receipt) in receipts.iter().enumerate() {
+                trie.insert(&rlp::encode(&(i as u64)), receipt).unwrap();
+            }
+        }
+
+        let prove = |index: u64| -> Vec<Vec<u8>> {
+            let mut recorder = Recorder::<Layout>::new();
+            let trie = TrieDBBuilder::<Layout>::new(&db, &root)
+                .with_recorder(&mut recorder)
+                .build();
+            trie.get(&rlp::encode(&index)).unwrap().unwrap();
+            drop(trie);
+            recorder
+                .drain()
+                .into_iter()
+                .map(|record| record.data)
+                .collect()
+        };
+
+        let client = LightClient::default();
+        let receipts_root = Bytes32(root.0);
+        for index in [0, 1, 17, 39] {
+            let proof = prove(index);
+            client
+                .verify_receipt(
+                    receipts_root.clone(),
+                    index,
+                    &receipts[index as usize],
+                    &proof,
+                )
+                .unwrap();
+        }
+
+        // a different receipt, index, or root is rejected
+        let proof = prove(17);
+        assert!(client
+            .verify_receipt(receipts_root.clone(), 17, &receipts[18], &proof)
+            .is_err());
+        assert!(client
+            .verify_receipt(receipts_root.clone(), 18, &receipts[18], &proof)
+            .is_err());
+        assert!(client
+            .verify_receipt(Bytes32([1; 32]), 17, &receipts[17], &proof)
+            .is_err());
+        assert!(client
+            .verify_receipt(receipts_root, 17, &receipts[17], &proof[1..])
+            .is_err());
+    }
+
+    #[test]
+    fn verify_state_proof() {
+        use sha2::{Digest, Sha256};
+        let hash = |a: [u8; 32], b: [u8; 32]| -> [u8; 32] {
+            let mut hasher = Sha256::new();
+            hasher.update(a);
+            hasher.update(b);
+            hasher.finalize().into()
+        };
+
+        // a depth-2 tree with leaves at generalized indices 4 to 7
+        let leaves: Vec<[u8; 32]> = (0..4).map(|i| [i; 32]).collect();
+        let left = hash(leaves[0], leaves[1]);
+        let right = hash(leaves[2], leaves[3]);
+        let root = hash(left, right);
+
+        let mut client = LightClient::default();
+        client.lcs.finalized_header.state_root.0 = root;
+
+        let branch = [Bytes32(leaves[3]), Bytes32(left)];
+        assert!(client
+            .verify_state_proof(Bytes32(leaves[2]), &branch, 6)
+            .unwrap());
+        assert!(!client
+            .verify_state_proof(Bytes32(leaves[3]), &branch, 6)
+            .unwrap());
+        assert!(!client
+            .verify_state_proof(Bytes32(leaves[2]), &branch, 7)
+            .unwrap());
+        assert!(client
+            .verify_state_proof(Bytes32(leaves[2]), &branch, 12)
+            .is_err());
+        assert!(client
+            .verify_state_proof(Bytes32(leaves[2]), &branch, 0)
+            .is_err());
+
+        // the finality branch of a real update proves the finalized block root
+        // at generalized index 105 of the attested header's state
+        let (_, _, update) = fixtures();
+        let leaf = Bytes32(update.finalized_header.tree_hash_root().0);
+        let root = Bytes32(update.attested_header.state_root.0);
+        assert!(verify_merkle_branch(leaf, &update.finality_branch, 105, root).unwrap());
+    }
+
+    #[test]
+    fn bytes32_json_forms() {
+        let mut expected = [0; 32];
+        expected[30] = 0x12;
+        expected[31] = 0xab;
+        let hex = hex::encode(expected);
+
+        let forms = [
+            format!("\"0x{}\"", hex),
+            format!("\"{}\"", hex),
+            format!("\"{}\"", hex.to_uppercase()),
+            "\"0x12ab\"".to_string(),
+            "\"12ab\"".to_string(),
+            serde_json::to_string(&expected.to_vec()).unwrap(),
+        ];
+        for form in forms {
+            let bytes: Bytes32 = serde_json::from_str(&form).unwrap();
+            assert_eq!(bytes.0, expected, "{}", form);
+        }
+
+        // always serialized in the canonical form
+        let bytes = Bytes32(expected);
+        assert_eq!(
+            serde_json::to_string(&bytes).unwrap(),
+            format!("\"0x{}\"", hex)
+        );
+
+        for invalid in [
+            "\"\"".to_string(),
+            "\"0x\"".to_string(),
+            "\"0xzz\"".to_string(),
+            format!("\"0x00{}\"", hex),
+            serde_json::to_string(&vec![0u8; 31]).unwrap(),
+            serde_json::to_string(&vec![0u8; 33]).unwrap(),
+            serde_json::to_string(&vec![256u32; 32]).unwrap(),
+        ] {
+            assert!(
+                serde_json::from_str::<Bytes32>(&invalid).is_err(),
+                "{}",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn field_keyop() {
+        use orga::describe::KeyOp;
+
+        for field in LightClient::ENCODED_FIELDS {
+            let keyop = <LightClient as State>::field_keyop(field);
+            assert!(matches!(keyop, Some(KeyOp::Append(prefix)) if prefix.is_empty()));
+        }
+        assert!(<LightClient as State>::field_keyop("divergence_threshold").is_none());
+        assert!(<LightClient as State>::field_keyop("unknown").is_none());
+    }
+
+    #[test]
+    fn encoding_length() {
+        let (bootstrap, updates, finality_update) = fixtures();
+
+        let header = bootstrap.header.clone();
+        assert_eq!(header.encode().unwrap().len(), HEADER_ENCODED_LEN);
+        assert_eq!(header.encoding_length().unwrap(), HEADER_ENCODED_LEN);
+        let committee = bootstrap.current_sync_committee.clone();
+        assert_eq!(
+            committee.encode().unwrap().len(),
+            SYNC_COMMITTEE_ENCODED_LEN
+        );
+        assert_eq!(
+            committee.encoding_length().unwrap(),
+            SYNC_COMMITTEE_ENCODED_LEN
+        );
+
+        let mut client = LightClient::new(bootstrap, Network::ethereum_mainnet()).unwrap();
+        assert_eq!(
+            client.encoding_length().unwrap(),
+            client.encode().unwrap().len()
+        );
+        for update in updates.iter().chain([&finality_update]) {
+            client.update(update.clone(), 1727740110).unwrap();
+            assert_eq!(
+                client.encoding_length().unwrap(),
+                client.encode().unwrap().len()
+            );
+        }
+        assert!(client.encoding_length().unwrap() <= LightClient::MAX_ENCODED_LEN);
+    }
+
+    #[test]
+    fn next_sync_committee_encoding() {
+        let (bootstrap, updates, _) = fixtures();
+        let presence_offset = HEADER_ENCODED_LEN + SYNC_COMMITTEE_ENCODED_LEN;
+
+        let mut client = LightClient::new(bootstrap, Network::ethereum_mainnet()).unwrap();
+        assert!(client.lcs.next_sync_committee.is_none());
+        let bytes = client.encode().unwrap();
+        assert_eq!(bytes.len(), client.encoding_length().unwrap());
+        assert_eq!(bytes[presence_offset], 0);
+        let decoded = LightClient::decode(bytes.as_slice()).unwrap();
+        assert!(decoded.lcs.next_sync_committee.is_none());
+        assert_eq!(decoded.encode().unwrap(), bytes);
+
+        client.update(updates[0].clone(), 1727740110).unwrap();
+        assert!(client.lcs.next_sync_committee.is_some());
+        let bytes = client.encode().unwrap();
+        assert_eq!(bytes.len(), client.encoding_length().unwrap());
+        assert_eq!(bytes[presence_offset], 1);
+        let decoded = LightClient::decode(bytes.as_slice()).unwrap();
+        assert_eq!(
+            decoded
+                .lcs
+                .next_sync_committee
+                .as_ref()
+                .map(|sc| sc.tree_hash_root()),
+            client
+                .lcs
+                .next_sync_committee
+                .as_ref()
+                .map(|sc| sc.tree_hash_root())
+        );
+        assert_eq!(decoded.encode().unwrap(), bytes);
+
+        // the presence byte must be 0 or 1
+        let mut bad = bytes.clone();
+        bad[presence_offset] = 2;
+        assert!(LightClient::decode(bad.as_slice()).is_err());
+    }
+
+    #[test]
+    fn network_validation() {
+        Network::ethereum_mainnet().validate().unwrap();
+
+        let mut network = Network::ethereum_mainnet();
+        network.genesis_time *= 1_000;
+        assert!(network.validate().is_err());
+
+        network.genesis_time = 0;
+        assert!(network.validate().is_err());
+
+        let mut network = Network::ethereum_mainnet();
+        network.deneb_fork_version = 0;
+        assert!(network.validate().is_err());
+
+        let mut network = Network::ethereum_mainnet();
+        network.genesis_vals_root = Bytes32([0; 32]);
+        let err = network.validate().unwrap_err();
+        assert!(err.to_string().contains("Genesis validators root"));
+
+        let (bootstrap, updates, _) = fixtures();
+        assert!(LightClient::new(bootstrap.clone(), network).is_err());
+
+        // an update checked at a time before genesis is rejected rather than
+        // underflowing
+        let network = Network::ethereum_mainnet();
+        let genesis_time = network.genesis_time;
+        let mut client = LightClient::new(bootstrap, network).unwrap();
+        let err = client
+            .update(updates[0].clone(), genesis_time - 1)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::LightClient(LightClientError::BeforeGenesis)
+        ));
+    }
+
+    #[test]
+    fn prune_finalized_history() {
+        let mut client = LightClient::default();
+        for slot in [100, 200, 300, 400] {
+            client.lcs.finalized_header.slot = slot;
+            client.record_finalized_header();
+        }
+        // not recorded since it does not advance finality
+        client.record_finalized_header();
+        assert_eq!(client.finalized_history().count(), 4);
+
+        client.prune_finalized_history(250);
+        let slots: Vec<_> = client.finalized_history().map(|h| h.slot).collect();
+        assert_eq!(slots, vec![300, 400]);
+
+        client.prune_finalized_history(1_000);
+        assert_eq!(client.finalized_history().count(), 0);
+    }
+
+    #[test]
+    fn head_divergence() {
+        let mut client = LightClient::default();
+        client.lcs.finalized_header.slot = 1_000;
+        client.lcs.optimistic_header.slot = 1_000 + DEFAULT_DIVERGENCE_THRESHOLD;
+        assert_eq!(
+            client.optimistic_finality_gap(),
+            DEFAULT_DIVERGENCE_THRESHOLD
+        );
+        assert!(!client.is_head_diverging());
+
+        client.lcs.optimistic_header.slot += 1;
+        assert!(client.is_head_diverging());
+
+        let mut client = client.with_divergence_threshold(1_000);
+        assert!(!client.is_head_diverging());
+
+        client.lcs.optimistic_header.slot = 2_001;
+        assert_eq!(client.optimistic_finality_gap(), 1_001);
+        assert!(client.is_head_diverging());
+
+        // an optimistic header behind finality is not a gap
+        client.lcs.optimistic_header.slot = 500;
+        assert_eq!(client.optimistic_finality_gap(), 0);
+        assert!(!client.is_head_diverging());
+    }
+
+    #[test]
+    fn current_fork() {
+        let mut client = LightClient::default();
+        client.network = Network::ethereum_mainnet();
+
+        let boundaries = [
+            (
+                client.network.bellatrix_fork_epoch,
+                Fork::Altair,
+                Fork::Bellatrix,
+            ),
+            (
+                client.network.capella_fork_epoch,
+                Fork::Bellatrix,
+                Fork::Capella,
+            ),
+            (client.network.deneb_fork_epoch, Fork::Capella, Fork::Deneb),
+            (
+                client.network.electra_fork_epoch,
+                Fork::Deneb,
+                Fork::Electra,
+            ),
+        ];
+        for (epoch, before, after) in boundaries {
+            client.lcs.finalized_header.slot = epoch * SLOTS_PER_EPOCH - 1;
+            assert_eq!(client.current_fork(), before);
+            client.lcs.finalized_header.slot = epoch * SLOTS_PER_EPOCH;
+            assert_eq!(client.current_fork(), after);
+        }
+
+        client.lcs.finalized_header.slot = client.network.altair_fork_epoch * SLOTS_PER_EPOCH;
+        assert_eq!(client.current_fork(), Fork::Altair);
+        client.lcs.finalized_header.slot = 0;
+        assert_eq!(client.current_fork(), Fork::Altair);
+    }
+
+    #[test]
+    fn empty_finalized_header() {
+        let (bootstrap, _, _) = fixtures();
+        let json: serde_json::Value =
+            serde_json::from_str(include_str!("test_fixtures.json")).unwrap();
+
+        // the beacon API serves an all-zero finalized header this way
+        let mut finality_update = json[2].clone();
+        let header = &mut finality_update["data"]["finalized_header"]["beacon"];
+        for field in ["slot", "proposer_index"] {
+            header[field] = "0".into();
+        }
+        for field in ["parent_root", "state_root", "body_root"] {
+            header[field] = format!("0x{}", "00".repeat(32)).into();
+        }
+        let update: Response<Update> = serde_json::from_value(finality_update).unwrap();
+        assert!(update.data.finalized_header.is_empty().unwrap());
+
+        let mut client = LightClient::new(bootstrap, Network::ethereum_mainnet()).unwrap();
+        let err = client.update(update.data, 1727740110).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::LightClient(LightClientError::EmptyFinalizedHeader)
+        ));
+    }
+
+    #[test]
+    fn forks_from_network() {
+        let network = Network::ethereum_mainnet();
+        let forks = Forks::from(&network);
+        assert_eq!(forks.altair.epoch, 74240);
+        assert_eq!(forks.bellatrix.epoch, 144896);
+        assert_eq!(forks.capella.epoch, 194048);
+        assert_eq!(forks.deneb.epoch, 269568);
+        assert_eq!(forks.deneb.fork_version, (&[4, 0, 0, 0]).into());
+
+        let (bootstrap, updates, _) = fixtures();
+        let update = updates[0].clone();
+
+        let mut client = LightClient::new(bootstrap, network).unwrap();
+        assert!(client.forks.is_none());
+        client.clone().update(update.clone(), 1727740110).unwrap();
+
+        // update verifies against the cached forks rather than rebuilding them
+        let mut forks = Forks::from(&client.network);
+        forks.deneb.fork_version = (&[0, 0, 0, 0]).into();
+        client.forks = Some(forks);
+        assert!(client.update(update, 1727740110).is_err());
+    }
+
+    #[test]
+    fn slot_conversions() {
+        assert_eq!(slots_per_period(), 8192);
+
+        assert_eq!(epoch_of_slot(0), 0);
+        assert_eq!(epoch_of_slot(31), 0);
+        assert_eq!(epoch_of_slot(32), 1);
+        assert_eq!(epoch_of_slot(10076224), 314882);
+
+        assert_eq!(period_of_slot(0), 0);
+        assert_eq!(period_of_slot(8191), 0);
+        assert_eq!(period_of_slot(8192), 1);
+        assert_eq!(period_of_slot(10076224), 1230);
+    }
+
+    #[test]
+    fn aggregate_pubkeys() {
+        let mut client = LightClient::default();
+        let pk_str = serde_json::to_string(&client.current_aggregate_pubkey()).unwrap();
+        assert_eq!(pk_str, format!("\"0x{}\"", "00".repeat(48)));
+        assert!(client.next_aggregate_pubkey().is_none());
+
+        client.lcs.next_sync_committee = Some(Default::default());
+        assert_eq!(
+            serde_json::to_string(&client.next_aggregate_pubkey().unwrap()).unwrap(),
+            pk_str
+        );
+    }
+
+    #[test]
+    fn load_integrity_check() {
+        use orga::prelude::Store;
+
+        let (bootstrap, _, _) = fixtures();
+        let client = LightClient::new(bootstrap, Network::ethereum_mainnet()).unwrap();
+
+        let load = |client: &LightClient| {
+            let bytes = client.encode().unwrap();
+            LightClient::load(Store::with_map_store(), &mut bytes.as_slice())
+        };
+        load(&client).unwrap();
+
+        let mut corrupt = client.clone();
+        corrupt.lcs.optimistic_header.slot = corrupt.lcs.finalized_header.slot - 1;
+        assert!(load(&corrupt).is_err());
+
+        let mut corrupt = client.clone();
+        corrupt.lcs.current_max_active_participants = 513;
+        assert!(load(&corrupt).is_err());
+
+        assert!(load(&LightClient::default()).is_err());
+    }
+
+    #[test]
+    fn migrate_legacy_network() {
+        use orga::prelude::Store;
+
+        let (bootstrap, _, _) = fixtures();
+        let client = LightClient::new(bootstrap, Network::ethereum_mainnet()).unwrap();
+        let migrate = |bytes: &[u8]| -> orga::Result<LightClient> {
+            let mut bytes = bytes;
+            let client =
+                LightClient::migrate(Store::with_map_store(), Store::with_map_store(), &mut bytes)?;
+            assert!(bytes.is_empty());
+            Ok(client)
+        };
+
+        let bytes = client.encode().unwrap();
+        let migrated = migrate(&bytes).unwrap();
+        assert_eq!(migrated.encode().unwrap(), bytes);
+
+        // the layouts before the finality proof and update time were
+        // persisted, which ended with the network
+        assert!(client.last_finality_proof.is_none());
+        assert!(client.last_update_at.is_none());
+        let migrated = migrate(&bytes[..bytes.len() - 1]).unwrap();
+        assert_eq!(migrated.encode().unwrap(), bytes);
+        let network_end = bytes.len() - 2;
+        let migrated = migrate(&bytes[..network_end]).unwrap();
+        assert_eq!(migrated.encode().unwrap(), bytes);
+
+        // the layout before the fork schedule was added to the network
+        let mut legacy = bytes[..network_end - NETWORK_ENCODED_LEN].to_vec();
+        legacy.extend_from_slice(&client.network.genesis_vals_root.0);
+        legacy.extend_from_slice(&client.network.deneb_fork_version.encode().unwrap());
+        legacy.extend_from_slice(&client.network.genesis_time.encode().unwrap());
+        assert_eq!(
+            legacy.len(),
+            network_end - NETWORK_ENCODED_LEN + LEGACY_NETWORK_ENCODED_LEN
+        );
+
+        let migrated = migrate(&legacy).unwrap();
+        assert_eq!(migrated.slot(), client.slot());
+        assert_eq!(migrated.network.genesis_time, client.network.genesis_time);
+        assert_eq!(
+            migrated.network.electra_fork_epoch,
+            Network::ethereum_mainnet().electra_fork_epoch
+        );
+        assert_eq!(migrated.encode().unwrap(), bytes);
+
+        assert!(migrate(&legacy[..legacy.len() - 1]).is_err());
+
+        // migrated state gets the same integrity checks as loaded state
+        let mut corrupt = client.clone();
+        corrupt.lcs.current_max_active_participants = SYNC_COMMITTEE_SIZE as u64 + 1;
+        let err = migrate(&corrupt.encode().unwrap()).unwrap_err();
+        assert!(err.to_string().contains("Corrupt light client state"));
+    }
+
+    #[test]
+    fn validate_store() {
+        let (bootstrap, updates, _) = fixtures();
+        let mut client = LightClient::new(bootstrap, Network::ethereum_mainnet()).unwrap();
+        client.validate_store().unwrap();
+        for update in updates {
+            client.update(update, 1727740110).unwrap();
+        }
+        client.validate_store().unwrap();
+
+        // the finalized header is from a period the committees don't cover
+        let mut invalid = client.clone();
+        invalid.lcs.finalized_header.slot -= 2 * slots_per_period();
+        assert!(invalid.validate_store().is_err());
+
+        let mut invalid = client.clone();
+        invalid.lcs.next_sync_committee = Some(invalid.lcs.current_sync_committee.clone());
+        assert!(invalid.validate_store().is_err());
+
+        assert!(LightClient::default().validate_store().is_err());
+    }
+
+    #[test]
+    fn fork_versions() {
+        let network = Network::ethereum_mainnet();
+        assert_eq!(network.deneb_fork_version, 4);
+        assert_eq!(network.electra_fork_epoch, 364032);
+
+        let electra_slot = network.electra_fork_epoch * SLOTS_PER_EPOCH;
+        let fork = network.fork_at_epoch(epoch_of_slot(electra_slot - 1));
+        assert_eq!(network.fork_version(fork), 4);
+        let fork = network.fork_at_epoch(epoch_of_slot(electra_slot));
+        assert_eq!(network.fork_version(fork), 5);
+
+        let forks = Forks::from(&network);
+        assert_eq!(forks.altair.fork_version, (&[1, 0, 0, 0]).into());
+        assert_eq!(forks.bellatrix.fork_version, (&[2, 0, 0, 0]).into());
+        assert_eq!(forks.capella.fork_version, (&[3, 0, 0, 0]).into());
+    }
+
+    #[test]
+    fn cross_fork_branches() {
+        use sha2::{Digest, Sha256};
+
+        // a synthetic `BeaconState` tree of the given depth with some nodes
+        // fixed and arbitrary leaves elsewhere
+        fn node(fixed: &BTreeMap<u64, [u8; 32]>, depth: u32, gindex: u64) -> [u8; 32] {
+            if let Some(node) = fixed.get(&gindex) {
+                return *node;
+            }
+            if gindex.ilog2() == depth {
+                return [gindex as u8; 32];
+            }
+            let mut hasher = Sha256::new();
+            hasher.update(node(fixed, depth, 2 * gindex));
+            hasher.update(node(fixed, depth, 2 * gindex + 1));
+            hasher.finalize().into()
+        }
+        fn branch(fixed: &BTreeMap<u64, [u8; 32]>, depth: u32, gindex: u64) -> Vec<Bytes32> {
+            let mut branch = vec![];
+            let mut gindex = gindex;
+            while gindex > 1 {
+                branch.push(Bytes32(node(fixed, depth, gindex ^ 1)));
+                gindex >>= 1;
+            }
+            branch
+        }
+
+        let (bootstrap, updates, _) = fixtures();
+        let network = Network::ethereum_mainnet();
+        for update in &updates {
+            assert_eq!(update.verify_branches(&network).unwrap(), Fork::Deneb);
+        }
+
+        // moves the template update's headers to the given slots, and proves
+        // its finalized header and next sync committee into the attested
+        // state with the gindices of `shape`
+        let straddling = |attested_slot: u64, finalized_slot: u64, shape: Fork| {
+            let mut update = updates[0].clone();
+            update.attested_header.0.slot = attested_slot;
+            update.finalized_header.0.slot = finalized_slot;
+
+            let finalized_gindex = shape.finalized_root_gindex();
+            let committee_gindex = shape.next_sync_committee_gindex();
+            let committee = update.next_sync_committee.as_ref().unwrap();
+            let fixed = BTreeMap::from([
+                (finalized_gindex, update.finalized_header.tree_hash_root().0),
+                (committee_gindex, committee.tree_hash_root().0),
+            ]);
+            let depth = finalized_gindex.ilog2();
+
+            update.attested_header.0.state_root.0 = node(&fixed, depth, 1);
+            update.finality_branch = branch(&fixed, depth, finalized_gindex).try_into().unwrap();
+            update.next_sync_committee_branch =
+                Some(branch(&fixed, depth, committee_gindex).try_into().unwrap());
+            update
+        };
+        let deneb_slot = network.deneb_fork_epoch * SLOTS_PER_EPOCH;
+        let electra_slot = network.electra_fork_epoch * SLOTS_PER_EPOCH;
+
+        // finalized under Capella, attested under Deneb
+        let update = straddling(deneb_slot + 32, deneb_slot - 64, Fork::Deneb);
+        assert_eq!(update.verify_branches(&network).unwrap(), Fork::Deneb);
+
+        // the signature fork does not affect the branches
+        let mut update = straddling(electra_slot - 1, electra_slot - 65, Fork::Deneb);
+        update.signature_slot = electra_slot + 1;
+        assert_eq!(update.verify_branches(&network).unwrap(), Fork::Deneb);
+
+        // finalized under Deneb, attested under Electra: the branches are
+        // shaped by the attested header's fork
+        let update = straddling(electra_slot + 32, electra_slot - 64, Fork::Electra);
+        assert_eq!(update.verify_branches(&network).unwrap(), Fork::Electra);
+        let update = straddling(electra_slot + 32, electra_slot - 64, Fork::Deneb);
+        assert!(update.verify_branches(&network).is_err());
+        let update = straddling(electra_slot - 32, electra_slot - 64, Fork::Electra);
+        assert!(update.verify_branches(&network).is_err());
+
+        let mut tampered = straddling(deneb_slot + 32, deneb_slot - 64, Fork::Deneb);
+        tampered.finalized_header.0.slot += 1;
+        let err = tampered.verify_branches(&network).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::LightClient(LightClientError::InvalidProof(_))
+        ));
+
+        assert_eq!(bootstrap.verify_branch(&network).unwrap(), Fork::Deneb);
+
+        // a network which activates Electra within the bootstrap's period, so
+        // an Electra-attested update gets through to the sync committee
+        // signature, which no longer matches the moved attested header
+        let electra_epoch = epoch_of_slot(bootstrap.header.slot) + 4;
+        let electra_network = Network::with_forks(
+            network.genesis_vals_root.clone(),
+            network.genesis_time,
+            [1, 2, 3, 4, 5],
+            [74240, 144896, 194048, 269568, electra_epoch],
+        );
+        let electra_slot = electra_epoch * SLOTS_PER_EPOCH;
+        let mut client = LightClient::new(bootstrap.clone(), electra_network.clone()).unwrap();
+        let slot = client.slot();
+
+        let update = straddling(electra_slot + 32, electra_slot - 64, Fork::Electra);
+        assert!(update.signature_slot > electra_slot + 32);
+        let err = client.update(update, 1727740110).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::LightClient(LightClientError::InvalidSignature(_))
+        ));
+        let update = straddling(electra_slot + 32, electra_slot - 64, Fork::Deneb);
+        client.update(update, 1727740110).unwrap_err();
+        assert_eq!(client.slot(), slot);
+
+        // a bootstrap from the Electra state needs a deeper committee branch
+        let mut bootstrap = bootstrap;
+        bootstrap.header.0.slot = electra_slot;
+        assert!(bootstrap.verify_branch(&electra_network).is_err());
+    }
+
+    #[test]
+    fn update_error_classification() {
+        let err = update_error(Some(&ConsensusError::InvalidPeriod), "stale".to_string());
+        assert!(matches!(err, LightClientError::StaleUpdate(_)));
+        let err = update_error(
+            Some(&ConsensusError::InvalidNextSyncCommitteeProof),
+            "bad branch".to_string(),
+        );
+        assert!(matches!(err, LightClientError::InvalidProof(_)));
+        let err = update_error(Some(&ConsensusError::InvalidSignature), String::new());
+        assert!(matches!(err, LightClientError::InvalidSignature(_)));
+
+        // messages are not inspected, so a forged one can't pass as stale
+        let err = update_error(None, "invalid sync committee period".to_string());
+        assert!(matches!(err, LightClientError::InvalidUpdate(_)));
+        let err = update_error(
+            Some(&ConsensusError::InsufficientParticipation),
+            "signature period proof".to_string(),
+        );
+        assert!(matches!(err, LightClientError::InvalidUpdate(_)));
+    }
+
+    #[test]
+    fn finality_attestation() {
+        let (bootstrap, updates, finality_update) = fixtures();
+
+        let mut client = LightClient::new(bootstrap, Network::ethereum_mainnet()).unwrap();
+        assert!(client.finality_attestation().is_none());
+        for update in updates {
+            client.update(update, 1727740110).unwrap();
+        }
+
+        let signature_slot = finality_update.signature_slot;
+        client.update(finality_update, 1727740110).unwrap();
+        let attestation = client.finality_attestation().unwrap();
+        assert_eq!(
+            attestation.finalized_header.state_root.0,
+            client.state_root().0
+        );
+        assert_eq!(attestation.finalized_header.slot, client.slot());
+        assert_eq!(attestation.signature_slot, signature_slot);
+
+        // the proof is part of the encoded state
+        let decoded = LightClient::decode(client.encode().unwrap().as_slice()).unwrap();
+        assert_eq!(
+            decoded.finality_attestation().unwrap().encode().unwrap(),
+            attestation.encode().unwrap()
+        );
+        assert_eq!(decoded.participation(), client.participation());
+    }
+
+    #[test]
+    fn update_with_genesis_root() {
+        let (bootstrap, updates, _) = fixtures();
+        let update = updates[0].clone();
+
+        let network = Network::ethereum_mainnet();
+        let mut client = LightClient::new(bootstrap, network.clone()).unwrap();
+        let slot = client.slot();
+
+        let err = client
+            .update_with_genesis_root(update.clone(), 1727740110, Bytes32([1; 32]))
+            .unwrap_err();
+        assert!(err.to_string().contains("Invalid update"));
+        assert!(matches!(
+            err,
+            Error::LightClient(LightClientError::InvalidSignature(_))
+        ));
+        assert_eq!(client.slot(), slot);
+
+        client
+            .update_with_genesis_root(update, 1727740110, network.genesis_vals_root.clone())
+            .unwrap();
+        assert!(client.slot() > slot);
+        assert_eq!(
+            client.network.genesis_vals_root.0,
+            network.genesis_vals_root.0
+        );
+    }
+
+    #[test]
+    fn state_summary() {
+        let (bootstrap, updates, finality_update) = fixtures();
+
+        let mut client = LightClient::new(bootstrap, Network::ethereum_mainnet()).unwrap();
+        let now = 1727740110;
+        for update in updates {
+            client.update(update, now).unwrap();
+        }
+        client.update(finality_update, now).unwrap();
+
+        let summary = client.state_summary();
+        assert_eq!(summary.finalized_header.slot, client.slot());
+        assert_eq!(summary.optimistic_header.slot, client.optimistic_slot());
+        assert_eq!(summary.period, 1230);
+        assert_eq!(summary.participation, client.participation());
+        assert!(summary.participation.is_some());
+        assert_eq!(
+            summary.current_committee_root.0,
+            client
+                .light_client_store()
+                .current_sync_committee
+                .tree_hash_root()
+                .0
+        );
+        assert_eq!(
+            summary.next_committee_root.map(|root| root.0),
+            client
+                .light_client_store()
+                .next_sync_committee
+                .as_ref()
+                .map(|sc| sc.tree_hash_root().0)
+        );
+
+        let json = serde_json::to_value(&summary).unwrap();
+        assert_eq!(json["period"], "1230");
+    }
+
+    #[test]
+    fn min_participants() {
+        let (bootstrap, updates, _) = fixtures();
+        let update = updates[0].clone();
+        let participants = update.sync_aggregate.participants();
+        assert!(participants < SYNC_COMMITTEE_SIZE);
+
+        let client = LightClient::default().with_min_participants(usize::MAX);
+        assert_eq!(client.min_participants, Some(SYNC_COMMITTEE_SIZE));
+
+        let network = Network::ethereum_mainnet();
+        let mut client = LightClient::new(bootstrap.clone(), network.clone())
+            .unwrap()
+            .with_min_participants(participants + 1);
+        let slot = client.slot();
+        let err = client.update(update.clone(), 1727740110).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::LightClient(LightClientError::InsufficientParticipation {
+                committee_size: SYNC_COMMITTEE_SIZE,
+                ..
+            })
+        ));
+        assert_eq!(client.slot(), slot);
+
+        let mut client = LightClient::new(bootstrap, network)
+            .unwrap()
+            .with_min_participants(participants);
+        client.update(update, 1727740110).unwrap();
+        assert!(client.slot() > slot);
+    }
+
+    #[test]
+    fn future_updates() {
+        let (bootstrap, updates, _) = fixtures();
+        let update = updates[0].clone();
+        let network = Network::ethereum_mainnet();
+        let slot_start = |slot: u64| network.genesis_time + slot * SECONDS_PER_SLOT;
+
+        let mut client = LightClient::new(bootstrap, network.clone()).unwrap();
+        let slot = client.slot();
+        let now = slot_start(update.signature_slot - MAX_FUTURE_SLOTS - 1);
+        let err = client.update(update.clone(), now).unwrap_err();
+        assert!(err.to_string().contains("update from the future"));
+        assert!(matches!(err, Error::EthereumConsensus(_)));
+        assert_eq!(client.slot(), slot);
+
+        // a signature slot slightly ahead of the local clock is not yet valid,
+        // but isn't rejected as from the future
+        let now = slot_start(update.signature_slot - MAX_FUTURE_SLOTS);
+        let err = client.update(update.clone(), now).unwrap_err();
+        assert!(matches!(err, Error::LightClient(_)));
+        assert_eq!(client.slot(), slot);
+
+        let now = slot_start(update.signature_slot);
+        client.update(update, now).unwrap();
+        assert!(client.slot() > slot);
+    }
+
+    #[test]
+    fn best_updates() {
+        let (bootstrap, updates, _) = fixtures();
+        let update = updates[0].clone();
+        let mut client = LightClient::new(bootstrap, Network::ethereum_mainnet()).unwrap();
+        let period = period_of_slot(client.slot());
+        assert_eq!(period_of_slot(update.signature_slot), period);
+
+        // nothing to advance with yet
+        assert!(client.force_advance(1727740110).is_err());
+
+        // an update with fewer participants does not replace a better one
+        let mut worse = update.clone();
+        let bits = &mut worse.sync_aggregate.sync_committee_bits;
+        let set = (0..SYNC_COMMITTEE_SIZE)
+            .find(|i| bits.get(*i).unwrap())
+            .unwrap();
+        bits.set(set, false).unwrap();
+        assert!(client
+            .insert_best_update(update.clone(), 1727740110)
+            .unwrap());
+        assert!(!client
+            .insert_best_update(worse.clone(), 1727740110)
+            .unwrap());
+        assert!(!client
+            .insert_best_update(update.clone(), 1727740110)
+            .unwrap());
+
+        // a forged update claiming full participation fails verification
+        // rather than displacing the real one
+        let mut forged = update.clone();
+        let bits = &mut forged.sync_aggregate.sync_committee_bits;
+        for i in 0..SYNC_COMMITTEE_SIZE {
+            bits.set(i, true).unwrap();
+        }
+        assert!(forged.sync_aggregate.participants() > update.sync_aggregate.participants());
+        assert!(client.insert_best_update(forged, 1727740110).is_err());
+        assert_eq!(
+            client.best_updates[&period].sync_aggregate.participants(),
+            update.sync_aggregate.participants()
+        );
+
+        // updates which can't be verified against the store are not kept
+        let mut later = update.clone();
+        later.signature_slot += 2 * slots_per_period();
+        assert!(client.insert_best_update(later, 1727740110).is_err());
+        assert_eq!(client.best_updates.len(), 1);
+
+        let mut other = client.clone();
+        other.best_updates.clear();
+        assert!(other.keep_best_update(worse));
+        assert!(other.keep_best_update(update.clone()));
+        assert_eq!(
+            other.best_updates[&period].sync_aggregate.participants(),
+            update.sync_aggregate.participants()
+        );
+
+        // only the latest periods are kept
+        for i in 1..=MAX_BEST_UPDATES as u64 {
+            let mut later = update.clone();
+            later.signature_slot += i * slots_per_period();
+            assert!(other.keep_best_update(later));
+        }
+        assert_eq!(other.best_updates.len(), MAX_BEST_UPDATES);
+        assert!(!other.best_updates.contains_key(&period));
+
+        // the kept update is applied to advance the client
+        let slot = client.slot();
+        let res = client.force_advance(1727740110).unwrap();
+        assert!(res.new_finalized_slot > slot);
+        assert!(client.lcs.next_sync_committee.is_some());
+        assert!(!client.best_updates.contains_key(&period));
+    }
+
+    #[test]
+    fn update_branch_length_limits() {
+        let (bootstrap, updates, _) = fixtures();
+        let update = updates[0].clone();
+        update.check_branch_lengths().unwrap();
+
+        let bytes = update.encode().unwrap();
+        Update::decode(bytes.as_slice()).unwrap();
+
+        let mut long_finality = update.clone();
+        long_finality.finality_branch = vec![Bytes32::default(); 200].try_into().unwrap();
+        let bytes = long_finality.encode().unwrap();
+        let err = Update::decode(bytes.as_slice()).unwrap_err();
+        assert!(err.to_string().contains("Finality branch has 200 nodes"));
+
+        let mut long_committee = update.clone();
+        long_committee.next_sync_committee_branch =
+            Some(vec![Bytes32::default(); 7].try_into().unwrap());
+        let bytes = long_committee.encode().unwrap();
+        let err = Update::decode(bytes.as_slice()).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Next sync committee branch has 7 nodes"));
+
+        // Electra's branches are one node deeper than Deneb's
+        let mut electra = update.clone();
+        electra.finality_branch = vec![Bytes32::default(); 7].try_into().unwrap();
+        electra.next_sync_committee_branch = Some(vec![Bytes32::default(); 6].try_into().unwrap());
+        electra.check_branch_lengths().unwrap();
+
+        // updates from the JSON API are checked before verification
+        let mut client = LightClient::new(bootstrap, Network::ethereum_mainnet()).unwrap();
+        let err = client.update(long_finality, 1727740110).unwrap_err();
+        assert!(err.to_string().contains("Finality branch"));
+        client.update(update, 1727740110).unwrap();
+    }
+
+    #[test]
+    fn zeroed_next_sync_committee() {
+        let (bootstrap, updates, finality_update) = fixtures();
+
+        let mut client = LightClient::new(bootstrap, Network::ethereum_mainnet()).unwrap();
+        for update in updates {
+            client.update(update, 1727740110).unwrap();
+        }
+
+        let mut update = finality_update;
+        assert!(!update.has_empty_next_sync_committee());
+        update.next_sync_committee = Some(SyncCommittee::default());
+        assert!(update.has_empty_next_sync_committee());
+        update.next_sync_committee_branch = Some(vec![Bytes32([0; 32]); 5].try_into().unwrap());
+        assert!(update.has_empty_next_sync_committee());
+
+        let res = client.update(update, 1727740110).unwrap();
+        assert_eq!(res.new_finalized_slot, 10076224);
+        assert!(!res.committee_rotated);
+    }
+
+    #[test]
+    fn optimistic_header_and_age() {
+        let mut client = LightClient::default();
+        client.network = Network::ethereum_mainnet();
+        client.lcs.finalized_header.slot = 100;
+        client.lcs.optimistic_header.slot = 164;
+        client.lcs.optimistic_header.state_root.0 = [7; 32];
+
+        assert_eq!(client.optimistic_slot(), 164);
+        assert_eq!(client.optimistic_state_root().0, [7; 32]);
+
+        let slot_time = client.network.genesis_time + 100 * SECONDS_PER_SLOT;
+        assert_eq!(client.age_seconds(slot_time + 30), 30);
+        assert_eq!(client.age_seconds(slot_time - 1), 0);
+    }
+
+    #[test]
+    fn to_bootstrap() {
+        let (bootstrap, updates, _) = fixtures();
+
+        let mut client = LightClient::new(bootstrap, Network::ethereum_mainnet()).unwrap();
+        let served = client.to_bootstrap().unwrap();
+        let peer = LightClient::new(served, Network::ethereum_mainnet()).unwrap();
+        assert_eq!(peer.encode().unwrap(), client.encode().unwrap());
+
+        client.update(updates[0].clone(), 1727740110).unwrap();
+        assert!(client.to_bootstrap().is_err());
+
+        let decoded = LightClient::decode(peer.encode().unwrap().as_slice()).unwrap();
+        assert!(decoded.to_bootstrap().is_err());
+    }
+
+    #[test]
+    fn field_query() {
+        let (bootstrap, _, _) = fixtures();
+        let aggregate_pubkey = bootstrap
+            .current_sync_committee
+            .aggregate_pubkey
+            .as_ssz_bytes();
+        let mut client = LightClient::new(bootstrap, Network::ethereum_mainnet()).unwrap();
+        client.lcs.finalized_header.slot = 123;
+        client.lcs.optimistic_header.slot = 456;
+
+        for query in [
+            LightClientQuery::FinalizedHeader,
+            LightClientQuery::OptimisticHeader,
+            LightClientQuery::CurrentAggregatePubkey,
+            LightClientQuery::Slot,
+        ] {
+            client.field_query(query).unwrap();
+            let bytes = query.encode().unwrap();
+            assert_eq!(LightClientQuery::decode(bytes.as_slice()).unwrap(), query);
+        }
+
+        match client.query_field(LightClientQuery::FinalizedHeader) {
+            LightClientField::FinalizedHeader(header) => {
+                assert_eq!(header.slot, 123);
+                assert_eq!(header.state_root, client.lcs.finalized_header.state_root);
+            }
+            field => panic!("Unexpected field {:?}", field),
+        }
+        match client.query_field(LightClientQuery::OptimisticHeader) {
+            LightClientField::OptimisticHeader(header) => assert_eq!(header.slot, 456),
+            field => panic!("Unexpected field {:?}", field),
+        }
+        match client.query_field(LightClientQuery::CurrentAggregatePubkey) {
+            LightClientField::CurrentAggregatePubkey(pubkey) => {
+                assert_eq!(pubkey.as_ssz_bytes(), aggregate_pubkey);
+            }
+            field => panic!("Unexpected field {:?}", field),
+        }
+        match client.query_field(LightClientQuery::Slot) {
+            LightClientField::Slot(slot) => assert_eq!(slot, 123),
+            field => panic!("Unexpected field {:?}", field),
+        }
+    }
 }
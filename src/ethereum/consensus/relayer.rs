@@ -1,13 +1,23 @@
+use std::future::Future;
+use std::net::SocketAddr;
+
 use ed::{Decode, Encode};
+use log::info;
 use orga::{client::Client as OrgaClient, encoding::LengthVec};
 use reqwest::get;
 use serde::{Deserialize, Serialize};
 
 use super::{
-    encode_sync_aggregate, Bootstrap, Bytes32, Header, LightClient, SyncAggregate, SyncCommittee,
-    Update,
+    encode_sync_aggregate, epoch_of_slot, period_of_slot, Bootstrap, Bytes32, Header, LightClient,
+    SyncAggregate, SyncCommittee, Update,
+};
+use crate::{
+    app,
+    babylon::proto::FinalityProvider,
+    bitcoin::relayer::handle_rejection,
+    error::{Error, Result},
+    utils::time_now,
 };
-use crate::{app, babylon::proto::FinalityProvider, error::Result};
 
 use super::{encode_header, encode_sync_committee};
 
@@ -19,11 +29,11 @@ pub async fn get_updates<C: OrgaClient<LightClient>>(
 
     let finality_update = eth_client.get_finality_update().await?.data;
 
-    let app_epoch = lc.slot() / 32;
-    let eth_epoch = finality_update.finalized_header.slot / 32;
+    let app_epoch = epoch_of_slot(lc.slot());
+    let eth_epoch = epoch_of_slot(finality_update.finalized_header.slot);
 
-    let app_period = app_epoch / 256;
-    let eth_period = eth_epoch / 256;
+    let app_period = period_of_slot(lc.slot());
+    let eth_period = period_of_slot(finality_update.finalized_header.slot);
 
     let mut updates = vec![];
 
@@ -44,6 +54,33 @@ pub async fn get_updates<C: OrgaClient<LightClient>>(
     Ok(updates)
 }
 
+/// Serves the light client's [EthereumRelayerStatus] as JSON at `GET /status`
+/// on `addr`, for monitoring systems to check that updates are being applied.
+pub fn serve_status<C>(app_client: C, addr: SocketAddr) -> impl Future<Output = ()>
+where
+    C: OrgaClient<LightClient> + Clone + Send + Sync + 'static,
+{
+    use warp::Filter;
+
+    let status_route = warp::get()
+        .and(warp::path("status"))
+        .and_then(move || {
+            let app_client = app_client.clone();
+            async move {
+                let lc = app_client
+                    .query(Ok)
+                    .await
+                    .map_err(|e| warp::reject::custom(Error::from(e)))?;
+                let status = EthereumRelayerStatus::new(&lc, time_now());
+                Ok::<_, warp::Rejection>(warp::reply::json(&status))
+            }
+        })
+        .recover(handle_rejection);
+
+    info!("Serving Ethereum relayer status at http://{}/status", addr);
+    warp::serve(status_route).run(addr)
+}
+
 pub struct RpcClient {
     rpc_addr: String,
 }
@@ -103,6 +140,37 @@ impl RpcClient {
     }
 }
 
+/// A summary of the light client's sync state, for monitoring the relayer.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EthereumRelayerStatus {
+    /// The slot of the light client's finalized header.
+    pub finalized_slot: u64,
+    /// How many slots the finalized header is behind the current slot.
+    pub slots_behind: u64,
+    /// The sync committee period of the finalized header.
+    pub period: u64,
+    /// The time, in seconds, at which the light client last applied an
+    /// update, if known.
+    pub last_update_applied_at: Option<u64>,
+    /// The fraction of the sync committee which signed the update for the
+    /// finalized header, if known.
+    pub participation: Option<f64>,
+}
+
+impl EthereumRelayerStatus {
+    pub fn new(lc: &LightClient, now_seconds: u64) -> Self {
+        let finalized_slot = lc.slot();
+
+        Self {
+            finalized_slot,
+            slots_behind: lc.current_slot(now_seconds).saturating_sub(finalized_slot),
+            period: period_of_slot(finalized_slot),
+            last_update_applied_at: lc.last_update_at(),
+            participation: lc.participation(),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Response<T> {
     pub version: Option<String>,
@@ -112,6 +180,39 @@ pub struct Response<T> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ethereum::consensus::{tests::fixtures, Network};
+
+    #[test]
+    fn relayer_status() {
+        let (bootstrap, updates, finality_update) = fixtures();
+
+        let mut lc = LightClient::new(bootstrap, Network::ethereum_mainnet()).unwrap();
+        let now = 1727740110;
+        let status = EthereumRelayerStatus::new(&lc, now);
+        assert_eq!(status.finalized_slot, 10051584);
+        assert_eq!(status.period, 1227);
+        assert_eq!(status.last_update_applied_at, None);
+        assert_eq!(status.participation, None);
+
+        for update in updates {
+            lc.update(update, now).unwrap();
+        }
+        let participants = finality_update.sync_aggregate.participants();
+        lc.update(finality_update, now).unwrap();
+
+        // the status only depends on state which survives being stored
+        let lc = LightClient::decode(lc.encode().unwrap().as_slice()).unwrap();
+        let status = EthereumRelayerStatus::new(&lc, now);
+        assert_eq!(status.finalized_slot, 10076224);
+        assert_eq!(status.period, 1230);
+        assert_eq!(status.slots_behind, lc.current_slot(now) - 10076224);
+        assert_eq!(status.last_update_applied_at, Some(now));
+        assert_eq!(status.participation, Some(participants as f64 / 512.0));
+        assert!(participants > 512 * 2 / 3);
+
+        let later = EthereumRelayerStatus::new(&lc, now + 120);
+        assert_eq!(later.slots_behind, status.slots_behind + 10);
+    }
 
     #[tokio::test]
     async fn get_updates() {
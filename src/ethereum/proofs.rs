@@ -208,6 +208,37 @@ fn verify_key(root: [u8; 32], key: &[u8], proof: &EncodedProof) -> AppResult<Vec
     Ok(result)
 }
 
+/// Verifies a Merkle-Patricia trie proof that `receipt` is the encoded
+/// receipt at `index` in the receipts trie with root `receipts_root`. The
+/// receipt is given as stored in the trie, i.e. the RLP encoding, prefixed
+/// with the transaction type for typed receipts.
+pub fn verify_receipt_proof(
+    receipts_root: [u8; 32],
+    index: u64,
+    receipt: &[u8],
+    proof: &[Vec<u8>],
+) -> AppResult<()> {
+    let root = H256(receipts_root);
+    let db = StorageProof::new(proof.to_vec()).into_memory_db::<KeccakHasher>();
+    let trie = TrieDBBuilder::<EIP1186Layout<KeccakHasher>>::new(&db, &root).build();
+
+    let key = rlp::encode(&index);
+    let value = trie
+        .get(&key)
+        .map_err(|e| Error::EthereumConsensus(format!("Invalid receipt proof: {}", e)))?
+        .ok_or_else(|| {
+            Error::EthereumConsensus(format!("No receipt at index {} in proof", index))
+        })?;
+    if value != receipt {
+        return Err(Error::EthereumConsensus(format!(
+            "Receipt at index {} does not match proof",
+            index
+        )));
+    }
+
+    Ok(())
+}
+
 /// Data proven by a [StateProof].
 #[derive(Debug, Clone)]
 pub struct BridgeContractData {
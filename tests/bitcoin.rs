@@ -20,6 +20,7 @@ use nomic::bitcoin::deposit_index::DepositIndex;
 use nomic::bitcoin::deposit_index::{Deposit, DepositInfo};
 use nomic::bitcoin::header_queue::Config as HeaderQueueConfig;
 use nomic::bitcoin::relayer::DepositAddress;
+use nomic::bitcoin::relayer::DepositRelayOutcome;
 use nomic::bitcoin::relayer::Relayer;
 use nomic::bitcoin::signer::Signer;
 use nomic::bitcoin::threshold_sig::Pubkey;
@@ -138,7 +139,7 @@ async fn deposit_bitcoin(
     address: &Address,
     btc: bitcoin::Amount,
     wallet: &bitcoind::bitcoincore_rpc::Client,
-) -> Result<()> {
+) -> Result<(bitcoin::Txid, u32)> {
     let deposit_address = generate_deposit_address(address).await.unwrap();
     broadcast_deposit_addr(
         address.to_string(),
@@ -148,20 +149,19 @@ async fn deposit_bitcoin(
     )
     .await?;
 
-    wallet
-        .send_to_address(
-            &bitcoin::Address::from_str(&deposit_address.deposit_addr).unwrap(),
-            btc,
-            None,
-            None,
-            None,
-            None,
-            None,
-            None,
-        )
+    let deposit_addr = bitcoin::Address::from_str(&deposit_address.deposit_addr).unwrap();
+    let txid = wallet
+        .send_to_address(&deposit_addr, btc, None, None, None, None, None, None)
         .unwrap();
 
-    Ok(())
+    let tx = wallet.get_raw_transaction(&txid, None).unwrap();
+    let vout = tx
+        .output
+        .iter()
+        .position(|output| output.script_pubkey == deposit_addr.script_pubkey())
+        .unwrap();
+
+    Ok((txid, vout as u32))
 }
 
 async fn withdraw_bitcoin(
@@ -403,7 +403,7 @@ async fn bitcoin_test() {
         poll_for_active_sigset().await;
         poll_for_signatory_key(consensus_key).await;
 
-        deposit_bitcoin(
+        let (deposit_txid, deposit_vout) = deposit_bitcoin(
             &funded_accounts[0].address,
             bitcoin::Amount::from_btc(10.0).unwrap(),
             &wallet,
@@ -441,6 +441,15 @@ async fn bitcoin_test() {
         let balance = poll_for_updated_balance(funded_accounts[0].address, expected_balance).await;
         assert_eq!(balance, Amount::from(expected_balance));
 
+        let outcome = Relayer::new(
+            test_bitcoin_client(rpc_url.clone(), cookie_file.clone()).await,
+            rpc_addr.clone(),
+        )
+        .wait_for_deposit(deposit_txid, deposit_vout, Duration::from_secs(60))
+        .await
+        .unwrap();
+        assert_eq!(outcome, DepositRelayOutcome::Relayed);
+
         btc_client
             .generate_to_address(3, &async_wallet_address)
             .await